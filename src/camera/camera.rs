@@ -18,6 +18,10 @@ pub struct CameraUniform {
 
   /// Contains the view projection matrix.
   pub view_proj: [[f32; 4]; 4],
+
+  /// The inverse of the view projection matrix, for casting rays from the
+  /// eye through each pixel.
+  pub inverse_view_proj: [[f32; 4]; 4],
 }
 
 impl CameraUniform {
@@ -27,7 +31,9 @@ impl CameraUniform {
   /// * `camera`: The [OrbitCamera] from which the matrix will be computed.
   pub fn update_view_proj(&mut self, camera: &OrbitCamera) {
       self.view_position = [camera.eye.x, camera.eye.y, camera.eye.z, 1.0];
-      self.view_proj = camera.build_view_projection_matrix().to_cols_array_2d();
+      let view_proj = camera.build_view_projection_matrix();
+      self.view_proj = view_proj.to_cols_array_2d();
+      self.inverse_view_proj = view_proj.inverse().to_cols_array_2d();
   }
 }
 
@@ -37,6 +43,7 @@ impl Default for CameraUniform {
       Self {
           view_position: [0.0; 4],
           view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+          inverse_view_proj: Mat4::IDENTITY.to_cols_array_2d(),
       }
   }
 }
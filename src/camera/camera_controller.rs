@@ -17,6 +17,7 @@ impl CameraController {
     }
   }
 
+  #[allow(clippy::single_match, clippy::collapsible_match)]
   pub fn process_events(
     &mut self,
     event: &Event<()>,
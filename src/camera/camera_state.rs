@@ -1,7 +1,10 @@
-use game_loop::winit::window::Window;
+use cgmath::Vector3;
+use game_loop::winit::dpi::PhysicalSize;
 use glam::Vec3;
 use wgpu::{Buffer, BindGroupLayout, Device, util::DeviceExt, BindGroup};
 
+use crate::flicker_scheduler::ViewPoint;
+
 use super::{camera::{CameraUniform}, orbit_camera::OrbitCamera, camera_controller::CameraController};
 
 pub struct CameraState {
@@ -14,9 +17,7 @@ pub struct CameraState {
 }
 
 impl CameraState {
-  pub fn new(device: &Device, window: &Window) -> Self {
-
-    let size = window.inner_size();
+  pub fn new(device: &Device, size: PhysicalSize<u32>) -> Self {
 
     let mut camera = OrbitCamera::new(
       2.0,
@@ -74,4 +75,12 @@ impl CameraState {
       camera_bind_group       ,
     };
   }
+
+  /// Where the camera is looking from and towards, in world space.
+  pub fn view_point(&self) -> ViewPoint {
+    return ViewPoint {
+      eye   : Vector3::new(self.camera.eye.x, self.camera.eye.y, self.camera.eye.z),
+      target: Vector3::new(self.camera.target.x, self.camera.target.y, self.camera.target.z),
+    };
+  }
 }
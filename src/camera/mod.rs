@@ -1,6 +1,8 @@
 
 mod camera_controller;
+#[allow(clippy::module_inception)]
 mod camera;
 
 pub mod orbit_camera;
 pub mod camera_state;
+pub mod stereo_camera;
@@ -0,0 +1,213 @@
+use game_loop::winit::dpi::PhysicalSize;
+use glam::{Mat4, Vec3};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue, util::DeviceExt};
+
+use super::{camera::CameraUniform, orbit_camera::OrbitCamera};
+
+/// How the two eyes of a [StereoCamera] are laid out in the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoMode {
+  /// A single view from the [OrbitCamera] itself.
+  Off,
+
+  /// The left eye on the left half of the frame, the right eye on the right.
+  SideBySide,
+
+  /// The left eye on the top half of the frame, the right eye on the bottom.
+  OverUnder,
+
+  /// Both eyes over the whole frame, the left in red and the right in cyan,
+  /// for red/cyan glasses.
+  Anaglyph,
+}
+
+/// One of the eyes of a [StereoCamera].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoEye {
+  Left,
+  Right,
+}
+
+/// A pair of eyes either side of an [OrbitCamera], looking parallel to it.
+///
+/// The eyes' frustums are shifted so their views line up at the convergence
+/// distance: things there appear at the screen, nearer things in front of it
+/// and further things behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StereoCamera {
+  /// The interpupillary distance: how far apart the eyes are.
+  pub ipd: f32,
+
+  /// The distance from the eyes at which both views line up.
+  pub convergence: f32,
+}
+
+/// The camera uniforms for both eyes of a [StereoCamera], bound like the
+/// main camera's.
+pub struct StereoCameraState {
+  pub stereo_camera: StereoCamera,
+  pub mode         : StereoMode,
+  eye_buffers      : [Buffer; 2],
+  eye_bind_groups  : [BindGroup; 2],
+}
+
+impl StereoMode {
+  /// Steps to the next mode, coming back around to [StereoMode::Off].
+  pub fn next(&self) -> Self {
+    match self {
+      StereoMode::Off => return StereoMode::SideBySide,
+      StereoMode::SideBySide => return StereoMode::OverUnder,
+      StereoMode::OverUnder => return StereoMode::Anaglyph,
+      StereoMode::Anaglyph => return StereoMode::Off,
+    }
+  }
+
+  /// The part of the frame an eye is drawn into, as `[x, y, width, height]`
+  /// in pixels.
+  ///
+  /// Arguments:
+  ///
+  /// * `eye`: The eye to place.
+  /// * `size`: The size of the whole frame.
+  pub fn viewport(&self, eye: StereoEye, size: PhysicalSize<u32>) -> [f32; 4] {
+    let width = size.width as f32;
+    let height = size.height as f32;
+    let second = (eye == StereoEye::Right) as u32 as f32;
+
+    match self {
+      StereoMode::Off | StereoMode::Anaglyph => return [0.0, 0.0, width, height],
+      StereoMode::SideBySide => return [second * width / 2.0, 0.0, width / 2.0, height],
+      StereoMode::OverUnder => return [0.0, second * height / 2.0, width, height / 2.0],
+    }
+  }
+}
+
+impl StereoEye {
+  pub const ALL: [StereoEye; 2] = [StereoEye::Left, StereoEye::Right];
+}
+
+impl Default for StereoCamera {
+  /// Eyes a twentieth of the camera's default distance apart, converging on
+  /// its target.
+  fn default() -> Self {
+    return Self {
+      ipd        : 0.1,
+      convergence: 2.0,
+    };
+  }
+}
+
+impl StereoCamera {
+  /// How far an eye is from the camera along its right vector.
+  pub fn eye_offset(&self, eye: StereoEye) -> f32 {
+    match eye {
+      StereoEye::Left => return -self.ipd / 2.0,
+      StereoEye::Right => return self.ipd / 2.0,
+    }
+  }
+
+  /// The position of an eye in world space.
+  ///
+  /// Arguments:
+  ///
+  /// * `camera`: The camera the eyes are either side of.
+  /// * `eye`: The eye to place.
+  pub fn eye_position(&self, camera: &OrbitCamera, eye: StereoEye) -> Vec3 {
+    return camera.eye.to_vec3() + right_vector(camera) * self.eye_offset(eye);
+  }
+
+  /// Builds the view projection matrix of an eye.
+  ///
+  /// Arguments:
+  ///
+  /// * `camera`: The camera the eyes are either side of.
+  /// * `eye`: The eye to build the matrix for.
+  pub fn build_view_projection_matrix(&self, camera: &OrbitCamera, eye: StereoEye) -> Mat4 {
+    let shift = right_vector(camera) * self.eye_offset(eye);
+    let view = Mat4::look_at_rh(
+      camera.eye.to_vec3() + shift,
+      camera.target.to_vec3() + shift,
+      camera.up.to_vec3(),
+    );
+
+    // Shears the frustum sideways, so the point straight ahead of the camera
+    // at the convergence distance lands in the middle of both views
+    let mut proj = Mat4::perspective_rh(
+      camera.fovy,
+      camera.aspect,
+      camera.znear,
+      camera.zfar,
+    );
+    proj.z_axis.x -= proj.x_axis.x * self.eye_offset(eye) / self.convergence.max(f32::EPSILON);
+
+    return proj * view;
+  }
+
+  /// Builds the camera uniform of an eye.
+  pub fn uniform(&self, camera: &OrbitCamera, eye: StereoEye) -> CameraUniform {
+    let position = self.eye_position(camera, eye);
+    let view_proj = self.build_view_projection_matrix(camera, eye);
+    return CameraUniform {
+      view_position    : [position.x, position.y, position.z, 1.0],
+      view_proj        : view_proj.to_cols_array_2d(),
+      inverse_view_proj: view_proj.inverse().to_cols_array_2d(),
+    };
+  }
+}
+
+impl StereoCameraState {
+  /// Creates the eyes' uniform buffers, bound with the main camera's layout
+  /// so they can be used with the same pipelines.
+  pub fn new(device: &Device, camera_bind_group_layout: &BindGroupLayout) -> Self {
+    let eye_buffers = StereoEye::ALL.map(|_| {
+      return device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Stereo eye camera Buffer"),
+        contents: bytemuck::cast_slice(&[CameraUniform::default()]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+    });
+
+    let eye_bind_groups = std::array::from_fn(|i| {
+      return device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: camera_bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+          binding: 0,
+          resource: eye_buffers[i].as_entire_binding(),
+        }],
+        label: Some("stereo_eye_camera_bind_group"),
+      });
+    });
+
+    return Self {
+      stereo_camera: StereoCamera::default(),
+      mode         : StereoMode::Off,
+      eye_buffers,
+      eye_bind_groups,
+    };
+  }
+
+  /// Moves both eyes along with the camera.
+  pub fn update(&self, queue: &Queue, camera: &OrbitCamera) {
+    for (i, eye) in StereoEye::ALL.iter().enumerate() {
+      queue.write_buffer(
+        &self.eye_buffers[i],
+        0,
+        bytemuck::cast_slice(&[self.stereo_camera.uniform(camera, *eye)]),
+      );
+    }
+  }
+
+  /// The bind group holding an eye's camera uniform.
+  pub fn bind_group(&self, eye: StereoEye) -> &BindGroup {
+    match eye {
+      StereoEye::Left => return &self.eye_bind_groups[0],
+      StereoEye::Right => return &self.eye_bind_groups[1],
+    }
+  }
+}
+
+/// The direction to the camera's right, which the eyes are spread along.
+fn right_vector(camera: &OrbitCamera) -> Vec3 {
+  let forward = camera.target.to_vec3() - camera.eye.to_vec3();
+  return forward.cross(camera.up.to_vec3()).normalize_or_zero();
+}
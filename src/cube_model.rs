@@ -2,6 +2,11 @@ use wgpu::{Buffer, Device, util::{DeviceExt, self}, BufferUsages};
 
 use crate::render_pipeline_state::Vertex;
 
+/**
+ * The edge length of the cube model
+ */
+pub const CUBE_SIZE: f32 = 0.2;
+
 const CUBE_VERTICES: &[Vertex] = &[
   Vertex { position: [-0.1, -0.1, 0.1 ], color: [0.0, 0.0, 0.0] }, // A: 0
   Vertex { position: [0.1 , -0.1, 0.1 ], color: [0.0, 0.0, 0.5] }, // B: 1
@@ -0,0 +1,464 @@
+//! An egui panel for tuning the renderer while it runs, instead of editing
+//! constants and recompiling.
+//!
+//! Window events reach egui through egui-winit, and the panel is painted
+//! over the frame by egui-wgpu's renderer. The panel edits a
+//! [DebugSettings] read from the [GameState] each frame, and whatever it
+//! changed is applied back.
+
+use std::path::PathBuf;
+
+use egui::{ClippedPrimitive, TexturesDelta};
+use game_loop::winit::{
+  event::{DeviceEvent, ElementState, Event},
+  window::Window,
+};
+use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
+
+use crate::{
+  camera::stereo_camera::StereoMode,
+  flicker::{FlickerConfig, SliceAxis, SweepDirection, VoxelOrder},
+  flicker_clock::MIN_VOLUMES_PER_SECOND,
+  game_state::GameState,
+  render_pipeline_state::{RenderBackend, RenderMode},
+  transfer_function::{TransferFunction, TransferFunctionPreset},
+  voxel_volume::VoxelVolume,
+};
+
+/**
+ * Everything the debug panel can change
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugSettings {
+  /// The voxels along each axis. Changing them replaces the volume with a
+  /// gradient grid of that size
+  pub volume_dimensions: [u32; 3],
+
+  /// The distance between voxel centres. Changing it places the volume
+  /// again with [VoxelVolume::centered_origin]
+  pub voxel_spacing    : f32,
+
+  pub voxel_flicker     : bool,
+  pub volumes_per_second: f32,
+  pub flicker           : FlickerConfig,
+
+  pub rotate_speed: f32,
+  pub zoom_speed  : f32,
+
+  /// The nearest and furthest the camera can be from its target, with no
+  /// furthest limit when None
+  pub min_distance: f32,
+  pub max_distance: Option<f32>,
+
+  pub render_mode             : RenderMode,
+  pub render_backend          : RenderBackend,
+  pub stereo_mode             : StereoMode,
+  pub transfer_function_preset: Option<TransferFunctionPreset>,
+
+  /// The control points voxels are coloured through, which the panel can
+  /// add, move, recolour and remove
+  pub transfer_function: Option<TransferFunction>,
+
+  /// Set by the panel's save button, to save the transfer function to a new
+  /// file in the working directory
+  pub save_transfer_function: bool,
+
+  /// The colour the frame is cleared to behind the voxels, as RGB
+  pub background_color: [f32; 3],
+  pub show_hud        : bool,
+}
+
+impl DebugSettings {
+
+  /**
+   * The current values of everything the debug panel can change
+   */
+  pub fn from_game_state(game_state: &GameState) -> Self {
+    let camera = &game_state.camera_state().camera;
+    let controller = &game_state.camera_state().camera_controller;
+    let background_color = game_state.background_color();
+
+    return Self {
+      volume_dimensions       : game_state.volume().dimensions,
+      voxel_spacing           : game_state.volume().spacing,
+      voxel_flicker           : game_state.voxel_flicker(),
+      volumes_per_second      : game_state.flicker_clock().volumes_per_second(),
+      flicker                 : game_state.flicker_config(),
+      rotate_speed            : controller.rotate_speed,
+      zoom_speed              : controller.zoom_speed,
+      min_distance            : camera.bounds.min_distance.unwrap_or(f32::EPSILON),
+      max_distance            : camera.bounds.max_distance,
+      render_mode             : game_state.render_mode(),
+      render_backend          : game_state.render_backend(),
+      stereo_mode             : game_state.stereo_mode(),
+      transfer_function_preset: game_state.transfer_function_preset(),
+      transfer_function       : game_state.transfer_function().cloned(),
+      save_transfer_function  : false,
+      background_color        : [background_color.r, background_color.g, background_color.b].map(|c| c as f32),
+      show_hud                : game_state.show_hud(),
+    };
+  }
+
+  /**
+   * Applies the settings which differ from `previous` to `game_state`, as
+   * the panel changed them
+   */
+  pub fn apply(&self, previous: &DebugSettings, game_state: &mut GameState) {
+    if self.volume_dimensions != previous.volume_dimensions {
+      game_state.set_volume(VoxelVolume::gradient(self.volume_dimensions, self.voxel_spacing));
+    } else if self.voxel_spacing != previous.voxel_spacing {
+      let mut volume = game_state.volume().clone();
+      volume.spacing = self.voxel_spacing;
+      volume.origin = VoxelVolume::centered_origin(volume.dimensions, volume.spacing);
+      game_state.set_volume(volume);
+    }
+
+    if self.voxel_flicker != previous.voxel_flicker {
+      game_state.set_voxel_flicker(self.voxel_flicker);
+    }
+    if self.volumes_per_second != previous.volumes_per_second {
+      game_state.set_volumes_per_second(self.volumes_per_second);
+    }
+    if self.flicker != previous.flicker {
+      game_state.set_flicker_config(self.flicker);
+    }
+
+    if self.rotate_speed != previous.rotate_speed || self.zoom_speed != previous.zoom_speed {
+      game_state.set_camera_speeds(self.rotate_speed, self.zoom_speed);
+    }
+    if self.min_distance != previous.min_distance || self.max_distance != previous.max_distance {
+      game_state.set_camera_distance_bounds(Some(self.min_distance), self.max_distance);
+    }
+
+    if self.render_mode != previous.render_mode {
+      game_state.set_render_mode(self.render_mode);
+    }
+    if self.render_backend != previous.render_backend {
+      game_state.set_render_backend(self.render_backend);
+    }
+    if self.stereo_mode != previous.stereo_mode {
+      game_state.set_stereo_mode(self.stereo_mode);
+    }
+    if self.transfer_function_preset != previous.transfer_function_preset {
+      game_state.set_transfer_function_preset(self.transfer_function_preset);
+    } else if self.transfer_function != previous.transfer_function {
+      game_state.set_transfer_function(self.transfer_function.clone());
+    }
+    if self.save_transfer_function {
+      game_state.save_transfer_function(&PathBuf::from(format!(
+        "transfer_function_{}.tf",
+        GameState::capture_timestamp(),
+      )));
+    }
+
+    let [r, g, b] = self.background_color.map(|c| c as f64);
+    game_state.set_background_color(wgpu::Color { r, g, b, a: 1.0 });
+    game_state.set_show_hud(self.show_hud);
+  }
+}
+
+/**
+ * Feeds window events to egui, runs the debug panel and paints it
+ */
+pub struct DebugUi {
+  context    : egui::Context,
+  winit_state: egui_winit::State,
+  renderer   : egui_wgpu::Renderer,
+
+  /// The input taken from the window for the next frame. Without a window
+  /// the next frame only gets the screen's size
+  window_input: Option<egui::RawInput>,
+
+  /// What the last frame asked of the window, eg. the cursor to show
+  platform_output: egui::PlatformOutput,
+
+  /// The last frame's shapes, and the textures to change before they're
+  /// painted
+  paint_jobs    : Vec<ClippedPrimitive>,
+  textures_delta: TexturesDelta,
+}
+
+impl DebugUi {
+
+  /**
+   * Creates a panel painted into frames of `format`
+   */
+  pub fn new(device: &Device, format: TextureFormat) -> Self {
+    // The game state is made from a window rather than the event loop, and
+    // egui-winit's clipboard, the only user of the Wayland display, is off
+    let winit_state = egui_winit::State::new_with_wayland_display(None);
+
+    return Self {
+      context: egui::Context::default(),
+      winit_state,
+      renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+      window_input: None,
+      platform_output: egui::PlatformOutput::default(),
+      paint_jobs: Vec::new(),
+      textures_delta: TexturesDelta::default(),
+    };
+  }
+
+  /**
+   * `pixels_per_point` is the window's scale factor
+   */
+  pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+    self.winit_state.set_pixels_per_point(pixels_per_point);
+  }
+
+  /**
+   * Whether the pointer is over the panel or dragging one of its widgets, so
+   * the camera should ignore it
+   */
+  pub fn wants_pointer_input(&self) -> bool {
+    return self.context.wants_pointer_input();
+  }
+
+  /**
+   * Passes `event` on to egui, returning whether the panel used it, in which
+   * case the rest of the renderer should ignore it
+   */
+  pub fn handle_event(&mut self, event: &Event<()>) -> bool {
+    match event {
+      Event::WindowEvent { event, .. } => {
+        return self.winit_state.on_event(&self.context, event).consumed;
+      },
+
+      // The camera is turned by raw mouse motion, which egui-winit doesn't
+      // see, so that is kept from it while the panel is being used.
+      // Releases always go through, so a drag started outside the panel can
+      // end over it
+      Event::DeviceEvent {
+        event: DeviceEvent::Button { state: ElementState::Pressed, .. }
+          | DeviceEvent::MouseMotion { .. }
+          | DeviceEvent::MouseWheel { .. },
+        ..
+      } => return self.wants_pointer_input(),
+
+      _ => return false,
+    }
+  }
+
+  /**
+   * Shows the last frame's cursor and copied text in `window`, and takes
+   * the input gathered from it for the next frame
+   */
+  pub fn sync_window(&mut self, window: &Window) {
+    let platform_output = std::mem::take(&mut self.platform_output);
+    self.winit_state.handle_platform_output(window, &self.context, platform_output);
+    self.window_input = Some(self.winit_state.take_egui_input(window));
+  }
+
+  /**
+   * Runs the panel for a frame of `screen_size` pixels, letting it edit
+   * `settings`, and returns the shapes to paint
+   */
+  pub fn run(&mut self, screen_size: [u32; 2], settings: &mut DebugSettings) -> &[ClippedPrimitive] {
+    let raw_input = match self.window_input.take() {
+      Some(raw_input) => raw_input,
+      None => {
+        let pixels_per_point = self.winit_state.pixels_per_point();
+        let [width, height] = screen_size.map(|size| size as f32 / pixels_per_point);
+        egui::RawInput {
+          screen_rect     : Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height))),
+          pixels_per_point: Some(pixels_per_point),
+          ..Default::default()
+        }
+      },
+    };
+
+    let full_output = self.context.run(raw_input, |context| show_panel(context, settings));
+    self.platform_output = full_output.platform_output;
+    self.textures_delta.append(full_output.textures_delta);
+    self.paint_jobs = self.context.tessellate(full_output.shapes);
+    return &self.paint_jobs;
+  }
+
+  /**
+   * Paints the last frame of the panel over `view`
+   */
+  pub fn encode(
+    &mut self,
+    device: &Device,
+    queue: &Queue,
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    screen_size: [u32; 2],
+  ) {
+    let screen_descriptor = egui_wgpu::renderer::ScreenDescriptor {
+      size_in_pixels  : screen_size,
+      pixels_per_point: self.winit_state.pixels_per_point(),
+    };
+
+    let textures_delta = std::mem::take(&mut self.textures_delta);
+    for (id, image_delta) in &textures_delta.set {
+      self.renderer.update_texture(device, queue, *id, image_delta);
+    }
+
+    // Only paint callbacks add command buffers of their own, and the panel
+    // has none
+    self.renderer.update_buffers(device, queue, encoder, &self.paint_jobs, &screen_descriptor);
+
+    {
+      let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Debug UI Pass"),
+
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+
+          ops: wgpu::Operations {
+            load : wgpu::LoadOp::Load,
+            store: true,
+          },
+        })],
+
+        depth_stencil_attachment: None,
+      });
+      self.renderer.render(&mut render_pass, &self.paint_jobs, &screen_descriptor);
+    }
+
+    for id in &textures_delta.free {
+      self.renderer.free_texture(id);
+    }
+  }
+}
+
+/**
+ * Every variant of an enum, from `first` through its `next()` method until
+ * it comes back around
+ */
+fn variants<T: Copy + PartialEq>(first: T, next: fn(&T) -> T) -> Vec<T> {
+  let mut variants = vec![first];
+  let mut variant = next(&first);
+  while variant != first {
+    variants.push(variant);
+    variant = next(&variant);
+  }
+  return variants;
+}
+
+fn combo_box<T: Copy + PartialEq + std::fmt::Debug>(ui: &mut egui::Ui, label: &str, value: &mut T, options: &[T]) {
+  egui::ComboBox::from_label(label)
+    .selected_text(format!("{:?}", value))
+    .show_ui(ui, |ui| {
+      for option in options {
+        ui.selectable_value(value, *option, format!("{:?}", option));
+      }
+    });
+}
+
+/**
+ * A row per control point of `transfer_function`, with its position, its
+ * colour and opacity, and a button removing it, or a button starting one
+ * from greyscale when there is none
+ */
+fn transfer_function_editor(ui: &mut egui::Ui, transfer_function: &mut Option<TransferFunction>) {
+  let Some(function) = transfer_function else {
+    if ui.button("New").clicked() {
+      *transfer_function = Some(TransferFunctionPreset::Greyscale.transfer_function());
+    }
+    return;
+  };
+
+  let points = function.points().to_vec();
+  let mut removed = None;
+  for (index, point) in points.iter().enumerate() {
+    // Each point is dragged only as far as its neighbours, so the rows keep
+    // their order
+    let min = index.checked_sub(1).map_or(0.0, |previous| points[previous].position).min(point.position);
+    let max = points.get(index + 1).map_or(1.0, |next| next.position).max(point.position);
+
+    let mut edited = *point;
+    ui.horizontal(|ui| {
+      ui.add(egui::DragValue::new(&mut edited.position).clamp_range(min..=max).speed(0.005));
+      ui.color_edit_button_rgba_unmultiplied(&mut edited.color);
+      if ui.add_enabled(points.len() > 1, egui::Button::new("Remove")).clicked() {
+        removed = Some(index);
+      }
+    });
+    if edited != *point {
+      function.set_point(index, edited);
+    }
+  }
+
+  if let Some(index) = removed {
+    function.remove_point(index);
+  }
+  if ui.button("Add point").clicked() {
+    function.add_point(function.new_point());
+  }
+}
+
+/**
+ * Lays out the panel's widgets, editing `settings`
+ */
+pub fn show_panel(context: &egui::Context, settings: &mut DebugSettings) {
+  // Opposite the HUD, which is in the top left corner
+  let window = egui::Window::new("Debug")
+    .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+    .default_width(260.0);
+
+  window.show(context, |ui| {
+    ui.collapsing("Volume", |ui| {
+      ui.horizontal(|ui| {
+        for dimension in &mut settings.volume_dimensions {
+          ui.add(egui::DragValue::new(dimension).clamp_range(1..=64));
+        }
+        ui.label("Dimensions");
+      });
+      ui.add(egui::Slider::new(&mut settings.voxel_spacing, 0.01..=1.0).text("Spacing"));
+    });
+
+    egui::CollapsingHeader::new("Flicker").default_open(true).show(ui, |ui| {
+      ui.checkbox(&mut settings.voxel_flicker, "Voxel flicker");
+      ui.add(
+        egui::Slider::new(&mut settings.volumes_per_second, MIN_VOLUMES_PER_SECOND..=120.0)
+          .text("Volumes per second"),
+      );
+      combo_box(ui, "Order", &mut settings.flicker.order, &variants(VoxelOrder::Planes, VoxelOrder::next));
+      combo_box(ui, "Axis", &mut settings.flicker.axis, &[SliceAxis::X, SliceAxis::Y, SliceAxis::Z, SliceAxis::View]);
+      combo_box(ui, "Direction", &mut settings.flicker.direction, &variants(SweepDirection::Ascending, SweepDirection::next));
+      ui.add(egui::Slider::new(&mut settings.flicker.slab_thickness, 1..=16).text("Slab thickness"));
+    });
+
+    ui.collapsing("Camera", |ui| {
+      ui.add(egui::Slider::new(&mut settings.rotate_speed, 0.0005..=0.05).logarithmic(true).text("Rotate speed"));
+      ui.add(egui::Slider::new(&mut settings.zoom_speed, 0.01..=1.0).logarithmic(true).text("Zoom speed"));
+      ui.add(egui::Slider::new(&mut settings.min_distance, 0.01..=10.0).text("Min distance"));
+
+      let mut limit_distance = settings.max_distance.is_some();
+      ui.checkbox(&mut limit_distance, "Limit max distance");
+      let mut max_distance = settings.max_distance.unwrap_or(10.0).max(settings.min_distance);
+      if limit_distance {
+        ui.add(egui::Slider::new(&mut max_distance, settings.min_distance..=50.0).text("Max distance"));
+      }
+      settings.max_distance = limit_distance.then_some(max_distance);
+    });
+
+    ui.collapsing("Rendering", |ui| {
+      combo_box(ui, "Mode", &mut settings.render_mode, &variants(RenderMode::Opaque, RenderMode::next));
+      combo_box(ui, "Backend", &mut settings.render_backend, &variants(RenderBackend::InstancedCubes, RenderBackend::next));
+      combo_box(ui, "Stereo", &mut settings.stereo_mode, &variants(StereoMode::Off, StereoMode::next));
+    });
+
+    ui.collapsing("Transfer function", |ui| {
+      let mut presets = vec![None];
+      presets.extend(TransferFunctionPreset::ALL.map(Some));
+      combo_box(ui, "Preset", &mut settings.transfer_function_preset, &presets);
+
+      transfer_function_editor(ui, &mut settings.transfer_function);
+      if ui.add_enabled(settings.transfer_function.is_some(), egui::Button::new("Save")).clicked() {
+        settings.save_transfer_function = true;
+      }
+    });
+
+    ui.collapsing("Colours", |ui| {
+      ui.horizontal(|ui| {
+        ui.color_edit_button_rgb(&mut settings.background_color);
+        ui.label("Background");
+      });
+      ui.checkbox(&mut settings.show_hud, "HUD");
+    });
+  });
+}
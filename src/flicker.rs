@@ -0,0 +1,307 @@
+use std::ops::Range;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::voxel_volume::VoxelVolume;
+
+/**
+ * The axis the volume is sliced along for voxel flicker
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SliceAxis {
+  X,
+  Y,
+  Z,
+
+  /// Perpendicular to the camera's view direction, nearest layer first, so
+  /// each layer is a depth layer as seen from the eye
+  View,
+}
+
+/**
+ * The order the slabs of the volume are shown in
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SweepDirection {
+  /// From the lowest plane to the highest, then back to the lowest
+  Ascending,
+
+  /// From the highest plane to the lowest, then back to the highest
+  Descending,
+
+  /// From the lowest plane to the highest and back again, without repeating
+  /// the planes at either end
+  PingPong,
+}
+
+/**
+ * Which voxels make up each layer of the flicker, and the order the layers
+ * are drawn in. See [crate::flicker_scheduler] for the implementations
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VoxelOrder {
+  /// A plane of voxels at a time, along the slice axis
+  Planes,
+
+  /// A single voxel at a time, in storage order
+  Raster,
+
+  /// A single voxel at a time, in a random permutation
+  Random,
+
+  /// A single voxel at a time, along the Morton (Z-order) curve
+  Morton,
+
+  /// A single voxel at a time, along a Hilbert curve
+  Hilbert,
+}
+
+/**
+ * How voxel flicker slices the volume and sweeps through it
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FlickerConfig {
+  pub order         : VoxelOrder,
+
+  /// Only used when flickering through planes
+  pub axis          : SliceAxis,
+  pub direction     : SweepDirection,
+
+  /// The number of neighbouring layers shown together in each frame
+  pub slab_thickness: u32,
+}
+
+/**
+ * Walks through the slabs of a volume in the order given by a [FlickerConfig]
+ */
+#[derive(Copy, Clone, Debug)]
+pub struct PlaneSweep {
+  pub config: FlickerConfig,
+
+  /// The position within the sweep sequence
+  step: u32,
+}
+
+/**
+ * Assigns voxels to the planes of a slice axis, by their depth along the
+ * axis's normal
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlaneBinning {
+  pub normal     : Vector3<f32>,
+  pub plane_count: u32,
+
+  /// The depth of the first plane
+  min_depth: f32,
+
+  /// The distance between neighbouring planes
+  thickness: f32,
+}
+
+/**
+ * The space between two depths along a normal, which a ray marcher shows in
+ * place of a flicker layer's voxels
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthWindow {
+  pub normal: Vector3<f32>,
+  pub depths: Range<f32>,
+}
+
+impl PlaneBinning {
+
+  /**
+   * Planes along `axis`, facing the camera looking from `eye` to `target`
+   * for [SliceAxis::View]
+   */
+  pub fn for_axis(axis: SliceAxis, volume: &VoxelVolume, eye: Vector3<f32>, target: Vector3<f32>) -> Self {
+    match axis {
+      SliceAxis::View => return Self::along_view(volume, eye, target),
+      axis => return Self::along_axis(axis, volume),
+    }
+  }
+
+  /**
+   * Planes through the voxel centres along one of the volume's axes
+   */
+  pub fn along_axis(axis: SliceAxis, volume: &VoxelVolume) -> Self {
+    let (normal, axis_index) = match axis {
+      SliceAxis::X => (Vector3::unit_x(), 0),
+      SliceAxis::Z => (Vector3::unit_z(), 2),
+      // View-aligned slicing needs the camera, so falls back to horizontal
+      // planes when the camera isn't known
+      SliceAxis::Y | SliceAxis::View => (Vector3::unit_y(), 1),
+    };
+
+    return Self {
+      normal,
+      plane_count: volume.dimensions[axis_index],
+      min_depth  : volume.origin.dot(normal),
+      thickness  : volume.spacing,
+    };
+  }
+
+  /**
+   * Planes perpendicular to the direction from `eye` to `target`, one voxel
+   * spacing apart, ordered from nearest the eye to furthest
+   */
+  pub fn along_view(volume: &VoxelVolume, eye: Vector3<f32>, target: Vector3<f32>) -> Self {
+    let normal = (target - eye).normalize();
+
+    // The nearest and furthest voxel centres are at corners of the volume
+    let [x_max, y_max, z_max] = volume.dimensions.map(|d| d.saturating_sub(1));
+    let (min_depth, max_depth) = [0, x_max].iter()
+      .flat_map(|x| [0, y_max].map(move |y| (*x, y)))
+      .flat_map(|(x, y)| [0, z_max].map(move |z| volume.position(x, y, z).dot(normal)))
+      .fold((f32::MAX, f32::MIN), |(min, max), depth| (min.min(depth), max.max(depth)));
+
+    let thickness = volume.spacing.max(f32::EPSILON);
+    let plane_count = ((max_depth - min_depth) / thickness).round() as u32 + 1;
+
+    return Self {
+      normal,
+      plane_count,
+      min_depth,
+      thickness,
+    };
+  }
+
+  /**
+   * The plane a position falls into
+   */
+  pub fn plane(&self, position: Vector3<f32>) -> u32 {
+    let plane = ((position.dot(self.normal) - self.min_depth) / self.thickness).round();
+    return (plane.max(0.0) as u32).min(self.plane_count.saturating_sub(1));
+  }
+
+  /**
+   * The space taken up by `planes`, from half a plane before the first to
+   * half a plane after the last. Fractional bounds cover part of a plane
+   */
+  pub fn depth_window(&self, planes: Range<f32>) -> DepthWindow {
+    let depth = |plane: f32| self.min_depth + (plane - 0.5) * self.thickness;
+    return DepthWindow {
+      normal: self.normal,
+      depths: depth(planes.start)..depth(planes.end),
+    };
+  }
+}
+
+impl SweepDirection {
+
+  pub fn next(&self) -> Self {
+    match self {
+      SweepDirection::Ascending => return SweepDirection::Descending,
+      SweepDirection::Descending => return SweepDirection::PingPong,
+      SweepDirection::PingPong => return SweepDirection::Ascending,
+    }
+  }
+}
+
+impl VoxelOrder {
+
+  pub fn next(&self) -> Self {
+    match self {
+      VoxelOrder::Planes => return VoxelOrder::Raster,
+      VoxelOrder::Raster => return VoxelOrder::Random,
+      VoxelOrder::Random => return VoxelOrder::Morton,
+      VoxelOrder::Morton => return VoxelOrder::Hilbert,
+      VoxelOrder::Hilbert => return VoxelOrder::Planes,
+    }
+  }
+}
+
+impl Default for FlickerConfig {
+  /// Sweeps up through horizontal planes one at a time, as the original
+  /// renderer did
+  fn default() -> Self {
+    return Self {
+      order         : VoxelOrder::Planes,
+      axis          : SliceAxis::Y,
+      direction     : SweepDirection::Ascending,
+      slab_thickness: 1,
+    };
+  }
+}
+
+impl FlickerConfig {
+
+  /**
+   * The number of slabs `plane_count` planes (or layers) are grouped into
+   */
+  pub fn slab_count(&self, plane_count: u32) -> u32 {
+    return plane_count.div_ceil(self.slab_thickness.max(1));
+  }
+
+  /**
+   * The planes making up a slab
+   */
+  pub fn slab_planes(&self, slab_index: u32, plane_count: u32) -> Range<u32> {
+    let thickness = self.slab_thickness.max(1);
+    let start = (slab_index * thickness).min(plane_count);
+    return start..(start + thickness).min(plane_count);
+  }
+}
+
+impl PlaneSweep {
+
+  pub fn new(config: FlickerConfig) -> Self {
+    return Self {
+      config,
+      step: 0,
+    };
+  }
+
+  /**
+   * The number of frames before the sweep repeats
+   */
+  pub fn sequence_length(&self, slab_count: u32) -> u32 {
+    match self.config.direction {
+      SweepDirection::PingPong if slab_count > 1 => return 2 * (slab_count - 1),
+      _ => return slab_count,
+    }
+  }
+
+  /**
+   * The slab shown at the current step
+   */
+  pub fn current_slab(&self, slab_count: u32) -> u32 {
+    if slab_count == 0 {
+      return 0;
+    }
+
+    let step = self.step % self.sequence_length(slab_count);
+    match self.config.direction {
+      SweepDirection::Ascending => return step,
+      SweepDirection::Descending => return slab_count - 1 - step,
+      SweepDirection::PingPong => {
+        if step < slab_count {
+          return step;
+        }
+        return 2 * (slab_count - 1) - step;
+      },
+    }
+  }
+
+  /**
+   * Moves to the next step, returning the slab to show
+   */
+  pub fn advance(&mut self, slab_count: u32) -> u32 {
+    if slab_count > 0 {
+      self.step = (self.step + 1) % self.sequence_length(slab_count);
+    }
+    return self.current_slab(slab_count);
+  }
+
+  /**
+   * Jumps to the first step which shows `slab_index`
+   */
+  pub fn jump_to_slab(&mut self, slab_index: u32, slab_count: u32) {
+    self.step = (0..self.sequence_length(slab_count))
+      .find(|step| {
+        let sweep = Self { config: self.config, step: *step };
+        sweep.current_slab(slab_count) == slab_index
+      })
+      .unwrap_or(0);
+  }
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+/**
+ * The volumes shown per second when the renderer starts: a layer per frame
+ * at 60 Hz for the default volume's three layers
+ */
+pub const DEFAULT_VOLUMES_PER_SECOND: f32 = 20.0;
+
+/**
+ * The slowest the volume can be flickered through, so keys 1 and 2 can't
+ * stop the flicker or run it backwards
+ */
+pub const MIN_VOLUMES_PER_SECOND: f32 = 1.0;
+
+/**
+ * Times voxel flicker in real time, independently of how often the scene is
+ * updated or frames are presented. Every layer of the volume is shown
+ * `volumes_per_second` times a second, if the display can present frames
+ * fast enough.
+ *
+ * Alternatively the clock can be locked to the display's vblanks, showing
+ * each layer for exactly one of them, so the volumes per second follow the
+ * refresh rate
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FlickerClock {
+  volumes_per_second: f32,
+
+  /// The number of layers making up one pass through the volume
+  pub layers_per_volume: u32,
+
+  /// The time towards showing the next layer
+  elapsed: Duration,
+
+  /// Whether the last layer was shown later than a layer was due
+  falling_behind: bool,
+
+  /// The layers which came due during the last frame besides the one shown
+  skipped_layers: u32,
+
+  /// The extra vblanks the previous layer stayed on screen for, while
+  /// locked to vblanks
+  repeated_layers: u32,
+
+  /// The display's refresh rate, while each layer is locked to one vblank
+  vblank_rate: Option<f32>,
+}
+
+impl Default for FlickerClock {
+  fn default() -> Self {
+    return Self::new(DEFAULT_VOLUMES_PER_SECOND, 1);
+  }
+}
+
+impl FlickerClock {
+
+  pub fn new(volumes_per_second: f32, layers_per_volume: u32) -> Self {
+    return Self {
+      volumes_per_second: volumes_per_second.max(MIN_VOLUMES_PER_SECOND),
+      layers_per_volume,
+      elapsed: Duration::ZERO,
+      falling_behind: false,
+      skipped_layers: 0,
+      repeated_layers: 0,
+      vblank_rate: None,
+    };
+  }
+
+  /**
+   * How often the whole volume is shown, which follows the refresh rate
+   * while locked to vblanks
+   */
+  pub fn volumes_per_second(&self) -> f32 {
+    match self.vblank_rate {
+      Some(vblank_rate) => return vblank_rate / self.layers_per_volume.max(1) as f32,
+      None => return self.volumes_per_second,
+    }
+  }
+
+  /**
+   * Changes how often the whole volume is shown, no slower than
+   * [MIN_VOLUMES_PER_SECOND]. Has no effect while locked to vblanks
+   */
+  pub fn set_volumes_per_second(&mut self, volumes_per_second: f32) {
+    self.volumes_per_second = volumes_per_second.max(MIN_VOLUMES_PER_SECOND);
+  }
+
+  /**
+   * The frames per second needed to show a layer per frame at the target
+   * volumes per second
+   */
+  pub fn required_frame_rate(&self) -> f32 {
+    return self.volumes_per_second() * self.layers_per_volume.max(1) as f32;
+  }
+
+  /**
+   * Shows each layer for exactly one vblank of a display refreshing
+   * `refresh_rate` times a second, or times layers by volumes per second
+   * again with None. Frames must be presented once per vblank, ie. with
+   * [wgpu::PresentMode::Fifo]
+   */
+  pub fn lock_to_vblank(&mut self, refresh_rate: Option<f32>) {
+    self.vblank_rate = refresh_rate;
+    self.restart();
+  }
+
+  /**
+   * The refresh rate each layer is locked to, if any
+   */
+  pub fn vblank_rate(&self) -> Option<f32> {
+    return self.vblank_rate;
+  }
+
+  /**
+   * How long each layer is shown for
+   */
+  pub fn layer_duration(&self) -> Duration {
+    return Duration::from_secs_f32(1.0 / self.required_frame_rate());
+  }
+
+  /**
+   * Whether a display presenting `frame_rate` frames per second can show
+   * every layer at the target volumes per second
+   */
+  pub fn can_keep_up(&self, frame_rate: f32) -> bool {
+    return frame_rate >= self.required_frame_rate();
+  }
+
+  /**
+   * Moves the clock on by the time since the last frame, returning whether
+   * the next layer is due.
+   *
+   * Layers are never skipped, since a layer that's never presented would be
+   * missing from the volume. If more than one layer came due since the last
+   * frame, the clock drops the extra time, and the volume is shown slower
+   * than the target until the display catches up
+   */
+  pub fn tick(&mut self, frame_time: Duration) -> bool {
+
+    // Each presented frame is one vblank, unless rendering it took long
+    // enough to miss one, which shows the last layer again. Nothing is ever
+    // skipped, since every frame shows the next layer
+    if let Some(vblank_rate) = self.vblank_rate {
+      let vblanks = (frame_time.as_secs_f32() * vblank_rate).round() as u32;
+      self.skipped_layers = 0;
+      self.repeated_layers = vblanks.saturating_sub(1);
+      self.falling_behind = self.repeated_layers > 0;
+      return true;
+    }
+
+    self.repeated_layers = 0;
+    self.elapsed += frame_time;
+
+    let layer_duration = self.layer_duration();
+    if self.elapsed < layer_duration {
+      self.skipped_layers = 0;
+      return false;
+    }
+
+    self.elapsed -= layer_duration;
+    self.skipped_layers = (self.elapsed.as_nanos() / layer_duration.as_nanos().max(1)) as u32;
+    self.falling_behind = self.skipped_layers > 0;
+    if self.falling_behind {
+      self.elapsed = Duration::ZERO;
+    }
+    return true;
+  }
+
+  /**
+   * Whether the last layer came due more than a layer late, because frames
+   * aren't being presented fast enough
+   */
+  pub fn falling_behind(&self) -> bool {
+    return self.falling_behind;
+  }
+
+  /**
+   * How many more layers came due during the last frame than the one it
+   * showed. Their time is dropped rather than the layers, so the volume is
+   * shown that many layers late. Always 0 while locked to vblanks
+   */
+  pub fn skipped_layers(&self) -> u32 {
+    return self.skipped_layers;
+  }
+
+  /**
+   * How many extra vblanks the layer before the last one was shown for,
+   * because a vblank was missed while locked to them. The volume is shown
+   * that many vblanks late. Always 0 while timing by volumes per second
+   */
+  pub fn repeated_layers(&self) -> u32 {
+    return self.repeated_layers;
+  }
+
+  /**
+   * Starts timing the current layer from now
+   */
+  pub fn restart(&mut self) {
+    self.elapsed = Duration::ZERO;
+    self.falling_behind = false;
+    self.skipped_layers = 0;
+    self.repeated_layers = 0;
+  }
+}
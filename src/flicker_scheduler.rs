@@ -0,0 +1,496 @@
+use std::ops::Range;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::{
+  flicker::{DepthWindow, FlickerConfig, PlaneBinning, PlaneSweep, SliceAxis, VoxelOrder},
+  voxel_volume::VoxelVolume,
+};
+
+/**
+ * The seed of the random permutation order, fixed so the same volume always
+ * flickers in the same order
+ */
+pub const RANDOM_ORDER_SEED: u64 = 0x5EED_F11C_CE12;
+
+/**
+ * How far in radians the camera has to turn before a schedule following it
+ * is rebuilt, so float jitter in the view direction doesn't rebuild it
+ */
+pub const RESCHEDULE_ANGLE: f32 = 0.005;
+
+/**
+ * Where the camera is looking from, for schedules which depend on it
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ViewPoint {
+  pub eye   : Vector3<f32>,
+  pub target: Vector3<f32>,
+}
+
+/**
+ * The order voxels are drawn in, grouped into the layers shown one per frame
+ */
+#[derive(Clone, Debug, Default)]
+pub struct FlickerSchedule {
+  /// The coordinates of every occupied voxel, layer by layer
+  pub voxel_order: Vec<[u32; 3]>,
+
+  /// The voxels of `voxel_order` making up each layer, in sweep order. Empty
+  /// layers have empty ranges
+  pub layer_ranges: Vec<Range<u32>>,
+}
+
+/**
+ * Decides which subset of a volume's voxels is drawn in each frame of voxel
+ * flicker
+ */
+pub trait FlickerScheduler {
+  /**
+   * Orders the occupied voxels of `volume` into layers
+   */
+  fn schedule(&self, volume: &VoxelVolume, view_point: ViewPoint) -> FlickerSchedule;
+
+  /**
+   * Whether the schedule has to be rebuilt when the camera turns
+   */
+  fn follows_camera(&self) -> bool {
+    return false;
+  }
+}
+
+/**
+ * One plane of voxels per layer, along a [SliceAxis]
+ */
+pub struct PlaneScheduler {
+  pub axis: SliceAxis,
+}
+
+/**
+ * One voxel per layer, in storage order: along x, then z, then up through
+ * the horizontal planes from the lowest
+ */
+pub struct RasterScheduler;
+
+/**
+ * One voxel per layer, in a seeded random permutation, so consecutive frames
+ * are spread across the volume
+ */
+pub struct RandomScheduler {
+  pub seed: u64,
+}
+
+/**
+ * One voxel per layer, along a space-filling curve, so consecutive frames stay
+ * close together while still covering every region of the volume evenly
+ */
+pub struct SpaceFillingScheduler {
+  pub curve: SpaceFillingCurve,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpaceFillingCurve {
+  /// Z-order: interleaves the bits of the coordinates
+  Morton,
+
+  /// Never jumps between non-neighbouring voxels of a power-of-two cube
+  Hilbert,
+}
+
+/**
+ * Runs a [FlickerScheduler], tracking the layer shown each frame and the
+ * instances drawn for it
+ */
+pub struct VoxelScheduler {
+  sweep: PlaneSweep,
+  scheduler: Box<dyn FlickerScheduler>,
+
+  /// The instances making up each layer, from the last schedule
+  layer_ranges: Vec<Range<u32>>,
+
+  /// The camera the last schedule was made for
+  view_point: Option<ViewPoint>,
+
+  slab_index: u32,
+  shown_layers: Range<u32>,
+  instances_to_render: Range<u32>,
+
+  /// Whether [VoxelScheduler::show_all] was called since a slab was last
+  /// shown
+  showing_all: bool,
+}
+
+impl FlickerSchedule {
+
+  /**
+   * Schedules each group of voxels as a layer
+   */
+  pub fn from_layers(layers: Vec<Vec<[u32; 3]>>) -> Self {
+    let mut voxel_order = Vec::new();
+    let mut layer_ranges = Vec::with_capacity(layers.len());
+    for layer in layers {
+      let layer_start = voxel_order.len() as u32;
+      voxel_order.extend(layer);
+      layer_ranges.push(layer_start..voxel_order.len() as u32);
+    }
+
+    return Self {
+      voxel_order,
+      layer_ranges,
+    };
+  }
+
+  /**
+   * Schedules each voxel as its own layer
+   */
+  pub fn one_voxel_per_layer(voxel_order: Vec<[u32; 3]>) -> Self {
+    let layer_ranges = (0..voxel_order.len() as u32)
+      .map(|voxel| voxel..voxel + 1)
+      .collect();
+
+    return Self {
+      voxel_order,
+      layer_ranges,
+    };
+  }
+}
+
+impl FlickerScheduler for PlaneScheduler {
+  fn schedule(&self, volume: &VoxelVolume, view_point: ViewPoint) -> FlickerSchedule {
+    let binning = PlaneBinning::for_axis(self.axis, volume, view_point.eye, view_point.target);
+
+    let mut planes = vec![Vec::new(); binning.plane_count as usize];
+    for ([x, y, z], _) in volume.occupied_voxels() {
+      planes[binning.plane(volume.position(x, y, z)) as usize].push([x, y, z]);
+    }
+
+    return FlickerSchedule::from_layers(planes);
+  }
+
+  fn follows_camera(&self) -> bool {
+    return self.axis == SliceAxis::View;
+  }
+}
+
+impl FlickerScheduler for RasterScheduler {
+  fn schedule(&self, volume: &VoxelVolume, _view_point: ViewPoint) -> FlickerSchedule {
+    let voxel_order = volume.occupied_voxels()
+      .map(|(coordinates, _)| coordinates)
+      .collect();
+    return FlickerSchedule::one_voxel_per_layer(voxel_order);
+  }
+}
+
+impl FlickerScheduler for RandomScheduler {
+  fn schedule(&self, volume: &VoxelVolume, _view_point: ViewPoint) -> FlickerSchedule {
+    let mut voxel_order = volume.occupied_voxels()
+      .map(|(coordinates, _)| coordinates)
+      .collect::<Vec<_>>();
+
+    // Fisher-Yates shuffle
+    let mut state = self.seed;
+    for i in (1..voxel_order.len()).rev() {
+      let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+      voxel_order.swap(i, j);
+    }
+
+    return FlickerSchedule::one_voxel_per_layer(voxel_order);
+  }
+}
+
+impl FlickerScheduler for SpaceFillingScheduler {
+  fn schedule(&self, volume: &VoxelVolume, _view_point: ViewPoint) -> FlickerSchedule {
+    let bits = curve_bits(volume.dimensions);
+
+    let mut voxel_order = volume.occupied_voxels()
+      .map(|(coordinates, _)| coordinates)
+      .collect::<Vec<_>>();
+    voxel_order.sort_by_key(|coordinates| match self.curve {
+      SpaceFillingCurve::Morton => morton_index(*coordinates),
+      SpaceFillingCurve::Hilbert => hilbert_index(*coordinates, bits),
+    });
+
+    return FlickerSchedule::one_voxel_per_layer(voxel_order);
+  }
+}
+
+impl VoxelOrder {
+
+  /**
+   * The scheduler implementing this order, slicing along `axis` if it sweeps
+   * through planes
+   */
+  pub fn scheduler(&self, axis: SliceAxis) -> Box<dyn FlickerScheduler> {
+    match self {
+      VoxelOrder::Planes => return Box::new(PlaneScheduler { axis }),
+      VoxelOrder::Raster => return Box::new(RasterScheduler),
+      VoxelOrder::Random => return Box::new(RandomScheduler { seed: RANDOM_ORDER_SEED }),
+      VoxelOrder::Morton => return Box::new(SpaceFillingScheduler { curve: SpaceFillingCurve::Morton }),
+      VoxelOrder::Hilbert => return Box::new(SpaceFillingScheduler { curve: SpaceFillingCurve::Hilbert }),
+    }
+  }
+}
+
+impl VoxelScheduler {
+
+  /**
+   * Starts with nothing scheduled; call [VoxelScheduler::schedule] once the
+   * volume is known
+   */
+  pub fn new(config: FlickerConfig) -> Self {
+    return Self {
+      sweep: PlaneSweep::new(config),
+      scheduler: config.order.scheduler(config.axis),
+      layer_ranges: Vec::new(),
+      view_point: None,
+      slab_index: 0,
+      shown_layers: 0..0,
+      instances_to_render: 0..0,
+      showing_all: false,
+    };
+  }
+
+  pub fn config(&self) -> FlickerConfig {
+    return self.sweep.config;
+  }
+
+  /**
+   * Changes the flicker config and restarts the sweep, returning whether the
+   * voxels need to be scheduled again
+   */
+  pub fn set_config(&mut self, config: FlickerConfig) -> bool {
+    let previous = self.sweep.config;
+    self.sweep = PlaneSweep::new(config);
+
+    if config.order == previous.order && config.axis == previous.axis {
+      return false;
+    }
+
+    self.scheduler = config.order.scheduler(config.axis);
+    return true;
+  }
+
+  /**
+   * Orders the voxels of `volume` for the current config, returning the
+   * schedule for building their instances
+   */
+  pub fn schedule(&mut self, volume: &VoxelVolume, view_point: ViewPoint) -> FlickerSchedule {
+    let schedule = self.scheduler.schedule(volume, view_point);
+    self.layer_ranges = schedule.layer_ranges.clone();
+    self.view_point = Some(view_point);
+    return schedule;
+  }
+
+  /**
+   * Treats `instance_count` arbitrary instances as a single layer, eg. to
+   * show a single cube
+   */
+  pub fn schedule_single_layer(&mut self, instance_count: u32) {
+    self.layer_ranges = std::iter::once(0..instance_count).collect();
+    self.view_point = None;
+  }
+
+  /**
+   * Whether the schedule depends on the camera, and it has turned more than
+   * [RESCHEDULE_ANGLE] since the voxels were last scheduled
+   */
+  pub fn needs_rescheduling(&self, view_point: ViewPoint) -> bool {
+    if self.scheduler.follows_camera() == false {
+      return false;
+    }
+
+    match self.view_point {
+      Some(scheduled) => {
+        let scheduled_direction = (scheduled.target - scheduled.eye).normalize();
+        let direction = (view_point.target - view_point.eye).normalize();
+        return scheduled_direction.dot(direction) < RESCHEDULE_ANGLE.cos();
+      },
+      None => return true,
+    }
+  }
+
+  /**
+   * The number of slabs of layers the volume is flickered through
+   */
+  pub fn slab_count(&self) -> u32 {
+    return self.sweep.config.slab_count(self.layer_ranges.len() as u32);
+  }
+
+  /**
+   * The slab shown by the last call to [VoxelScheduler::advance] or
+   * [VoxelScheduler::show_slab]
+   */
+  pub fn slab_index(&self) -> u32 {
+    return self.slab_index;
+  }
+
+  /**
+   * The slab being drawn, or None while every layer is drawn at once
+   */
+  pub fn shown_slab(&self) -> Option<u32> {
+    return (self.showing_all == false).then_some(self.slab_index);
+  }
+
+  /**
+   * Moves on to the next slab of the sweep
+   */
+  pub fn advance(&mut self) {
+    let slab_count = self.slab_count();
+    if slab_count == 0 {
+      return;
+    }
+
+    let slab_index = self.sweep.advance(slab_count);
+    self.show_slab_instances(slab_index);
+  }
+
+  /**
+   * Shows only the slab at `slab_index`, and continues the sweep from there
+   */
+  pub fn show_slab(&mut self, slab_index: u32) {
+    let slab_count = self.slab_count();
+    let slab_index = slab_index.min(slab_count.saturating_sub(1));
+
+    self.sweep.jump_to_slab(slab_index, slab_count);
+    self.show_slab_instances(slab_index);
+  }
+
+  /**
+   * Shows the slab the sweep is currently at
+   */
+  pub fn show_current_slab(&mut self) {
+    self.show_slab(self.sweep.current_slab(self.slab_count()));
+  }
+
+  /**
+   * Shows every layer at once
+   */
+  pub fn show_all(&mut self) {
+    let instance_count = self.layer_ranges.last().map(|layer| layer.end).unwrap_or(0);
+    self.shown_layers = 0..self.layer_ranges.len() as u32;
+    self.instances_to_render = 0..instance_count;
+    self.showing_all = true;
+  }
+
+  /**
+   * The instances to draw this frame
+   */
+  pub fn instances_to_render(&self) -> Range<u32> {
+    return self.instances_to_render.clone();
+  }
+
+  /**
+   * The space the layers shown this frame take up in `volume`, for drawing
+   * them without instances. This is exact when flickering through planes;
+   * other orders cover the same share of the planes along the slice axis as
+   * the shown layers are of all layers
+   */
+  pub fn depth_window(&self, volume: &VoxelVolume, view_point: ViewPoint) -> DepthWindow {
+    let binning = PlaneBinning::for_axis(
+      self.sweep.config.axis,
+      volume,
+      view_point.eye,
+      view_point.target,
+    );
+
+    let planes_per_layer = binning.plane_count as f32 / self.layer_ranges.len().max(1) as f32;
+    return binning.depth_window(
+      self.shown_layers.start as f32 * planes_per_layer..self.shown_layers.end as f32 * planes_per_layer,
+    );
+  }
+
+  fn show_slab_instances(&mut self, slab_index: u32) {
+    let layers = self.sweep.config.slab_planes(slab_index, self.layer_ranges.len() as u32);
+
+    // Layers are stored in order, so a slab's instances are contiguous
+    self.instances_to_render = match (self.layer_ranges.get(layers.start as usize), self.layer_ranges.get(layers.end.saturating_sub(1) as usize)) {
+      (Some(first_layer), Some(last_layer)) if layers.is_empty() == false => first_layer.start..last_layer.end,
+      _ => 0..0,
+    };
+    self.shown_layers = layers;
+    self.slab_index = slab_index;
+    self.showing_all = false;
+  }
+}
+
+/**
+ * The position of a voxel along the Morton (Z-order) curve
+ */
+pub fn morton_index(coordinates: [u32; 3]) -> u64 {
+  let mut index = 0;
+  for bit in 0..21 {
+    for (axis, coordinate) in coordinates.iter().enumerate() {
+      index |= ((*coordinate as u64 >> bit) & 1) << (3 * bit + axis);
+    }
+  }
+  return index;
+}
+
+/**
+ * The position of a voxel along a 3D Hilbert curve through a cube `2^bits`
+ * voxels wide, using Skilling's transpose method
+ * (<https://doi.org/10.1063/1.1751381>)
+ */
+pub fn hilbert_index(coordinates: [u32; 3], bits: u32) -> u64 {
+  let mut x = coordinates;
+  let highest_bit = 1u32 << (bits.max(1) - 1);
+
+  // Undo the excess rotations and reflections of each sub-cube
+  let mut q = highest_bit;
+  while q > 1 {
+    let p = q - 1;
+    for i in 0..3 {
+      if x[i] & q != 0 {
+        x[0] ^= p;
+      } else {
+        let t = (x[0] ^ x[i]) & p;
+        x[0] ^= t;
+        x[i] ^= t;
+      }
+    }
+    q >>= 1;
+  }
+
+  // Gray encode
+  x[1] ^= x[0];
+  x[2] ^= x[1];
+  let mut t = 0;
+  let mut q = highest_bit;
+  while q > 1 {
+    if x[2] & q != 0 {
+      t ^= q - 1;
+    }
+    q >>= 1;
+  }
+  for coordinate in x.iter_mut() {
+    *coordinate ^= t;
+  }
+
+  // The index is the transposed coordinates, read a bit of each at a time
+  let mut index = 0;
+  for bit in (0..bits.max(1)).rev() {
+    for coordinate in x {
+      index = (index << 1) | ((coordinate >> bit) & 1) as u64;
+    }
+  }
+  return index;
+}
+
+/**
+ * The number of bits per axis a curve needs to cover a volume
+ */
+fn curve_bits(dimensions: [u32; 3]) -> u32 {
+  let largest = dimensions.into_iter().max().unwrap_or(1);
+  return (u32::BITS - largest.saturating_sub(1).leading_zeros()).max(1);
+}
+
+/**
+ * Steps a SplitMix64 generator, returning its next output
+ */
+fn splitmix64(state: &mut u64) -> u64 {
+  *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+  let mut z = *state;
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  return z ^ (z >> 31);
+}
@@ -0,0 +1,149 @@
+use cgmath::{InnerSpace, Vector4};
+
+use crate::voxel_volume::VoxelVolume;
+
+use super::{
+  hypervoxel_volume::HypervoxelVolume,
+  transform::{RotationPlane, Transform4D},
+};
+
+/**
+ * One of the eyes of an [EyePair4D]
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Eye {
+  Left,
+  Right,
+}
+
+/**
+ * Two 4D eyes looking at a 4D scene along -W from slightly different
+ * positions, like a pair of 3D eyes looking along -Z. Each eye sees the scene
+ * on a 3D retina, so the pair turns one 4D scene into two 3D volumes
+ *
+ * The eyes sit either side of the W axis along X, and turn in the XW plane
+ * so their lines of sight cross at the convergence distance
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EyePair4D {
+  /// The distance between the eyes along X
+  pub separation: f32,
+
+  /// The distance from the eyes along -W at which their lines of sight
+  /// cross. Points at this depth appear at the same place on both retinas
+  pub convergence: f32,
+
+  /// The distance from the eyes to the 4D origin along W. Points at W = 0
+  /// keep their size on the retinas
+  pub viewer_distance: f32,
+}
+
+impl Default for EyePair4D {
+  /// Looks at the 4D origin from far enough away to see the default
+  /// hypervolume, converging on its centre
+  fn default() -> Self {
+    return Self {
+      separation     : 0.2,
+      convergence    : 1.0,
+      viewer_distance: 1.0,
+    };
+  }
+}
+
+impl EyePair4D {
+
+  /**
+   * The transform from the scene into an eye's own space, where the eye is
+   * at (0, 0, 0, `viewer_distance`) looking along -W
+   */
+  pub fn view_transform(&self, eye: Eye) -> Transform4D {
+    let offset = match eye {
+      Eye::Left => -self.separation / 2.0,
+      Eye::Right => self.separation / 2.0,
+    };
+
+    // Turns the line of sight from the eye to the convergence point onto -W
+    let mut view = Transform4D::default();
+    view.rotate(RotationPlane::XW, (offset / self.convergence.max(f32::EPSILON)).atan());
+
+    let eye_position = Vector4::new(offset, 0.0, 0.0, self.viewer_distance);
+    view.translation = Vector4::unit_w() * self.viewer_distance - view.rotation * eye_position;
+    return view;
+  }
+
+  /**
+   * What `eye` sees of `volume`, turned by `orientation`, as a 3D volume with
+   * the same spacing. Each retina voxel shows the nearest occupied hypervoxel
+   * along the ray from the eye through it, so nearer hypervoxels hide the
+   * ones behind them in W like nearer objects do in 3D
+   *
+   * The retina is one and a half times as wide as the hypervolume's longest
+   * side, since the parts of the scene nearer the eye appear larger
+   */
+  pub fn retina(&self, eye: Eye, volume: &HypervoxelVolume, orientation: &Transform4D) -> VoxelVolume {
+    let longest_side = volume.dimensions.into_iter().max().unwrap_or(0);
+    let side = longest_side + longest_side / 2;
+    let mut retina = VoxelVolume::new([side; 3], volume.spacing);
+
+    let view = self.view_transform(eye);
+    let eye_position = Vector4::unit_w() * self.viewer_distance;
+    let step = volume.spacing / 2.0;
+
+    // Rays are only marched through the hypersphere around the hypervolume
+    let centre = view.apply(orientation.translation);
+    let radius = volume.origin.magnitude() + volume.spacing;
+
+    let retina_centre = side.saturating_sub(1) as f32 / 2.0;
+    let local = |coordinate: u32| (coordinate as f32 - retina_centre) * volume.spacing;
+
+    for y in 0..side {
+      for z in 0..side {
+        for x in 0..side {
+          let retina_point = Vector4::new(local(x), local(y), local(z), 0.0);
+          let direction = (retina_point - eye_position).normalize();
+
+          let closest_approach = (centre - eye_position).dot(direction);
+          let miss_distance = (centre - eye_position - direction * closest_approach).magnitude();
+          if miss_distance > radius {
+            continue;
+          }
+          let half_chord = (radius * radius - miss_distance * miss_distance).sqrt();
+
+          let mut distance = (closest_approach - half_chord).max(0.0);
+          while distance <= closest_approach + half_chord {
+            let scene_point = view.inverse_apply(eye_position + direction * distance);
+            let voxel = volume.sample(orientation.inverse_apply(scene_point));
+            if let Some(voxel) = voxel.filter(|voxel| voxel.occupied) {
+              retina.set(x, y, z, *voxel);
+              break;
+            }
+            distance += step;
+          }
+        }
+      }
+    }
+
+    return retina;
+  }
+
+  /**
+   * The retinas of both eyes, left then right
+   */
+  pub fn retinas(&self, volume: &HypervoxelVolume, orientation: &Transform4D) -> [VoxelVolume; 2] {
+    return [
+      self.retina(Eye::Left, volume, orientation),
+      self.retina(Eye::Right, volume, orientation),
+    ];
+  }
+}
+
+impl Eye {
+
+  /**
+   * Where the eye is in the scene
+   */
+  pub fn position(&self, eye_pair: &EyePair4D) -> Vector4<f32> {
+    let view = eye_pair.view_transform(*self);
+    return view.inverse_apply(Vector4::unit_w() * eye_pair.viewer_distance);
+  }
+}
@@ -0,0 +1,79 @@
+use cgmath::InnerSpace;
+
+use crate::voxel_volume::DEFAULT_VOLUME_EXTENT;
+
+use super::{hypervoxel_volume::HypervoxelVolume, polytope::{Polytope, RegularPolytope}};
+
+/**
+ * The size of the 4D volume shapes are voxelised into when picked with key
+ * G, fine enough for the 120- and 600-cells to be recognisable
+ */
+pub const HYPERSHAPE_DIMENSIONS: [u32; 4] = [15, 15, 15, 15];
+
+/**
+ * A solid 4D shape which can be voxelised, hypersliced and flickered
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hypershape {
+  Regular(RegularPolytope),
+
+  /// The product of two polygons with these numbers of sides
+  Duoprism { sides: [u32; 2] },
+
+  /// A ball extruded along W, the 4D analogue of a cylinder
+  Spherinder,
+
+  /// The 4D ball
+  Hypersphere,
+}
+
+impl Default for Hypershape {
+  fn default() -> Self {
+    return Hypershape::Regular(RegularPolytope::Tesseract);
+  }
+}
+
+impl Hypershape {
+
+  /**
+   * The next shape, going through the regular polytopes then the others
+   */
+  pub fn next(&self) -> Self {
+    match self {
+      Hypershape::Regular(RegularPolytope::SixHundredCell) => return Hypershape::Duoprism { sides: [3, 4] },
+      Hypershape::Regular(polytope) => return Hypershape::Regular(polytope.next()),
+      Hypershape::Duoprism { .. } => return Hypershape::Spherinder,
+      Hypershape::Spherinder => return Hypershape::Hypersphere,
+      Hypershape::Hypersphere => return Hypershape::Regular(RegularPolytope::FiveCell),
+    }
+  }
+
+  /**
+   * Fills a hypervolume with the shape, as large as fits
+   */
+  pub fn voxelize(&self, dimensions: [u32; 4], spacing: f32) -> HypervoxelVolume {
+    let radius = HypervoxelVolume::inner_radius(dimensions, spacing);
+
+    match self {
+      Hypershape::Regular(polytope) => return polytope.polytope().voxelize(dimensions, spacing),
+      Hypershape::Duoprism { sides } => return Polytope::duoprism(sides[0], sides[1]).voxelize(dimensions, spacing),
+      Hypershape::Spherinder => {
+        return HypervoxelVolume::from_shape(dimensions, spacing, |point| {
+          return point.truncate().magnitude() <= radius && point.w.abs() <= radius;
+        });
+      },
+      Hypershape::Hypersphere => {
+        return HypervoxelVolume::from_shape(dimensions, spacing, |point| point.magnitude() <= radius);
+      },
+    }
+  }
+
+  /**
+   * The shape voxelised at [HYPERSHAPE_DIMENSIONS], as wide as the default
+   * volume
+   */
+  pub fn hypervolume(&self) -> HypervoxelVolume {
+    let spacing = DEFAULT_VOLUME_EXTENT / HYPERSHAPE_DIMENSIONS[0] as f32;
+    return self.voxelize(HYPERSHAPE_DIMENSIONS, spacing);
+  }
+}
@@ -0,0 +1,173 @@
+use cgmath::{Matrix, Vector4};
+
+use crate::voxel_volume::VoxelVolume;
+
+use super::{hypervoxel_volume::HypervoxelVolume, transform::Transform4D};
+
+/**
+ * How far the hyperplane moves along W each update while sweeping, in
+ * voxel spacings
+ */
+pub const W_SWEEP_SPEED: f32 = 0.1;
+
+/**
+ * A 3D cross-section of a 4D volume: the XYZ hyperplane at `w`, through the
+ * volume turned by `orientation`. Turning the volume rather than the
+ * hyperplane lets 4D rotations apply to hypervolumes the same way they apply
+ * to the tesseract
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HyperSlice {
+  pub w          : f32,
+  pub orientation: Transform4D,
+}
+
+/**
+ * Moves a [HyperSlice] back and forth along W, through the whole volume
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WSweep {
+  /// The distance moved each update
+  pub speed: f32,
+
+  /// 1 while moving towards +W, -1 towards -W
+  direction: f32,
+}
+
+/**
+ * A 4D volume and the hyperslice of it shown as the 3D volume
+ */
+#[derive(Clone, Debug)]
+pub struct Hyperslicer {
+  pub volume : HypervoxelVolume,
+  pub slice  : HyperSlice,
+  pub sweep  : WSweep,
+
+  /// Whether the hyperplane sweeps through W every update
+  pub animate: bool,
+}
+
+impl Default for HyperSlice {
+  fn default() -> Self {
+    return Self {
+      w          : 0.0,
+      orientation: Transform4D::default(),
+    };
+  }
+}
+
+impl HyperSlice {
+
+  /**
+   * Samples the cross-section into a 3D volume with the same spacing. The
+   * volume is a cube as wide as the hypervolume's longest side, so the
+   * cross-section isn't cut short when the hypervolume is turned
+   */
+  pub fn slice(&self, volume: &HypervoxelVolume) -> VoxelVolume {
+    let side = volume.dimensions.into_iter().max().unwrap_or(0);
+    let mut slice = VoxelVolume::new([side; 3], volume.spacing);
+
+    let centre = side.saturating_sub(1) as f32 / 2.0;
+    let local = |coordinate: u32| (coordinate as f32 - centre) * volume.spacing;
+
+    for y in 0..side {
+      for z in 0..side {
+        for x in 0..side {
+          let point = Vector4::new(local(x), local(y), local(z), self.w);
+          if let Some(voxel) = volume.sample(self.orientation.inverse_apply(point)) {
+            slice.set(x, y, z, *voxel);
+          }
+        }
+      }
+    }
+
+    return slice;
+  }
+
+  /**
+   * The lowest and highest W at which the hyperplane still cuts through the
+   * turned volume
+   */
+  pub fn w_range(&self, volume: &HypervoxelVolume) -> (f32, f32) {
+    // The W row of the rotation is the direction in the volume which ends up
+    // along W
+    let w_direction = self.orientation.rotation.row(3);
+    let (min, max) = volume.depth_range(w_direction);
+    let offset = self.orientation.translation.w;
+    return (min + offset, max + offset);
+  }
+}
+
+impl WSweep {
+
+  pub fn new(speed: f32) -> Self {
+    return Self {
+      speed,
+      direction: 1.0,
+    };
+  }
+
+  /**
+   * Moves the hyperplane one step, turning around at either end of the
+   * volume
+   */
+  pub fn advance(&mut self, slice: &mut HyperSlice, volume: &HypervoxelVolume) {
+    let (min, max) = slice.w_range(volume);
+    slice.w += self.speed * self.direction;
+
+    if slice.w >= max {
+      slice.w = max;
+      self.direction = -1.0;
+    } else if slice.w <= min {
+      slice.w = min;
+      self.direction = 1.0;
+    }
+  }
+}
+
+impl Hyperslicer {
+
+  pub fn new(volume: HypervoxelVolume) -> Self {
+    let sweep = WSweep::new(volume.spacing * W_SWEEP_SPEED);
+
+    return Self {
+      volume,
+      slice  : HyperSlice::default(),
+      sweep,
+      animate: false,
+    };
+  }
+
+  /**
+   * The current cross-section as a 3D volume
+   */
+  pub fn slice_volume(&self) -> VoxelVolume {
+    return self.slice.slice(&self.volume);
+  }
+
+  /**
+   * Sweeps the hyperplane along W if animating, returning whether it moved
+   */
+  pub fn update(&mut self) -> bool {
+    if self.animate == false {
+      return false;
+    }
+
+    self.sweep.advance(&mut self.slice, &self.volume);
+    return true;
+  }
+
+  /**
+   * Moves the hyperplane by `distance` along W, staying within the volume
+   */
+  pub fn step_w(&mut self, distance: f32) {
+    let (min, max) = self.slice.w_range(&self.volume);
+    self.slice.w = (self.slice.w + distance).clamp(min, max);
+  }
+}
+
+impl Default for Hyperslicer {
+  fn default() -> Self {
+    return Self::new(HypervoxelVolume::default());
+  }
+}
@@ -0,0 +1,248 @@
+use cgmath::{InnerSpace, Vector4};
+
+use crate::voxel_volume::{Voxel, DEFAULT_VOLUME_EXTENT};
+
+/**
+ * The size of the 4D volume shown when hyperslicing starts
+ */
+pub const DEFAULT_HYPERVOLUME_DIMENSIONS: [u32; 4] = [5, 5, 5, 5];
+
+/**
+ * A 4D grid of voxels, sized at runtime, analogous to
+ * [crate::voxel_volume::VoxelVolume]
+ *
+ * Voxels are stored w-major, so each 3D volume at a fixed w is a contiguous
+ * run laid out like a [crate::voxel_volume::VoxelVolume] (y, then z, then x)
+ */
+#[derive(Clone, Debug)]
+pub struct HypervoxelVolume {
+  /// The number of voxels along [x, y, z, w]
+  pub dimensions: [u32; 4],
+
+  /// The distance between the centres of neighbouring voxels
+  pub spacing: f32,
+
+  /// The position of the voxel at index (0, 0, 0, 0). The voxel centres are
+  /// placed symmetrically around the 4D origin
+  pub origin: Vector4<f32>,
+
+  voxels: Vec<Voxel>,
+}
+
+impl HypervoxelVolume {
+
+  /**
+   * Creates a volume of empty voxels, centred around the 4D origin
+   */
+  pub fn new(dimensions: [u32; 4], spacing: f32) -> Self {
+    let voxel_count = dimensions.iter().map(|d| *d as usize).product();
+
+    return Self {
+      dimensions,
+      spacing,
+      origin: Self::centered_origin(dimensions, spacing),
+      voxels: vec![Voxel::EMPTY; voxel_count],
+    };
+  }
+
+  /**
+   * Creates a volume by sampling `sample` at the centre of every voxel
+   */
+  pub fn from_fn(
+    dimensions: [u32; 4],
+    spacing: f32,
+    mut sample: impl FnMut(Vector4<f32>) -> Voxel,
+  ) -> Self {
+    let mut volume = Self::new(dimensions, spacing);
+    for i in 0..volume.voxels.len() {
+      let [x, y, z, w] = volume.coordinates(i);
+      volume.voxels[i] = sample(volume.position(x, y, z, w));
+    }
+    return volume;
+  }
+
+  /**
+   * Creates a fully occupied hypercube whose voxels are coloured by position,
+   * red along x, green along y and blue along w, like the tesseract's
+   * vertex colours
+   */
+  pub fn gradient(dimensions: [u32; 4], spacing: f32) -> Self {
+    let mut volume = Self::new(dimensions, spacing);
+
+    for i in 0..volume.voxels.len() {
+      let coordinates = volume.coordinates(i);
+      let mut color = [255; 4];
+      for (channel, axis) in [(0, 0), (1, 1), (2, 3)] {
+        let steps = dimensions[axis].saturating_sub(1).max(1);
+        color[channel] = (64 + coordinates[axis] * 191 / steps) as u8;
+      }
+      volume.voxels[i] = Voxel::filled(color, 1.0);
+    }
+
+    return volume;
+  }
+
+  /**
+   * Creates a volume containing a solid shape centred on the 4D origin,
+   * occupying the voxels whose centres `contains` accepts. The voxels are
+   * coloured by position like [HypervoxelVolume::gradient]
+   */
+  pub fn from_shape(
+    dimensions: [u32; 4],
+    spacing: f32,
+    contains: impl Fn(Vector4<f32>) -> bool,
+  ) -> Self {
+    let radius = Self::inner_radius(dimensions, spacing).max(f32::EPSILON);
+
+    return Self::from_fn(dimensions, spacing, |position| {
+      if contains(position) == false {
+        return Voxel::EMPTY;
+      }
+
+      let mut color = [255; 4];
+      for (channel, axis) in [(0, 0), (1, 1), (2, 3)] {
+        let fraction = ((position[axis] / radius + 1.0) / 2.0).clamp(0.0, 1.0);
+        color[channel] = (64.0 + fraction * 191.0) as u8;
+      }
+      return Voxel::filled(color, 1.0);
+    });
+  }
+
+  /**
+   * The distance from the 4D origin to the outermost voxel centres along the
+   * volume's shortest side, the radius of the largest shape that fits
+   */
+  pub fn inner_radius(dimensions: [u32; 4], spacing: f32) -> f32 {
+    let shortest_side = dimensions.into_iter().min().unwrap_or(0);
+    return shortest_side.saturating_sub(1) as f32 * spacing / 2.0;
+  }
+
+  /**
+   * The origin which places the voxel centres around the 4D origin
+   */
+  pub fn centered_origin(dimensions: [u32; 4], spacing: f32) -> Vector4<f32> {
+    return -Vector4::new(
+      dimensions[0].saturating_sub(1) as f32,
+      dimensions[1].saturating_sub(1) as f32,
+      dimensions[2].saturating_sub(1) as f32,
+      dimensions[3].saturating_sub(1) as f32,
+    ) * spacing / 2.0;
+  }
+
+  pub fn voxel_count(&self) -> usize {
+    return self.voxels.len();
+  }
+
+  pub fn voxels(&self) -> &[Voxel] {
+    return &self.voxels;
+  }
+
+  /**
+   * Returns the storage index of a voxel, or None if it's out of bounds
+   */
+  pub fn index(&self, x: u32, y: u32, z: u32, w: u32) -> Option<usize> {
+    let [x_max, y_max, z_max, w_max] = self.dimensions;
+    if x >= x_max || y >= y_max || z >= z_max || w >= w_max {
+      return None;
+    }
+
+    return Some((((w * y_max + y) * z_max + z) * x_max + x) as usize);
+  }
+
+  /**
+   * The inverse of [HypervoxelVolume::index]
+   */
+  pub fn coordinates(&self, index: usize) -> [u32; 4] {
+    let [x_max, y_max, z_max, _] = self.dimensions;
+    let index = index as u32;
+
+    return [
+      index % x_max,
+      index / x_max / z_max % y_max,
+      index / x_max % z_max,
+      index / x_max / z_max / y_max,
+    ];
+  }
+
+  pub fn get(&self, x: u32, y: u32, z: u32, w: u32) -> Option<&Voxel> {
+    return self.index(x, y, z, w).map(|i| &self.voxels[i]);
+  }
+
+  pub fn get_mut(&mut self, x: u32, y: u32, z: u32, w: u32) -> Option<&mut Voxel> {
+    return self.index(x, y, z, w).map(move |i| &mut self.voxels[i]);
+  }
+
+  /**
+   * Sets a voxel; out of bounds coordinates are ignored
+   */
+  pub fn set(&mut self, x: u32, y: u32, z: u32, w: u32, voxel: Voxel) {
+    if let Some(existing) = self.get_mut(x, y, z, w) {
+      *existing = voxel;
+    }
+  }
+
+  /**
+   * The 4D position of the centre of a voxel
+   */
+  pub fn position(&self, x: u32, y: u32, z: u32, w: u32) -> Vector4<f32> {
+    return self.origin + Vector4::new(
+      x as f32,
+      y as f32,
+      z as f32,
+      w as f32,
+    ) * self.spacing;
+  }
+
+  /**
+   * The voxel whose centre is nearest `position`, or None outside the volume
+   */
+  pub fn sample(&self, position: Vector4<f32>) -> Option<&Voxel> {
+    let grid = (position - self.origin) / self.spacing;
+    let mut coordinates = [0; 4];
+    for axis in 0..4 {
+      let coordinate = grid[axis].round();
+      if coordinate < 0.0 || coordinate >= self.dimensions[axis] as f32 {
+        return None;
+      }
+      coordinates[axis] = coordinate as u32;
+    }
+
+    let [x, y, z, w] = coordinates;
+    return self.get(x, y, z, w);
+  }
+
+  /**
+   * The nearest and furthest voxel centres along `direction`, measured from
+   * the 4D origin
+   */
+  pub fn depth_range(&self, direction: Vector4<f32>) -> (f32, f32) {
+    let direction = direction.normalize();
+    let [x_max, y_max, z_max, w_max] = self.dimensions.map(|d| d.saturating_sub(1));
+
+    // The extremes are always at corners of the volume
+    return (0..16u32)
+      .map(|corner| {
+        let pick = |bit: u32, max: u32| if corner & (1 << bit) == 0 { 0 } else { max };
+        return self.position(pick(0, x_max), pick(1, y_max), pick(2, z_max), pick(3, w_max)).dot(direction);
+      })
+      .fold((f32::MAX, f32::MIN), |(min, max), depth| (min.min(depth), max.max(depth)));
+  }
+
+  /**
+   * Iterates all occupied voxels in storage order, with their coordinates
+   */
+  pub fn occupied_voxels(&self) -> impl Iterator<Item = ([u32; 4], &Voxel)> {
+    return self.voxels
+      .iter()
+      .enumerate()
+      .filter(|(_, voxel)| voxel.occupied)
+      .map(|(i, voxel)| (self.coordinates(i), voxel));
+  }
+}
+
+impl Default for HypervoxelVolume {
+  fn default() -> Self {
+    let spacing = DEFAULT_VOLUME_EXTENT / DEFAULT_HYPERVOLUME_DIMENSIONS[0] as f32;
+    return Self::gradient(DEFAULT_HYPERVOLUME_DIMENSIONS, spacing);
+  }
+}
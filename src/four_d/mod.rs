@@ -0,0 +1,9 @@
+pub mod eye_pair;
+pub mod hypershape;
+pub mod hypervoxel_volume;
+pub mod hyperslice;
+pub mod polytope;
+pub mod projection;
+pub mod retina_worker;
+pub mod tesseract_model;
+pub mod transform;
@@ -0,0 +1,395 @@
+use std::f32::consts::TAU;
+
+use cgmath::{InnerSpace, Vector4, Zero};
+
+use super::{hypervoxel_volume::HypervoxelVolume, tesseract_model::TesseractModel};
+
+const GOLDEN_RATIO: f32 = 1.618_034;
+
+/**
+ * How close lengths and depths must be to count as equal, relative to the
+ * polytope's circumradius
+ */
+const TOLERANCE: f32 = 1e-3;
+
+/**
+ * One of the six convex regular 4-polytopes, named after their cell counts
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RegularPolytope {
+  FiveCell,
+  Tesseract,
+  SixteenCell,
+  TwentyFourCell,
+  OneHundredTwentyCell,
+  SixHundredCell,
+}
+
+/**
+ * A 3D face of a polytope: the vertices lying on one of its bounding
+ * hyperplanes
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+  /// The unit normal of the hyperplane, pointing out of the polytope
+  pub normal  : Vector4<f32>,
+
+  /// The distance of the hyperplane from the polytope's centre
+  pub offset  : f32,
+
+  /// The indices of the vertices on the hyperplane, in ascending order
+  pub vertices: Vec<u32>,
+}
+
+/**
+ * A convex 4D polytope centred on the 4D origin
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polytope {
+  pub vertices: Vec<Vector4<f32>>,
+  pub edges   : Vec<[u32; 2]>,
+  pub cells   : Vec<Cell>,
+}
+
+impl RegularPolytope {
+
+  pub const ALL: [RegularPolytope; 6] = [
+    RegularPolytope::FiveCell,
+    RegularPolytope::Tesseract,
+    RegularPolytope::SixteenCell,
+    RegularPolytope::TwentyFourCell,
+    RegularPolytope::OneHundredTwentyCell,
+    RegularPolytope::SixHundredCell,
+  ];
+
+  pub fn polytope(&self) -> Polytope {
+    match self {
+      RegularPolytope::FiveCell => return Polytope::five_cell(),
+      RegularPolytope::Tesseract => return Polytope::tesseract(),
+      RegularPolytope::SixteenCell => return Polytope::sixteen_cell(),
+      RegularPolytope::TwentyFourCell => return Polytope::twenty_four_cell(),
+      RegularPolytope::OneHundredTwentyCell => return Polytope::one_hundred_twenty_cell(),
+      RegularPolytope::SixHundredCell => return Polytope::six_hundred_cell(),
+    }
+  }
+
+  pub fn next(&self) -> Self {
+    match self {
+      RegularPolytope::FiveCell => return RegularPolytope::Tesseract,
+      RegularPolytope::Tesseract => return RegularPolytope::SixteenCell,
+      RegularPolytope::SixteenCell => return RegularPolytope::TwentyFourCell,
+      RegularPolytope::TwentyFourCell => return RegularPolytope::OneHundredTwentyCell,
+      RegularPolytope::OneHundredTwentyCell => return RegularPolytope::SixHundredCell,
+      RegularPolytope::SixHundredCell => return RegularPolytope::FiveCell,
+    }
+  }
+}
+
+impl Polytope {
+
+  /**
+   * Builds a polytope from its vertices and edges, finding its cells from the
+   * directions of their hyperplanes. Each cell is made of the vertices which
+   * are furthest along its direction
+   */
+  pub fn new(vertices: Vec<Vector4<f32>>, edges: Vec<[u32; 2]>, cell_directions: &[Vector4<f32>]) -> Self {
+    let tolerance = TOLERANCE * circumradius(&vertices);
+
+    let cells = cell_directions
+      .iter()
+      .map(|direction| {
+        let normal = direction.normalize();
+        let offset = vertices.iter().map(|vertex| vertex.dot(normal)).fold(f32::MIN, f32::max);
+        let cell_vertices = (0..vertices.len() as u32)
+          .filter(|i| vertices[*i as usize].dot(normal) >= offset - tolerance)
+          .collect();
+
+        return Cell {
+          normal,
+          offset,
+          vertices: cell_vertices,
+        };
+      })
+      .collect();
+
+    return Self {
+      vertices,
+      edges,
+      cells,
+    };
+  }
+
+  /**
+   * The 4D simplex: five vertices, each joined to all the others
+   */
+  pub fn five_cell() -> Self {
+    let w = 1.0 / 5f32.sqrt();
+    let vertices = vec![
+      Vector4::new(1.0, 1.0, 1.0, -w),
+      Vector4::new(1.0, -1.0, -1.0, -w),
+      Vector4::new(-1.0, 1.0, -1.0, -w),
+      Vector4::new(-1.0, -1.0, 1.0, -w),
+      Vector4::new(0.0, 0.0, 0.0, 4.0 * w),
+    ];
+
+    // Each cell is opposite a vertex
+    let cell_directions: Vec<_> = vertices.iter().map(|vertex| -*vertex).collect();
+    return Self::new(vertices.clone(), shortest_edges(&vertices), &cell_directions);
+  }
+
+  /**
+   * The 4D hypercube, with the same vertices as [TesseractModel]
+   */
+  pub fn tesseract() -> Self {
+    let vertices = TesseractModel::unit_vertices().to_vec();
+    return Self::new(vertices.clone(), shortest_edges(&vertices), &axis_vertices());
+  }
+
+  /**
+   * The 4D cross-polytope, the tesseract's dual
+   */
+  pub fn sixteen_cell() -> Self {
+    let vertices = axis_vertices();
+    let cell_directions = TesseractModel::unit_vertices();
+    return Self::new(vertices.clone(), shortest_edges(&vertices), &cell_directions);
+  }
+
+  /**
+   * The 24-cell, made of octahedra, which has no 3D analogue
+   */
+  pub fn twenty_four_cell() -> Self {
+    let vertices = signed_permutations([1.0, 1.0, 0.0, 0.0], false);
+
+    // Its dual is another 24-cell, turned so its vertices point at the cells
+    let mut cell_directions = axis_vertices();
+    cell_directions.extend(TesseractModel::unit_vertices());
+
+    return Self::new(vertices.clone(), shortest_edges(&vertices), &cell_directions);
+  }
+
+  /**
+   * The 600-cell, made of tetrahedra, five around every edge
+   */
+  pub fn six_hundred_cell() -> Self {
+    let half_golden = GOLDEN_RATIO / 2.0;
+    let half_inverse_golden = 1.0 / GOLDEN_RATIO / 2.0;
+
+    let mut vertices = axis_vertices();
+    vertices.extend(TesseractModel::unit_vertices().map(|vertex| vertex / 2.0));
+    vertices.extend(signed_permutations([half_golden, 0.5, half_inverse_golden, 0.0], true));
+
+    let edges = shortest_edges(&vertices);
+    let cell_directions: Vec<_> = tetrahedra(vertices.len(), &edges)
+      .iter()
+      .map(|tetrahedron| centroid(&vertices, tetrahedron))
+      .collect();
+
+    return Self::new(vertices, edges, &cell_directions);
+  }
+
+  /**
+   * The 120-cell, made of dodecahedra, the 600-cell's dual
+   */
+  pub fn one_hundred_twenty_cell() -> Self {
+    let six_hundred_cell = Self::six_hundred_cell();
+
+    // A vertex at the centre of every tetrahedron of the 600-cell, and a cell
+    // around every one of its vertices
+    let vertices: Vec<_> = six_hundred_cell.cells
+      .iter()
+      .map(|cell| centroid(&six_hundred_cell.vertices, &cell.vertices))
+      .collect();
+
+    return Self::new(vertices.clone(), shortest_edges(&vertices), &six_hundred_cell.vertices);
+  }
+
+  /**
+   * The product of a `p`-gon in the XY plane and a `q`-gon in the ZW plane.
+   * Its cells are `p` `q`-gonal prisms and `q` `p`-gonal prisms
+   */
+  pub fn duoprism(p: u32, q: u32) -> Self {
+    let (p, q) = (p.max(3), q.max(3));
+    let polygon_point = |i: f32, sides: u32| {
+      let angle = TAU * i / sides as f32;
+      return (angle.cos(), angle.sin());
+    };
+
+    // Vertex (i, j) is at index i * q + j
+    let mut vertices = Vec::new();
+    let mut edges = Vec::new();
+    for i in 0..p {
+      for j in 0..q {
+        let (x, y) = polygon_point(i as f32, p);
+        let (z, w) = polygon_point(j as f32, q);
+        vertices.push(Vector4::new(x, y, z, w));
+
+        let vertex = i * q + j;
+        edges.push(sorted_edge(vertex, (i + 1) % p * q + j));
+        edges.push(sorted_edge(vertex, i * q + (j + 1) % q));
+      }
+    }
+
+    // Each edge of either polygon sweeps out a prism
+    let mut cell_directions = Vec::new();
+    for i in 0..p {
+      let (x, y) = polygon_point(i as f32 + 0.5, p);
+      cell_directions.push(Vector4::new(x, y, 0.0, 0.0));
+    }
+    for j in 0..q {
+      let (z, w) = polygon_point(j as f32 + 0.5, q);
+      cell_directions.push(Vector4::new(0.0, 0.0, z, w));
+    }
+
+    return Self::new(vertices, edges, &cell_directions);
+  }
+
+  /**
+   * The distance from the centre to the vertices
+   */
+  pub fn circumradius(&self) -> f32 {
+    return circumradius(&self.vertices);
+  }
+
+  /**
+   * Whether a point is inside the polytope or on its boundary
+   */
+  pub fn contains(&self, point: Vector4<f32>) -> bool {
+    let tolerance = TOLERANCE * self.circumradius();
+    return self.cells.iter().all(|cell| point.dot(cell.normal) <= cell.offset + tolerance);
+  }
+
+  /**
+   * Fills a hypervolume with the solid polytope, scaled so its vertices touch
+   * the middle of each side
+   */
+  pub fn voxelize(&self, dimensions: [u32; 4], spacing: f32) -> HypervoxelVolume {
+    let radius = HypervoxelVolume::inner_radius(dimensions, spacing);
+    let scale = self.circumradius() / radius.max(f32::EPSILON);
+    return HypervoxelVolume::from_shape(dimensions, spacing, |point| self.contains(point * scale));
+  }
+}
+
+fn circumradius(vertices: &[Vector4<f32>]) -> f32 {
+  return vertices.iter().map(|vertex| vertex.magnitude()).fold(0.0, f32::max);
+}
+
+fn centroid(vertices: &[Vector4<f32>], indices: &[u32]) -> Vector4<f32> {
+  let sum = indices.iter().fold(Vector4::zero(), |sum, i| sum + vertices[*i as usize]);
+  return sum / indices.len() as f32;
+}
+
+fn sorted_edge(a: u32, b: u32) -> [u32; 2] {
+  return [a.min(b), a.max(b)];
+}
+
+/**
+ * The eight unit vectors along the positive and negative axes
+ */
+fn axis_vertices() -> Vec<Vector4<f32>> {
+  return signed_permutations([1.0, 0.0, 0.0, 0.0], false);
+}
+
+/**
+ * Every arrangement of the coordinates with every combination of signs on
+ * the non-zero ones, without repeats. With `even_only`, only the
+ * arrangements made by an even number of swaps are used
+ */
+fn signed_permutations(coordinates: [f32; 4], even_only: bool) -> Vec<Vector4<f32>> {
+  let mut points: Vec<Vector4<f32>> = Vec::new();
+
+  for order in permutations() {
+    if even_only && order.1 == false {
+      continue;
+    }
+
+    for signs in 0..16 {
+      let point = Vector4::from(std::array::from_fn(|axis| {
+        let coordinate = coordinates[order.0[axis]];
+        if signs & (1 << axis) == 0 {
+          return coordinate;
+        }
+        return -coordinate;
+      }));
+
+      // Negating zero doesn't make a new point
+      let negates_zero = (0..4).any(|axis| signs & (1 << axis) != 0 && point[axis] == 0.0);
+      if negates_zero == false && points.contains(&point) == false {
+        points.push(point);
+      }
+    }
+  }
+
+  return points;
+}
+
+/**
+ * All 24 orderings of four indices, each with whether it's an even
+ * permutation
+ */
+fn permutations() -> Vec<([usize; 4], bool)> {
+  let mut orders = Vec::new();
+  for a in 0..4 {
+    for b in 0..4 {
+      for c in 0..4 {
+        for d in 0..4 {
+          let order = [a, b, c, d];
+          let distinct = (0..4).all(|i| order.contains(&i));
+          if distinct == false {
+            continue;
+          }
+
+          let inversions = (0..4)
+            .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+            .filter(|(i, j)| order[*i] > order[*j])
+            .count();
+          orders.push((order, inversions % 2 == 0));
+        }
+      }
+    }
+  }
+  return orders;
+}
+
+/**
+ * Joins every pair of vertices which are the shortest distance apart, which
+ * are the edges of a regular polytope
+ */
+fn shortest_edges(vertices: &[Vector4<f32>]) -> Vec<[u32; 2]> {
+  let pairs = || (0..vertices.len()).flat_map(|a| (a + 1..vertices.len()).map(move |b| (a, b)));
+  let length = |(a, b): (usize, usize)| (vertices[a] - vertices[b]).magnitude();
+
+  let shortest = pairs().map(length).fold(f32::MAX, f32::min);
+  let tolerance = TOLERANCE * circumradius(vertices);
+
+  return pairs()
+    .filter(|pair| length(*pair) <= shortest + tolerance)
+    .map(|(a, b)| [a as u32, b as u32])
+    .collect();
+}
+
+/**
+ * Every set of four vertices which are all joined to each other
+ */
+fn tetrahedra(vertex_count: usize, edges: &[[u32; 2]]) -> Vec<[u32; 4]> {
+  let mut joined = vec![vec![false; vertex_count]; vertex_count];
+  for [a, b] in edges {
+    joined[*a as usize][*b as usize] = true;
+    joined[*b as usize][*a as usize] = true;
+  }
+
+  let mut tetrahedra = Vec::new();
+  for [a, b] in edges.iter().map(|[a, b]| [*a as usize, *b as usize]) {
+    let shared: Vec<usize> = (b + 1..vertex_count)
+      .filter(|c| joined[a][*c] && joined[b][*c])
+      .collect();
+
+    for (i, c) in shared.iter().enumerate() {
+      for d in &shared[i + 1..] {
+        if joined[*c][*d] {
+          tetrahedra.push([a as u32, b as u32, *c as u32, *d as u32]);
+        }
+      }
+    }
+  }
+  return tetrahedra;
+}
@@ -0,0 +1,52 @@
+use cgmath::{Vector3, Vector4};
+
+/**
+ * How 4D points are flattened into 3D, by looking along the W axis
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection4D {
+  /// Points nearer the viewer along W appear larger, so the two cells of a
+  /// tesseract facing the viewer nest inside each other
+  Perspective {
+    /// The viewer's position on the W axis, looking towards -W
+    viewer_w: f32,
+  },
+
+  /// Drops the W coordinate, so cells at different W overlap exactly
+  Orthographic,
+}
+
+impl Default for Projection4D {
+  fn default() -> Self {
+    return Projection4D::Perspective { viewer_w: 3.0 };
+  }
+}
+
+impl Projection4D {
+
+  /**
+   * Projects a 4D point into 3D. Points at W = 0 keep their size in both
+   * projections
+   */
+  pub fn project(&self, point: Vector4<f32>) -> Vector3<f32> {
+    match self {
+      Projection4D::Perspective { viewer_w } => {
+        // Points at or behind the viewer would flip, so are kept just in front
+        let distance = (viewer_w - point.w).max(f32::EPSILON);
+        return point.truncate() * (viewer_w / distance);
+      },
+
+      Projection4D::Orthographic => return point.truncate(),
+    }
+  }
+
+  /**
+   * Switches between perspective and orthographic projection
+   */
+  pub fn toggled(&self) -> Self {
+    match self {
+      Projection4D::Perspective { .. } => return Projection4D::Orthographic,
+      Projection4D::Orthographic => return Projection4D::default(),
+    }
+  }
+}
@@ -0,0 +1,163 @@
+use std::{
+  sync::{mpsc::{self, Receiver, Sender}, Arc},
+  thread,
+};
+
+use crate::voxel_volume::VoxelVolume;
+
+use super::{eye_pair::EyePair4D, hypervoxel_volume::HypervoxelVolume, transform::Transform4D};
+
+/**
+ * What the retinas are marched for
+ */
+struct RetinaRequest {
+  sequence   : u64,
+  eye_pair   : EyePair4D,
+  orientation: Transform4D,
+  volume     : Arc<HypervoxelVolume>,
+}
+
+/**
+ * Both retinas of a [RetinaRequest], left then right
+ */
+struct MarchedRetinas {
+  sequence: u64,
+  retinas : [VoxelVolume; 2],
+}
+
+/**
+ * Marches the retinas of an [EyePair4D] off the main thread while the
+ * hypervolume turns, since a march casts a ray per retina voxel.
+ *
+ * Requests made while a march is running replace each other, so only the
+ * latest orientation is marched, and a request for what was last requested
+ * is skipped. Where threads aren't available (eg. on the web) the retinas
+ * are marched as they are requested instead
+ */
+pub struct RetinaWorker {
+  volume: Arc<HypervoxelVolume>,
+
+  /// None when the worker thread couldn't be started
+  requests      : Option<Sender<RetinaRequest>>,
+  marched       : Receiver<MarchedRetinas>,
+  marched_sender: Sender<MarchedRetinas>,
+
+  /// The eye pair and orientation last requested or marched
+  last_request: Option<(EyePair4D, Transform4D)>,
+
+  /// Numbers requests in order, so retinas marched for an older request
+  /// never replace newer ones
+  sequence      : u64,
+  shown_sequence: u64,
+}
+
+impl RetinaWorker {
+
+  pub fn new(volume: &HypervoxelVolume) -> Self {
+    let (request_sender, requests) = mpsc::channel();
+    let (marched_sender, marched) = mpsc::channel();
+
+    let worker_sender = marched_sender.clone();
+    let spawned = thread::Builder::new()
+      .name("retina worker".to_string())
+      .spawn(move || Self::run(requests, worker_sender));
+
+    return Self {
+      volume: Arc::new(volume.clone()),
+      requests: spawned.ok().map(|_| request_sender),
+      marched,
+      marched_sender,
+      last_request: None,
+      sequence: 0,
+      shown_sequence: 0,
+    };
+  }
+
+  /**
+   * Replaces the hypervolume the retinas see
+   */
+  pub fn set_volume(&mut self, volume: &HypervoxelVolume) {
+    self.volume = Arc::new(volume.clone());
+    self.last_request = None;
+  }
+
+  /**
+   * Starts marching the retinas for `eye_pair` looking at the hypervolume
+   * turned by `orientation`, unless they were already requested. They are
+   * returned by [RetinaWorker::poll] once marched
+   */
+  pub fn request(&mut self, eye_pair: EyePair4D, orientation: Transform4D) {
+    if self.last_request == Some((eye_pair, orientation)) {
+      return;
+    }
+    self.last_request = Some((eye_pair, orientation));
+    self.sequence += 1;
+
+    let request = RetinaRequest {
+      sequence: self.sequence,
+      eye_pair,
+      orientation,
+      volume: Arc::clone(&self.volume),
+    };
+    match &self.requests {
+      Some(requests) => {
+        if requests.send(request).is_err() {
+          eprintln!("The retina worker stopped, so the retinas aren't updated");
+        }
+      },
+
+      None => {
+        let _ = self.marched_sender.send(request.march());
+      },
+    }
+  }
+
+  /**
+   * Marches the retinas straight away on this thread, replacing any still
+   * being marched, eg. when the eye pair is first shown
+   */
+  pub fn march(&mut self, eye_pair: EyePair4D, orientation: Transform4D) -> [VoxelVolume; 2] {
+    self.last_request = Some((eye_pair, orientation));
+    self.sequence += 1;
+    self.shown_sequence = self.sequence;
+    return eye_pair.retinas(&self.volume, &orientation);
+  }
+
+  /**
+   * The retinas of the latest request marched since the last poll, if any
+   */
+  pub fn poll(&mut self) -> Option<[VoxelVolume; 2]> {
+    let mut latest = None;
+    while let Ok(marched) = self.marched.try_recv() {
+      if marched.sequence > self.shown_sequence {
+        self.shown_sequence = marched.sequence;
+        latest = Some(marched.retinas);
+      }
+    }
+    return latest;
+  }
+
+  fn run(requests: Receiver<RetinaRequest>, marched: Sender<MarchedRetinas>) {
+    while let Ok(mut request) = requests.recv() {
+
+      // Only the latest turn is worth marching
+      while let Ok(newer_request) = requests.try_recv() {
+        request = newer_request;
+      }
+
+      if marched.send(request.march()).is_err() {
+        return;
+      }
+    }
+  }
+}
+
+impl RetinaRequest {
+
+  fn march(&self) -> MarchedRetinas {
+    return MarchedRetinas {
+      sequence: self.sequence,
+      retinas : self.eye_pair.retinas(&self.volume, &self.orientation),
+    };
+  }
+}
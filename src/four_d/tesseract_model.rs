@@ -0,0 +1,198 @@
+use cgmath::{Vector3, Vector4, Zero};
+use wgpu::{Buffer, BufferUsages, Device, Queue, util::{self, DeviceExt}};
+
+use crate::{
+  instance::{Instance, InstanceBuffer},
+  render_pipeline_state::Vertex,
+  voxel_volume::DEFAULT_VOLUME_EXTENT,
+};
+
+use super::{projection::Projection4D, transform::{RotationPlane, Transform4D}};
+
+/**
+ * The edge length of the tesseract, matching the width of the default volume
+ */
+pub const TESSERACT_SIZE: f32 = DEFAULT_VOLUME_EXTENT;
+
+pub const TESSERACT_VERTEX_COUNT: usize = 16;
+pub const TESSERACT_EDGE_COUNT  : usize = 32;
+pub const TESSERACT_FACE_COUNT  : usize = 24;
+
+/**
+ * The opacity of the tesseract's faces, low enough that the cells inside
+ * stay visible
+ */
+const TESSERACT_FACE_ALPHA: u8 = 96;
+
+/**
+ * A 4D hypercube, projected into 3D every frame and drawn through the voxel
+ * pipeline as translucent square faces
+ */
+pub struct TesseractModel {
+  /// The corners of the tesseract, from -1 to 1 on every axis. Bit `k` of a
+  /// vertex's index is set when its coordinate on axis `k` is +1
+  pub vertices  : [Vector4<f32>; TESSERACT_VERTEX_COUNT],
+  pub transform : Transform4D,
+  pub projection: Projection4D,
+
+  pub vertex_buffer  : Buffer,
+  pub index_buffer   : Buffer,
+  pub indices_count  : u32,
+
+  /// A single untransformed instance, since the projected vertices are
+  /// already in world space
+  pub instance_buffer: InstanceBuffer,
+}
+
+impl TesseractModel {
+
+  pub fn new(device: &Device) -> Self {
+    let vertices = Self::unit_vertices();
+    let transform = Transform4D::default();
+    let projection = Projection4D::default();
+
+    let vertex_buffer = device.create_buffer_init(
+      &util::BufferInitDescriptor {
+        label   : Some("Tesseract vertex buffer"),
+        contents: bytemuck::cast_slice(&Self::project_vertices(&vertices, &transform, projection)),
+        usage   : BufferUsages::VERTEX | BufferUsages::COPY_DST,
+      },
+    );
+
+    let indices = Self::face_indices();
+    let index_buffer = device.create_buffer_init(
+      &util::BufferInitDescriptor {
+        label   : Some("Tesseract index buffer"),
+        contents: bytemuck::cast_slice(&indices),
+        usage   : BufferUsages::INDEX,
+      },
+    );
+
+    let mut instance = Instance::at(Vector3::zero());
+    instance.color = [255, 255, 255, TESSERACT_FACE_ALPHA];
+    let instance_buffer = InstanceBuffer::from_instances(device, vec![instance]);
+
+    return Self {
+      vertices,
+      transform,
+      projection,
+      vertex_buffer,
+      index_buffer,
+      indices_count: indices.len() as u32,
+      instance_buffer,
+    };
+  }
+
+  /**
+   * Turns the tesseract by `angle` radians in `plane`
+   */
+  pub fn rotate(&mut self, plane: RotationPlane, angle: f32) {
+    self.transform.rotate(plane, angle);
+  }
+
+  /**
+   * Re-projects the transformed vertices into 3D and uploads them
+   */
+  pub fn update(&self, queue: &Queue) {
+    let projected = Self::project_vertices(&self.vertices, &self.transform, self.projection);
+    queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&projected));
+  }
+
+  /**
+   * The 16 corners of a tesseract from -1 to 1
+   */
+  pub fn unit_vertices() -> [Vector4<f32>; TESSERACT_VERTEX_COUNT] {
+    let coordinate = |vertex: usize, axis: usize| -> f32 {
+      if vertex & (1 << axis) == 0 {
+        return -1.0;
+      }
+      return 1.0;
+    };
+
+    return std::array::from_fn(|vertex| Vector4::new(
+      coordinate(vertex, 0),
+      coordinate(vertex, 1),
+      coordinate(vertex, 2),
+      coordinate(vertex, 3),
+    ));
+  }
+
+  /**
+   * The 32 edges, joining every pair of vertices which differ on one axis
+   */
+  pub fn edges() -> Vec<[u16; 2]> {
+    let mut edges = Vec::with_capacity(TESSERACT_EDGE_COUNT);
+    for vertex in 0..TESSERACT_VERTEX_COUNT as u16 {
+      for axis in 0..4 {
+        if vertex & (1 << axis) == 0 {
+          edges.push([vertex, vertex | (1 << axis)]);
+        }
+      }
+    }
+    return edges;
+  }
+
+  /**
+   * The 24 square faces, one for each pair of axes and each combination of
+   * the other two coordinates. Corners are listed around the square
+   */
+  pub fn faces() -> Vec<[u16; 4]> {
+    let mut faces = Vec::with_capacity(TESSERACT_FACE_COUNT);
+    for first_axis in 0..4 {
+      for second_axis in (first_axis + 1)..4 {
+        let first = 1 << first_axis;
+        let second = 1 << second_axis;
+
+        // Every vertex with neither axis set is the corner of one face
+        for corner in 0..TESSERACT_VERTEX_COUNT as u16 {
+          if corner & (first | second) == 0 {
+            faces.push([corner, corner | first, corner | first | second, corner | second]);
+          }
+        }
+      }
+    }
+    return faces;
+  }
+
+  /**
+   * Two triangles per face, indexed in both windings since projected faces
+   * have no consistent orientation for back-face culling
+   */
+  fn face_indices() -> Vec<u16> {
+    return Self::faces()
+      .iter()
+      .flat_map(|[a, b, c, d]| [
+        *a, *b, *c,
+        *a, *c, *d,
+        *c, *b, *a,
+        *d, *c, *a,
+      ])
+      .collect();
+  }
+
+  /**
+   * Transforms and projects vertices into 3D, and scales them to
+   * [TESSERACT_SIZE]. Vertex colours follow the untransformed 4D position, so
+   * the cells at either end of W can be followed as the tesseract rotates
+   */
+  fn project_vertices(
+    vertices: &[Vector4<f32>],
+    transform: &Transform4D,
+    projection: Projection4D,
+  ) -> Vec<Vertex> {
+    return vertices
+      .iter()
+      .map(|vertex| {
+        let position = projection.project(transform.apply(*vertex)) * (TESSERACT_SIZE / 2.0);
+        return Vertex {
+          position: position.into(),
+          color   : [
+            (vertex.x + 1.0) * 0.25,
+            (vertex.y + 1.0) * 0.25,
+            (vertex.w + 1.0) * 0.25,
+          ],
+        };
+      })
+      .collect();
+  }
+}
@@ -0,0 +1,140 @@
+use cgmath::{InnerSpace, Matrix, Matrix4, SquareMatrix, Vector4, Zero};
+
+/**
+ * How far a 4D object turns each update while a rotation key is held, in
+ * radians
+ */
+pub const KEY_ROTATION_SPEED: f32 = 0.02;
+
+/**
+ * A plane a 4D object can rotate in. Unlike 3D, rotations happen in planes
+ * rather than around axes, and 4D has six of them
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RotationPlane {
+  XY,
+  XZ,
+  YZ,
+  XW,
+  YW,
+  ZW,
+}
+
+/**
+ * Places a 4D object: a rotation followed by a translation
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform4D {
+  pub rotation   : Matrix4<f32>,
+  pub translation: Vector4<f32>,
+}
+
+impl RotationPlane {
+
+  pub const ALL: [RotationPlane; 6] = [
+    RotationPlane::XY,
+    RotationPlane::XZ,
+    RotationPlane::YZ,
+    RotationPlane::XW,
+    RotationPlane::YW,
+    RotationPlane::ZW,
+  ];
+
+  /**
+   * The indices of the two axes spanning the plane. Positive angles turn the
+   * first axis towards the second
+   */
+  pub fn axes(&self) -> (usize, usize) {
+    match self {
+      RotationPlane::XY => return (0, 1),
+      RotationPlane::XZ => return (0, 2),
+      RotationPlane::YZ => return (1, 2),
+      RotationPlane::XW => return (0, 3),
+      RotationPlane::YW => return (1, 3),
+      RotationPlane::ZW => return (2, 3),
+    }
+  }
+
+  /**
+   * A rotation by `angle` radians within this plane, leaving the other two
+   * axes fixed
+   */
+  pub fn rotation(&self, angle: f32) -> Matrix4<f32> {
+    let (first, second) = self.axes();
+    let (sin, cos) = angle.sin_cos();
+
+    // Matrices are indexed [column][row]
+    let mut rotation = Matrix4::identity();
+    rotation[first][first] = cos;
+    rotation[first][second] = sin;
+    rotation[second][first] = -sin;
+    rotation[second][second] = cos;
+    return rotation;
+  }
+}
+
+impl Default for Transform4D {
+  fn default() -> Self {
+    return Self {
+      rotation   : Matrix4::identity(),
+      translation: Vector4::zero(),
+    };
+  }
+}
+
+impl Transform4D {
+
+  /**
+   * Rotates by each angle in the matching plane of [RotationPlane::ALL], in
+   * that order
+   */
+  pub fn from_plane_angles(angles: [f32; 6]) -> Self {
+    let mut transform = Self::default();
+    for (plane, angle) in RotationPlane::ALL.iter().zip(angles) {
+      transform.rotate(*plane, angle);
+    }
+    return transform;
+  }
+
+  /**
+   * Adds a rotation in `plane` on top of the current rotation, eg. a small
+   * step each frame while a key is held
+   */
+  pub fn rotate(&mut self, plane: RotationPlane, angle: f32) {
+    self.rotation = plane.rotation(angle) * self.rotation;
+
+    // Many small rotations accumulate rounding errors which would skew the
+    // object, so the rotation is kept orthonormal
+    self.orthonormalize();
+  }
+
+  /**
+   * Applies the rotation then the translation to a point
+   */
+  pub fn apply(&self, point: Vector4<f32>) -> Vector4<f32> {
+    return self.rotation * point + self.translation;
+  }
+
+  /**
+   * Undoes [Transform4D::apply], mapping a point back into the object's own
+   * space
+   */
+  pub fn inverse_apply(&self, point: Vector4<f32>) -> Vector4<f32> {
+    // The inverse of a rotation is its transpose
+    return self.rotation.transpose() * (point - self.translation);
+  }
+
+  /**
+   * Gram-Schmidt orthonormalisation of the rotation's columns
+   */
+  fn orthonormalize(&mut self) {
+    for column in 0..4 {
+      let mut axis = self.rotation[column];
+      for previous in 0..column {
+        let previous_axis = self.rotation[previous];
+        axis -= previous_axis * axis.dot(previous_axis);
+      }
+      self.rotation[column] = axis.normalize();
+    }
+  }
+}
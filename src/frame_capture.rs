@@ -0,0 +1,198 @@
+use std::{fs::File, io::BufWriter, path::{Path, PathBuf}, sync::mpsc};
+
+use wgpu::{Buffer, BufferUsages, CommandEncoder, Device, SurfaceConfiguration, TextureFormat};
+
+/**
+ * The pixels of a rendered frame, read back from the GPU as tightly packed
+ * 8-bit RGBA rows
+ */
+#[derive(Clone, Debug)]
+pub struct CapturedFrame {
+  pub width : u32,
+  pub height: u32,
+  pub rgba  : Vec<u8>,
+}
+
+/**
+ * A texture-to-buffer copy recorded into a command encoder, which can be read
+ * back once the encoder has been submitted
+ */
+pub struct FrameReadback {
+  buffer              : Buffer,
+  width               : u32,
+  height              : u32,
+  padded_bytes_per_row: u32,
+  format              : TextureFormat,
+}
+
+/**
+ * Tracks the recording of a numbered PNG sequence covering every layer of one
+ * voxel flicker cycle
+ */
+pub struct SequenceRecording {
+  pub directory      : PathBuf,
+  pub layers_total   : u32,
+  pub layers_captured: Vec<bool>,
+}
+
+impl CapturedFrame {
+
+  /**
+   * Writes the frame to `path` as an 8-bit RGBA PNG
+   */
+  pub fn save_png(&self, path: &Path) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&self.rgba)?;
+    return Ok(());
+  }
+}
+
+impl FrameReadback {
+
+  /**
+   * Creates a texture with the same format and size as the render target,
+   * which can be drawn into and copied from when the target itself cannot
+   * (eg. a window surface)
+   */
+  pub fn create_capture_texture(
+    device: &Device,
+    config: &SurfaceConfiguration,
+  ) -> wgpu::Texture {
+    return device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Capture texture"),
+      size: wgpu::Extent3d {
+        width: config.width,
+        height: config.height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: config.format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: Default::default(),
+    });
+  }
+
+  /**
+   * Records a copy of `texture` into a mappable buffer. The texture must have
+   * been created with `COPY_SRC` usage and a 4-byte-per-pixel format
+   */
+  pub fn copy_texture(
+    device: &Device,
+    encoder: &mut CommandEncoder,
+    texture: &wgpu::Texture,
+    config: &SurfaceConfiguration,
+  ) -> Self {
+    let unpadded_bytes_per_row = config.width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+      label             : Some("Frame readback buffer"),
+      size              : (padded_bytes_per_row * config.height) as wgpu::BufferAddress,
+      usage             : BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+      texture.as_image_copy(),
+      wgpu::ImageCopyBuffer {
+        buffer: &buffer,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+          rows_per_image: std::num::NonZeroU32::new(config.height),
+        },
+      },
+      wgpu::Extent3d {
+        width: config.width,
+        height: config.height,
+        depth_or_array_layers: 1,
+      },
+    );
+
+    return Self {
+      buffer,
+      width : config.width,
+      height: config.height,
+      padded_bytes_per_row,
+      format: config.format,
+    };
+  }
+
+  /**
+   * Waits for the GPU to finish the copy, then returns the pixels as RGBA.
+   * Must only be called after the encoder holding the copy was submitted
+   */
+  pub fn read(self, device: &Device) -> CapturedFrame {
+    let slice = self.buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+      sender.send(result).unwrap();
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv().unwrap().expect("Failed to map frame readback buffer");
+
+    let swap_red_blue = matches!(
+      self.format,
+      TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb,
+    );
+
+    let mut rgba = Vec::with_capacity((self.width * self.height * 4) as usize);
+    {
+      let data = slice.get_mapped_range();
+      for row in data.chunks(self.padded_bytes_per_row as usize) {
+        let row = &row[..(self.width * 4) as usize];
+        if swap_red_blue {
+          for pixel in row.chunks_exact(4) {
+            rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+          }
+        } else {
+          rgba.extend_from_slice(row);
+        }
+      }
+    }
+    self.buffer.unmap();
+
+    return CapturedFrame {
+      width : self.width,
+      height: self.height,
+      rgba,
+    };
+  }
+}
+
+impl SequenceRecording {
+
+  pub fn new(directory: PathBuf, layers_total: u32) -> Self {
+    return Self {
+      directory,
+      layers_total,
+      layers_captured: vec![false; layers_total as usize],
+    };
+  }
+
+  /**
+   * The file a given flicker layer is saved to, numbered by its true index
+   */
+  pub fn layer_path(&self, layer_index: u32) -> PathBuf {
+    return self.directory.join(format!("layer_{:04}.png", layer_index));
+  }
+
+  pub fn mark_captured(&mut self, layer_index: u32) {
+    if let Some(captured) = self.layers_captured.get_mut(layer_index as usize) {
+      *captured = true;
+    }
+  }
+
+  pub fn is_complete(&self) -> bool {
+    return self.layers_captured.iter().all(|captured| *captured);
+  }
+}
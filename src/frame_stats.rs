@@ -0,0 +1,228 @@
+use std::{collections::VecDeque, fs::File, io::{self, BufWriter, Write}, path::Path, time::{Duration, Instant}};
+
+/**
+ * The number of frames [FrameStats] keeps, about ten seconds at 240 Hz
+ */
+pub const FRAME_STATS_CAPACITY: usize = 2400;
+
+/**
+ * What happened in one presented frame
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameRecord {
+  /// When the frame was handed to the surface, since the stats were started.
+  /// wgpu doesn't report when frames reach the display, so this is when
+  /// presenting returned
+  pub presented_at: Duration,
+
+  /// The time spent on the CPU advancing the flicker and encoding,
+  /// submitting and presenting the frame
+  pub cpu_time: Duration,
+
+  /// The flicker layer drawn, also with flicker off, or None while every
+  /// layer is drawn at once
+  pub layer: Option<u32>,
+  pub layer_count: u32,
+
+  /// Whether the frame moved on to the next layer, rather than showing the
+  /// last one again
+  pub new_layer: bool,
+
+  /// The layers which came due during the frame besides the one shown, see
+  /// [crate::flicker_clock::FlickerClock::skipped_layers]
+  pub skipped_layers: u32,
+
+  /// The extra vblanks the previous layer was shown for, while locked to
+  /// vblanks, see [crate::flicker_clock::FlickerClock::repeated_layers]
+  pub repeated_layers: u32,
+
+  pub target_volumes_per_second: f32,
+}
+
+/**
+ * Rolling frame pacing telemetry over the last [FRAME_STATS_CAPACITY]
+ * frames, for checking that voxel flicker shows every layer, and how many
+ * volumes per second it achieves against the target
+ */
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+  records : VecDeque<FrameRecord>,
+  capacity: usize,
+
+  /// The number of frames recorded, including those no longer kept
+  frame_count: u64,
+  started    : Instant,
+}
+
+impl Default for FrameStats {
+  fn default() -> Self {
+    return Self::new(FRAME_STATS_CAPACITY);
+  }
+}
+
+impl FrameStats {
+
+  pub fn new(capacity: usize) -> Self {
+    return Self {
+      records    : VecDeque::with_capacity(capacity),
+      capacity   : capacity.max(1),
+      frame_count: 0,
+      started    : Instant::now(),
+    };
+  }
+
+  /**
+   * The time since the stats were started, for [FrameRecord::presented_at]
+   */
+  pub fn elapsed(&self) -> Duration {
+    return self.started.elapsed();
+  }
+
+  /**
+   * Adds a frame, forgetting the oldest one once the stats are full
+   */
+  pub fn record(&mut self, record: FrameRecord) {
+    if self.records.len() == self.capacity {
+      self.records.pop_front();
+    }
+    self.records.push_back(record);
+    self.frame_count += 1;
+  }
+
+  /**
+   * The kept frames, oldest first
+   */
+  pub fn records(&self) -> impl Iterator<Item = &FrameRecord> {
+    return self.records.iter();
+  }
+
+  /**
+   * Stats over only the latest `frames` kept frames, for measuring the
+   * current frame rate rather than the average over all of them
+   */
+  pub fn recent(&self, frames: usize) -> FrameStats {
+    let skipped = self.records.len().saturating_sub(frames);
+    return Self {
+      records    : self.records.iter().skip(skipped).copied().collect(),
+      capacity   : frames.max(1),
+      frame_count: self.frame_count,
+      started    : self.started,
+    };
+  }
+
+  pub fn frame_count(&self) -> u64 {
+    return self.frame_count;
+  }
+
+  /**
+   * The time from the first kept frame being presented to the last
+   */
+  pub fn span(&self) -> Duration {
+    match (self.records.front(), self.records.back()) {
+      (Some(first), Some(last)) => return last.presented_at.saturating_sub(first.presented_at),
+      _ => return Duration::ZERO,
+    }
+  }
+
+  /**
+   * Frames presented per second over the kept frames
+   */
+  pub fn frame_rate(&self) -> f32 {
+    return Self::per_second(self.records.len().saturating_sub(1), self.span());
+  }
+
+  pub fn mean_cpu_time(&self) -> Duration {
+    if self.records.is_empty() {
+      return Duration::ZERO;
+    }
+    let total = self.records.iter().map(|record| record.cpu_time).sum::<Duration>();
+    return total / self.records.len() as u32;
+  }
+
+  pub fn max_cpu_time(&self) -> Duration {
+    return self.records.iter().map(|record| record.cpu_time).max().unwrap_or_default();
+  }
+
+  /**
+   * The total of [FrameRecord::skipped_layers] over the kept frames. None
+   * were skipped if every layer was shown when it was due
+   */
+  pub fn skipped_layers(&self) -> u32 {
+    return self.records.iter().map(|record| record.skipped_layers).sum();
+  }
+
+  /**
+   * The total of [FrameRecord::repeated_layers] over the kept frames. None
+   * were repeated if no vblank was missed
+   */
+  pub fn repeated_layers(&self) -> u32 {
+    return self.records.iter().map(|record| record.repeated_layers).sum();
+  }
+
+  /**
+   * New flicker layers shown per second over the kept frames, not counting
+   * the first frame, which the span starts at
+   */
+  pub fn layers_per_second(&self) -> f32 {
+    let new_layers = self.records.iter().skip(1).filter(|record| record.new_layer).count();
+    return Self::per_second(new_layers, self.span());
+  }
+
+  /**
+   * The volumes per second the flicker actually showed, from the new layers
+   * per second and the latest layer count
+   */
+  pub fn achieved_volumes_per_second(&self) -> f32 {
+    let layer_count = self.records.back().map_or(0, |record| record.layer_count);
+    if layer_count == 0 {
+      return 0.0;
+    }
+    return self.layers_per_second() / layer_count as f32;
+  }
+
+  /**
+   * The volumes per second the flicker was aiming for in the latest frame
+   */
+  pub fn target_volumes_per_second(&self) -> f32 {
+    return self.records.back().map_or(0.0, |record| record.target_volumes_per_second);
+  }
+
+  /**
+   * Writes the kept frames as CSV, one row per frame with times in
+   * milliseconds, and an empty layer while the whole volume is shown
+   */
+  pub fn write_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "frame,presented_at_ms,cpu_time_ms,layer,layer_count,new_layer,skipped_layers,repeated_layers,target_vps")?;
+
+    let first_frame = self.frame_count - self.records.len() as u64;
+    for (index, record) in self.records.iter().enumerate() {
+      writeln!(
+        writer,
+        "{},{:.3},{:.3},{},{},{},{},{},{}",
+        first_frame + index as u64,
+        record.presented_at.as_secs_f64() * 1000.0,
+        record.cpu_time.as_secs_f64() * 1000.0,
+        record.layer.map(|layer| layer.to_string()).unwrap_or_default(),
+        record.layer_count,
+        record.new_layer as u8,
+        record.skipped_layers,
+        record.repeated_layers,
+        record.target_volumes_per_second,
+      )?;
+    }
+    return Ok(());
+  }
+
+  pub fn save_csv(&self, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    self.write_csv(&mut writer)?;
+    return writer.flush();
+  }
+
+  fn per_second(count: usize, span: Duration) -> f32 {
+    if span.is_zero() {
+      return 0.0;
+    }
+    return count as f32 / span.as_secs_f32();
+  }
+}
@@ -4,6 +4,7 @@ use game_loop::winit::event::DeviceEvent;
 use game_loop::winit::event::ElementState;
 use game_loop::winit::event::Event;
 use game_loop::winit::event::KeyboardInput;
+use game_loop::winit::event::ModifiersState;
 use game_loop::winit::event::MouseScrollDelta;
 use game_loop::winit::event::VirtualKeyCode;
 use game_loop::winit::event::WindowEvent;
@@ -11,63 +12,262 @@ use game_loop::winit::window::Window;
 use wgpu::LoadOp;
 use wgpu::Operations;
 use wgpu::SurfaceError;
-use wgpu::TextureViewDescriptor;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::time::Instant;
 
 use crate::camera::camera_state::CameraState;
+use crate::camera::stereo_camera::{StereoCamera, StereoCameraState, StereoEye, StereoMode};
 use crate::cube_model::CubeModel;
-use crate::instance::NUM_INSTANCES_PER_COL;
-use crate::instance::NUM_INSTANCES_PER_ROW;
+use crate::flicker::FlickerConfig;
+use crate::flicker::SliceAxis;
+use crate::flicker::VoxelOrder;
+use crate::flicker_clock::FlickerClock;
+use crate::debug_ui::{DebugSettings, DebugUi};
+use crate::frame_stats::{FrameRecord, FrameStats};
+use crate::hud;
+use crate::hud_state::HudState;
+use crate::four_d::eye_pair::EyePair4D;
+use crate::four_d::retina_worker::RetinaWorker;
+use crate::four_d::hypershape::Hypershape;
+use crate::four_d::projection::Projection4D;
+use crate::four_d::hyperslice::HyperSlice;
+use crate::four_d::hyperslice::Hyperslicer;
+use crate::four_d::hypervoxel_volume::HypervoxelVolume;
+use crate::four_d::tesseract_model::TesseractModel;
+use crate::four_d::transform::KEY_ROTATION_SPEED;
+use crate::four_d::transform::RotationPlane;
+use crate::frame_capture::CapturedFrame;
+use crate::frame_capture::FrameReadback;
+use crate::frame_capture::SequenceRecording;
+use crate::instance::Instance;
+use crate::instance::InstanceBuffer;
+use crate::oit_state::OitState;
+use crate::presentation::{self, PresentationConfig};
+use crate::ray_march_state::RayMarchState;
+use crate::render_pipeline_state::RenderBackend;
+use crate::render_pipeline_state::RenderMode;
 use crate::render_pipeline_state::RenderPipelineState;
 use crate::render_pipeline_state::Texture;
 use crate::render_state::RenderState;
+use crate::render_state::RenderTarget;
+use crate::retina_view::RetinaView;
+use crate::transfer_function::TransferFunction;
+use crate::transfer_function::TransferFunctionPreset;
+use crate::transfer_function_state::TransferFunctionState;
+use crate::voxel_volume::VoxelVolume;
+
+/**
+ * The colour frames are cleared to behind the voxels
+ */
+pub const DEFAULT_BACKGROUND_COLOR: wgpu::Color = wgpu::Color {
+  r: 0.1,
+  g: 0.2,
+  b: 0.3,
+  a: 1.0,
+};
 
 pub struct GameState {
   render_state: RenderState,
   camera_state: CameraState,
+  stereo_camera_state: StereoCameraState,
   render_pipeline_state: RenderPipelineState,
+  oit_state: OitState,
+  render_mode: RenderMode,
+  ray_march_state: RayMarchState,
+  render_backend: RenderBackend,
+  transfer_function_state: TransferFunctionState,
+
+  /// Colours voxels by their scalar values, instead of their own colours
+  transfer_function: Option<TransferFunction>,
+
+  /// The preset the transfer function was picked from with key 7, if any
+  transfer_function_preset: Option<TransferFunctionPreset>,
   cube_model: CubeModel,
+  tesseract_model: TesseractModel,
+  volume: VoxelVolume,
+  show_tesseract: bool,
+  hyperslicer: Hyperslicer,
+  hypershape: Hypershape,
+  enable_hyperslicing: bool,
+
+  eye_pair: EyePair4D,
+
+  /// Marches the retinas while the hypervolume turns
+  retina_worker: RetinaWorker,
+
+  /// The right eye's retina, shown beside the main view (the left eye's)
+  /// while the 4D eye pair is looking at the hypervolume
+  right_retina: Option<RetinaView>,
+
+  /// The 3D volume to go back to when hyperslicing or the eye pair is turned
+  /// off
+  volume_before_4d: Option<VoxelVolume>,
+
+  /// The direction the shown 4D object is turning in each of
+  /// [RotationPlane::ALL], while the plane's key is held
+  spin_4d: [f32; 6],
+  modifiers: ModifiersState,
   enable_voxel_flicker: bool,
+
+  /// Decides when the next flicker layer is shown, in real time
+  flicker_clock: FlickerClock,
+
+  /// Whether the flicker clock should follow the refresh rate of whichever
+  /// monitor the window is on
+  lock_to_vblank: bool,
+
+  /// When the last frame was presented, for timing the flicker
+  last_frame_time: Option<Instant>,
+
+  /// Pacing of the recently presented frames and the layers they showed
+  frame_stats: FrameStats,
+  hud_state: HudState,
+  show_hud: bool,
+  debug_ui: DebugUi,
+  show_debug_ui: bool,
+  background_color: wgpu::Color,
   mouse_left_pressed: bool,
+  cursor_x: f64,
+  screenshot_path: Option<PathBuf>,
+  sequence_recording: Option<SequenceRecording>,
 }
 
 impl GameState {
 
-  pub async fn new(window: &Window) -> Self {
-    let render_state = RenderState::new(&window).await;
-    let camera_state = CameraState::new(&render_state.device, &window);
+  pub async fn new(window: &Window, presentation: PresentationConfig) -> Self {
+    let render_state = RenderState::new(window, &presentation).await;
+    let mut game_state = Self::from_render_state(render_state);
+    game_state.debug_ui.set_pixels_per_point(window.scale_factor() as f32);
+
+    let present_mode = game_state.render_state.config.present_mode;
+    if presentation.locks_flicker_to_vblank(present_mode) {
+      game_state.lock_to_vblank = true;
+      game_state.lock_flicker_to_refresh_rate();
+    } else if presentation.lock_flicker_to_vblank {
+      eprintln!("Flicker can only be locked to vblanks with Fifo presentation, not {:?}", present_mode);
+    }
+
+    return game_state;
+  }
+
+  /**
+   * Creates a [GameState] which renders into an offscreen texture of the
+   * given size, for producing frames without a window
+   */
+  pub async fn new_headless(size: PhysicalSize<u32>) -> Self {
+    let render_state = RenderState::new_headless(size).await;
+    return Self::from_render_state(render_state);
+  }
+
+  fn from_render_state(render_state: RenderState) -> Self {
+    let camera_state = CameraState::new(&render_state.device, render_state.size);
+    let stereo_camera_state = StereoCameraState::new(
+      &render_state.device,
+      &camera_state.camera_bind_group_layout,
+    );
+    let volume = VoxelVolume::default();
+    let transfer_function_state = TransferFunctionState::new(&render_state.device);
 
     let render_pipeline_state = RenderPipelineState::new(
       &render_state.device,
       &camera_state.camera_bind_group_layout,
+      &transfer_function_state.bind_group_layout,
       &render_state.config,
+      &volume,
+      camera_state.view_point(),
     );
 
+    let oit_state = OitState::new(&render_state.device, &render_state.config);
+    let render_mode = RenderMode::Opaque;
+
+    let ray_march_state = RayMarchState::new(
+      &render_state.device,
+      &render_state.queue,
+      &camera_state.camera_bind_group_layout,
+      &transfer_function_state.bind_group_layout,
+      &render_state.config,
+      &volume,
+    );
+    let render_backend = RenderBackend::InstancedCubes;
+    let transfer_function = None;
+    let transfer_function_preset = None;
+
     let cube_model = CubeModel::new(&render_state.device);
+    let tesseract_model = TesseractModel::new(&render_state.device);
 
+    let show_tesseract = false;
+    let hyperslicer = Hyperslicer::default();
+    let hypershape = Hypershape::default();
+    let enable_hyperslicing = false;
+    let eye_pair = EyePair4D::default();
+    let retina_worker = RetinaWorker::new(&hyperslicer.volume);
+    let right_retina = None;
+    let volume_before_4d = None;
+    let spin_4d = [0.0; 6];
+    let modifiers = ModifiersState::empty();
     let enable_voxel_flicker = false;
+    let flicker_clock = FlickerClock::default();
+    let lock_to_vblank = false;
+    let last_frame_time = None;
+    let frame_stats = FrameStats::default();
+    let hud_state = HudState::new(&render_state.device, &render_state.config);
+    let show_hud = true;
+    let debug_ui = DebugUi::new(&render_state.device, render_state.config.format);
+    let show_debug_ui = false;
+    let background_color = DEFAULT_BACKGROUND_COLOR;
     let mouse_left_pressed = false;
+    let cursor_x = 0.0;
+    let screenshot_path = None;
+    let sequence_recording = None;
 
     return Self {
       render_state,
       camera_state,
+      stereo_camera_state,
       render_pipeline_state,
+      oit_state,
+      render_mode,
+      ray_march_state,
+      render_backend,
+      transfer_function_state,
+      transfer_function,
+      transfer_function_preset,
       cube_model,
+      tesseract_model,
+      volume,
+      show_tesseract,
+      hyperslicer,
+      hypershape,
+      enable_hyperslicing,
+      eye_pair,
+      retina_worker,
+      right_retina,
+      volume_before_4d,
+      spin_4d,
+      modifiers,
       enable_voxel_flicker,
+      flicker_clock,
+      lock_to_vblank,
+      last_frame_time,
+      frame_stats,
+      hud_state,
+      show_hud,
+      debug_ui,
+      show_debug_ui,
+      background_color,
       mouse_left_pressed,
+      cursor_x,
+      screenshot_path,
+      sequence_recording,
     }
   }
 
   fn resize(&mut self, new_size: PhysicalSize<u32>) {
 
     if new_size.width > 0 && new_size.height > 0 {
-      self.render_state.size = new_size;
-      self.render_state.config.width = new_size.width;
-      self.render_state.config.height = new_size.height;
-
-      self.render_state.surface.configure(
-        &self.render_state.device,
-        &self.render_state.config
-      );
+      self.render_state.resize(new_size);
     }
 
     self.render_pipeline_state.depth_texture = Texture::create_depth_texture(
@@ -75,17 +275,24 @@ impl GameState {
       &self.render_state.config,
       "depth_texture",
     );
+    self.oit_state.resize(&self.render_state.device, &self.render_state.config);
   }
 
+  #[allow(clippy::collapsible_match)]
   pub fn input(
     &mut self,
     event: &Event<()>,
     window: &Window,
   ) -> bool {
-    self.camera_state.camera_controller.process_events(
+    if self.show_debug_ui && self.debug_ui.handle_event(event) {
+      return true;
+    }
+
+    let camera_state = self.camera_state_under_cursor();
+    camera_state.camera_controller.process_events(
       event,
       window,
-      &mut self.camera_state.camera,
+      &mut camera_state.camera,
     );
 
     match event {
@@ -102,10 +309,263 @@ impl GameState {
             },
             ..
           } => {
-            self.enable_voxel_flicker = !self.enable_voxel_flicker;
+            self.set_voxel_flicker(self.enable_voxel_flicker == false);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(key @ (VirtualKeyCode::Key1 | VirtualKeyCode::Key2)),
+              ..
+            },
+            ..
+          } => {
+            // Shows the volume one time a second slower or faster
+            let step = if *key == VirtualKeyCode::Key1 { -1.0 } else { 1.0 };
+            self.set_volumes_per_second(self.flicker_clock.volumes_per_second() + step);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::T),
+              ..
+            },
+            ..
+          } => {
+            self.show_tesseract = !self.show_tesseract;
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key4),
+              ..
+            },
+            ..
+          } => {
+            self.tesseract_model.projection = self.tesseract_model.projection.toggled();
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key3),
+              ..
+            },
+            ..
+          } => {
+            self.set_stereo_mode(self.stereo_camera_state.mode.next());
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key5),
+              ..
+            },
+            ..
+          } => {
+            self.render_mode = self.render_mode.next();
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key6),
+              ..
+            },
+            ..
+          } => {
+            self.set_render_backend(self.render_backend.next());
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key7),
+              ..
+            },
+            ..
+          } => {
+            // Goes through each preset, then back to the voxels' own colours
+            let preset = match self.transfer_function_preset {
+              None => Some(TransferFunctionPreset::Greyscale),
+              Some(TransferFunctionPreset::Rainbow) => None,
+              Some(preset) => Some(preset.next()),
+            };
+            self.set_transfer_function_preset(preset);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key8),
+              ..
+            },
+            ..
+          } => {
+            self.set_show_hud(self.show_hud == false);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::Key9),
+              ..
+            },
+            ..
+          } => {
+            self.set_show_debug_ui(self.show_debug_ui == false);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::H),
+              ..
+            },
+            ..
+          } => {
+            self.set_hyperslicing(self.enable_hyperslicing == false);
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::B),
+              ..
+            },
+            ..
+          } => {
+            self.set_binocular(self.right_retina.is_none());
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::W),
+              ..
+            },
+            ..
+          } => {
+            self.hyperslicer.animate = !self.hyperslicer.animate;
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::G),
+              ..
+            },
+            ..
+          } => {
+            self.set_hypershape(self.hypershape.next());
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(key @ (VirtualKeyCode::LBracket | VirtualKeyCode::RBracket)),
+              ..
+            },
+            ..
+          } => {
+            // Steps the hyperplane one voxel along W
+            let direction = if *key == VirtualKeyCode::LBracket { -1.0 } else { 1.0 };
+            self.hyperslicer.step_w(direction * self.hyperslicer.volume.spacing);
+            if self.enable_hyperslicing {
+              self.show_hyperslice();
+            }
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::P),
+              ..
+            },
+            ..
+          } => {
+            self.request_screenshot(PathBuf::from(format!(
+              "screenshot_{}.png",
+              Self::capture_timestamp(),
+            )));
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::R),
+              ..
+            },
+            ..
+          } => {
+            self.record_flicker_cycle(PathBuf::from(format!(
+              "flicker_cycle_{}",
+              Self::capture_timestamp(),
+            )));
+            return true;
+          },
+
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state: ElementState::Pressed,
+              virtual_keycode: Some(VirtualKeyCode::F),
+              ..
+            },
+            ..
+          } => {
+            self.save_frame_stats(&PathBuf::from(format!(
+              "frame_stats_{}.csv",
+              Self::capture_timestamp(),
+            )));
             return true;
           },
 
+          WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+              state,
+              virtual_keycode: Some(key),
+              ..
+            },
+            ..
+          } => {
+            if self.handle_rotation_key(*key, *state) {
+              return true;
+            }
+            if *state == ElementState::Pressed {
+              return self.handle_flicker_key(*key);
+            }
+            return false;
+          },
+
+          WindowEvent::ModifiersChanged(modifiers) => {
+            self.modifiers = *modifiers;
+            return false;
+          },
+
+          WindowEvent::CursorMoved { position, .. } => {
+            self.cursor_x = position.x;
+            return false;
+          },
+
           _ => return false
         }
       },
@@ -132,11 +592,12 @@ impl GameState {
           DeviceEvent::MouseMotion {
             delta,
           } => if self.mouse_left_pressed {
-            self.camera_state.camera.add_yaw(
-              -delta.0 as f32 * self.camera_state.camera_controller.rotate_speed
+            let camera_state = self.camera_state_under_cursor();
+            camera_state.camera.add_yaw(
+              -delta.0 as f32 * camera_state.camera_controller.rotate_speed
             );
-            self.camera_state.camera.add_pitch(
-              delta.1 as f32 * self.camera_state.camera_controller.rotate_speed
+            camera_state.camera.add_pitch(
+              delta.1 as f32 * camera_state.camera_controller.rotate_speed
             );
             return true;
 
@@ -156,8 +617,9 @@ impl GameState {
               },
             };
 
-            self.camera_state.camera.add_distance(
-              scroll_amount * self.camera_state.camera_controller.zoom_speed
+            let camera_state = self.camera_state_under_cursor();
+            camera_state.camera.add_distance(
+              scroll_amount * camera_state.camera_controller.zoom_speed
             );
             return true;
           },
@@ -171,13 +633,30 @@ impl GameState {
   }
 
   pub fn update(&mut self) {
+    let aspect = self.viewport_aspect();
+    self.camera_state.camera.aspect = aspect;
     self.camera_state.camera.update();
 
     self.camera_state.camera_uniform
       .update_view_proj(&self.camera_state.camera);
 
-    if self.enable_voxel_flicker {
-      self.iterate_volume_plane_instances_to_render();
+    if self.stereo_camera_state.mode != StereoMode::Off {
+      self.stereo_camera_state.update(&self.render_state.queue, &self.camera_state.camera);
+    }
+
+    self.update_4d_objects();
+
+    let view_point = self.camera_state.view_point();
+    if self.render_pipeline_state.voxel_scheduler.needs_rescheduling(view_point) {
+      self.reschedule_camera_facing_voxels();
+    }
+
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.update(
+        &self.render_state.device,
+        &self.render_state.queue,
+        aspect,
+      );
     }
 
     self.render_state.queue.write_buffer(
@@ -187,11 +666,59 @@ impl GameState {
     );
   }
 
+  /**
+   * Turns the shown 4D object while rotation keys are held, and re-projects
+   * or re-slices it
+   */
+  fn update_4d_objects(&mut self) {
+    let spinning = self.spin_4d.iter().any(|spin| *spin != 0.0);
+    if spinning {
+      for (plane, spin) in RotationPlane::ALL.iter().zip(self.spin_4d) {
+        if self.show_tesseract {
+          self.tesseract_model.rotate(*plane, spin * KEY_ROTATION_SPEED);
+        } else if self.enable_hyperslicing || self.right_retina.is_some() {
+          self.hyperslicer.slice.orientation.rotate(*plane, spin * KEY_ROTATION_SPEED);
+        }
+      }
+    }
+
+    if self.show_tesseract {
+      self.tesseract_model.update(&self.render_state.queue);
+    } else if self.right_retina.is_some() {
+      if spinning {
+        self.retina_worker.request(self.eye_pair, self.hyperslicer.slice.orientation);
+      }
+      if let Some(retinas) = self.retina_worker.poll() {
+        self.show_retinas(retinas);
+      }
+    } else if self.enable_hyperslicing {
+      let w_moved = self.hyperslicer.update();
+      if w_moved || spinning {
+        self.show_hyperslice();
+      }
+    }
+  }
+
   /**
    * Returns false on unrecoverable error
    */
   pub fn render (&mut self) -> bool {
-    match self.prerender() {
+    let frame_start = Instant::now();
+    if self.show_debug_ui {
+      self.run_debug_ui();
+    }
+    let new_layer = self.advance_flicker();
+
+    // Recordings capture each layer once, when it's first shown
+    let capture_requested = self.screenshot_path.is_some()
+      || (self.sequence_recording.is_some() && new_layer);
+
+    let rendered = self.prerender(capture_requested);
+    if rendered.is_ok() {
+      self.record_frame_stats(frame_start, new_layer);
+    }
+
+    match rendered {
 
       // Reconfigure the surface if lost
       Err(wgpu::SurfaceError::Lost) => {
@@ -207,86 +734,454 @@ impl GameState {
       Err(e) => {
         eprintln!("{:?}", e);
       },
-      
-      Ok(_) => {},
+
+      Ok(Some(frame)) => {
+        self.save_captured_frame(&frame, new_layer);
+      },
+
+      Ok(None) => {},
     }
 
     return true;
   }
 
-  fn prerender (&mut self) -> Result<(), SurfaceError> {
-    let output = self.render_state.surface.get_current_texture()?;
-    let view = output.texture.create_view(&TextureViewDescriptor::default());
+  /**
+   * Renders a frame and reads it back from the GPU, without saving it
+   */
+  pub fn capture_frame(&mut self) -> Result<CapturedFrame, SurfaceError> {
+    let frame = self.prerender(true)?;
+    return Ok(frame.expect("A captured frame is always read back"));
+  }
 
-    let mut encoder = self.render_state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-      label: Some("Render Encoder"),
-    });
+  /**
+   * Saves the next rendered frame to `path` as a PNG
+   */
+  pub fn request_screenshot(&mut self, path: PathBuf) {
+    self.screenshot_path = Some(path);
+  }
 
-    // Since `begin_render_pass()` borrows `encoder` mutably (aka &mut self),
-    // we need to use a scoped block to release this mutable borrow, to call
-    // encoder.finish()
-    {
-      let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-        label: Some("Render Pass"),
-
-        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-          view          : &view,
-          resolve_target: None,
-
-          ops: wgpu::Operations {
-            load: wgpu::LoadOp::Clear(wgpu::Color {
-              r: 0.1,
-              g: 0.2,
-              b: 0.3,
-              a: 1.0,
-            }),
-
-            store: true,
-          },
-        })],
+  /**
+   * Saves every layer of the next full voxel flicker cycle into `directory`
+   * as a numbered PNG sequence. Enables voxel flicker for the recording,
+   * which is cancelled if flicker is turned off or the layers are
+   * rescheduled before it completes
+   */
+  pub fn record_flicker_cycle(&mut self, directory: PathBuf) {
+    if let Err(e) = std::fs::create_dir_all(&directory) {
+      eprintln!("{:?}", e);
+      return;
+    }
+
+    self.set_voxel_flicker(true);
+    self.sequence_recording = Some(SequenceRecording::new(
+      directory,
+      self.flicker_layer_count(),
+    ));
+  }
+
+  pub fn is_recording(&self) -> bool {
+    return self.sequence_recording.is_some();
+  }
+
+  fn cancel_sequence_recording(&mut self, reason: &str) {
+    if self.sequence_recording.take().is_some() {
+      eprintln!("Cancelled recording the flicker cycle, as {}", reason);
+    }
+  }
+
+  fn prerender (&mut self, capture: bool) -> Result<Option<CapturedFrame>, SurfaceError> {
+    let output = self.render_state.acquire_frame()?;
+
+    // The rays are clipped to the flicker layer shown in this frame
+    if self.ray_marching() {
+      let window = self.render_pipeline_state.voxel_scheduler.depth_window(
+        &self.volume,
+        self.camera_state.view_point(),
+      );
+      self.ray_march_state.update(&self.render_state.queue, &self.volume, &window);
+    }
+
+    let mut encoder = self.render_state.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("Render Encoder"),
+    });
+
+    self.encode_voxel_pass(&mut encoder, &output.view);
+
+    let readback = if capture == false {
+      None
+    } else {
+      match &self.render_state.target {
+        // Offscreen frames can be copied from directly
+        RenderTarget::Offscreen(texture) => Some(FrameReadback::copy_texture(
+          &self.render_state.device,
+          &mut encoder,
+          texture,
+          &self.render_state.config,
+        )),
+
+        // Surface textures can't be copied from on every platform, so the
+        // same frame is drawn again into a texture which can
+        RenderTarget::Surface(_) => {
+          let capture_texture = FrameReadback::create_capture_texture(
+            &self.render_state.device,
+            &self.render_state.config,
+          );
+          let capture_view = capture_texture.create_view(&wgpu::TextureViewDescriptor::default());
+          self.encode_voxel_pass(&mut encoder, &capture_view);
+
+          Some(FrameReadback::copy_texture(
+            &self.render_state.device,
+            &mut encoder,
+            &capture_texture,
+            &self.render_state.config,
+          ))
+        },
+      }
+    };
+
+    // Drawn after the frame is copied for capturing, so screenshots and
+    // recorded flicker cycles show only the voxels
+    if self.show_hud {
+      let size = self.render_state.size;
+      self.hud_state.update(
+        &self.render_state.device,
+        &self.render_state.queue,
+        &self.hud_lines(),
+        [size.width, size.height],
+      );
+      self.hud_state.encode(&mut encoder, &output.view);
+    }
+    if self.show_debug_ui {
+      let size = self.render_state.size;
+      self.debug_ui.encode(
+        &self.render_state.device,
+        &self.render_state.queue,
+        &mut encoder,
+        &output.view,
+        [size.width, size.height],
+      );
+    }
+
+    self.render_state.queue.submit(std::iter::once(encoder.finish()));
+    output.present();
+
+    let captured_frame = readback.map(|readback| {
+      readback.read(&self.render_state.device)
+    });
+    return Ok(captured_frame);
+  }
+
+  fn encode_voxel_pass(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    if self.stereo_camera_state.mode == StereoMode::Anaglyph {
+      // Each eye is drawn over the whole frame in its own colours, so the
+      // depth is cleared in between
+      for eye in StereoEye::ALL {
+        self.encode_views(encoder, view, eye == StereoEye::Left, Some(eye));
+      }
+      return;
+    }
+
+    self.encode_views(encoder, view, true, None);
+  }
+
+  /**
+   * Draws the views into `view` the way the [RenderMode] combines voxels, or
+   * by ray marching. With `anaglyph_eye`, only that eye is drawn, in its own
+   * colours
+   */
+  fn encode_views(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    clear_color: bool,
+    anaglyph_eye: Option<StereoEye>,
+  ) {
+    if self.ray_marching() {
+      let mut render_pass = self.begin_voxel_pass(encoder, view, clear_color);
+      render_pass.set_pipeline(self.ray_march_state.pipelines.for_eye(anaglyph_eye));
+      self.draw_views(&mut render_pass, anaglyph_eye);
+      return;
+    }
+
+    let pipeline = self.render_pipeline_state.pipeline(self.render_mode, anaglyph_eye);
+
+    if self.render_mode != RenderMode::WeightedBlended {
+      let mut render_pass = self.begin_voxel_pass(encoder, view, clear_color);
+      render_pass.set_pipeline(pipeline);
+      self.draw_views(&mut render_pass, anaglyph_eye);
+      return;
+    }
+
+    {
+      let mut render_pass = self.oit_state.begin_accumulate_pass(encoder);
+      render_pass.set_pipeline(pipeline);
+      self.draw_views(&mut render_pass, anaglyph_eye);
+    }
+
+    let mut render_pass = self.begin_voxel_pass(encoder, view, clear_color);
+    render_pass.set_pipeline(match anaglyph_eye {
+      Some(eye) => &self.oit_state.anaglyph_composite_pipelines[eye as usize],
+      None => &self.oit_state.composite_pipeline,
+    });
+    self.oit_state.composite(&mut render_pass);
+  }
 
-        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-          view: &self.render_pipeline_state.depth_texture.view,
+  /**
+   * Draws the scene into each view the frame is split into, with a pipeline
+   * already set
+   */
+  fn draw_views<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    anaglyph_eye: Option<StereoEye>,
+  ) {
+    if let Some(eye) = anaglyph_eye {
+      self.draw_scene(render_pass, self.stereo_camera_state.bind_group(eye));
+      return;
+    }
+
+    if let (Some(right_retina), false) = (&self.right_retina, self.show_tesseract) {
+      // Each eye's retina takes half of the frame, left eye on the left
+      let half_width = self.render_state.config.width as f32 / 2.0;
+      let height = self.render_state.config.height as f32;
+
+      render_pass.set_viewport(0.0, 0.0, half_width, height, 0.0, 1.0);
+      self.draw_voxels(
+        render_pass,
+        &self.camera_state.camera_bind_group,
+        &self.render_pipeline_state.instance_buffer,
+        self.render_pipeline_state.voxel_scheduler.instances_to_render(),
+      );
+
+      render_pass.set_viewport(half_width, 0.0, half_width, height, 0.0, 1.0);
+      self.draw_voxels(
+        render_pass,
+        &right_retina.camera_state.camera_bind_group,
+        &right_retina.instance_buffer,
+        right_retina.voxel_scheduler.instances_to_render(),
+      );
+      return;
+    }
+
+    if self.stereo_camera_state.mode != StereoMode::Off {
+      for eye in StereoEye::ALL {
+        let [x, y, width, height] = self.stereo_camera_state.mode.viewport(eye, self.render_state.size);
+        render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+        self.draw_scene(render_pass, self.stereo_camera_state.bind_group(eye));
+      }
+      return;
+    }
+
+    self.draw_scene(render_pass, &self.camera_state.camera_bind_group);
+  }
 
-          depth_ops: Some(Operations {
-            load: LoadOp::Clear(1.0),
-            store: true,
-          }),
+  /**
+   * Starts a pass drawing into `view`, clearing the depth, and the colour if
+   * `clear_color` is set
+   */
+  fn begin_voxel_pass<'a>(
+    &'a self,
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    clear_color: bool,
+  ) -> wgpu::RenderPass<'a> {
+    let color_load = if clear_color {
+      wgpu::LoadOp::Clear(self.background_color)
+    } else {
+      wgpu::LoadOp::Load
+    };
+
+    // Since `begin_render_pass()` borrows `encoder` mutably (aka &mut self),
+    // the pass must be dropped before `encoder.finish()` can be called
+    return encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("Render Pass"),
+
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
 
-          stencil_ops : None,
+        ops: wgpu::Operations {
+          load: color_load,
+          store: true,
+        },
+      })],
+
+      depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+        view: &self.render_pipeline_state.depth_texture.view,
+
+        depth_ops: Some(Operations {
+          load: LoadOp::Clear(1.0),
+          store: true,
         }),
-      });
 
-      render_pass.set_pipeline(&self.render_pipeline_state.render_pipeline);
+        stencil_ops : None,
+      }),
+    });
+  }
 
+  /**
+   * Draws the tesseract or the volume, seen through a camera
+   */
+  fn draw_scene<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    camera_bind_group: &'a wgpu::BindGroup,
+  ) {
+    if self.show_tesseract {
       render_pass.set_bind_group(
         0,
-        &self.camera_state.camera_bind_group,
+        camera_bind_group,
+        &[],
+      );
+      render_pass.set_bind_group(
+        1,
+        &self.transfer_function_state.passthrough_bind_group,
         &[],
       );
-
       render_pass.set_vertex_buffer(
         0,
-        self.cube_model.cube_vertex_buffer.slice(..),
+        self.tesseract_model.vertex_buffer.slice(..),
       );
       render_pass.set_vertex_buffer(
         1,
-        self.render_pipeline_state.instance_buffer.buffer.slice(..),
+        self.tesseract_model.instance_buffer.buffer.slice(..),
       );
       render_pass.set_index_buffer(
-        self.cube_model.cube_index_buffer.slice(..),
+        self.tesseract_model.index_buffer.slice(..),
         wgpu::IndexFormat::Uint16,
       );
       render_pass.draw_indexed(
-        0..self.cube_model.cube_indices_count,
+        0..self.tesseract_model.indices_count,
         0,
-        self.render_pipeline_state.instances_to_render_start..self.render_pipeline_state.instances_to_render_end,
+        0..1,
       );
+      return;
     }
 
-    self.render_state.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
-    return Ok(());
+    if self.ray_marching() {
+      render_pass.set_bind_group(
+        2,
+        &self.transfer_function_state.bind_group,
+        &[],
+      );
+      self.ray_march_state.draw(render_pass, camera_bind_group);
+      return;
+    }
+
+    self.draw_voxels(
+      render_pass,
+      camera_bind_group,
+      &self.render_pipeline_state.instance_buffer,
+      self.render_pipeline_state.voxel_scheduler.instances_to_render(),
+    );
+  }
+
+  /**
+   * Draws a range of voxel instances as cubes, seen through a camera
+   */
+  fn draw_voxels<'a>(
+    &'a self,
+    render_pass: &mut wgpu::RenderPass<'a>,
+    camera_bind_group: &'a wgpu::BindGroup,
+    instance_buffer: &'a InstanceBuffer,
+    instances: Range<u32>,
+  ) {
+    render_pass.set_bind_group(
+      0,
+      camera_bind_group,
+      &[],
+    );
+    render_pass.set_bind_group(
+      1,
+      &self.transfer_function_state.bind_group,
+      &[],
+    );
+
+    render_pass.set_vertex_buffer(
+      0,
+      self.cube_model.cube_vertex_buffer.slice(..),
+    );
+    render_pass.set_vertex_buffer(
+      1,
+      instance_buffer.buffer.slice(..),
+    );
+    render_pass.set_index_buffer(
+      self.cube_model.cube_index_buffer.slice(..),
+      wgpu::IndexFormat::Uint16,
+    );
+    render_pass.draw_indexed(
+      0..self.cube_model.cube_indices_count,
+      0,
+      instances,
+    );
+  }
+
+  /**
+   * Whether the volume is ray marched this frame. The tesseract is always
+   * drawn as cubes, and so are both retinas of the 4D eye pair, since only
+   * the main volume is uploaded for ray marching
+   */
+  fn ray_marching(&self) -> bool {
+    return self.render_backend == RenderBackend::RayMarched
+      && self.show_tesseract == false
+      && self.right_retina.is_none();
+  }
+
+  /**
+   * The aspect ratio of each camera's viewport, which is half the frame when
+   * both retinas or both stereo eyes are shown side by side
+   */
+  fn viewport_aspect(&self) -> f32 {
+    let [_, _, mut width, height] = self.stereo_camera_state.mode.viewport(StereoEye::Left, self.render_state.size);
+    if self.right_retina.is_some() && self.show_tesseract == false {
+      width /= 2.0;
+    }
+    return width / height.max(1.0);
+  }
+
+  /**
+   * The camera the mouse controls: the right retina's while the cursor is
+   * over it, otherwise the main camera
+   */
+  fn camera_state_under_cursor(&mut self) -> &mut CameraState {
+    let half_width = self.render_state.config.width as f64 / 2.0;
+    match &mut self.right_retina {
+      Some(right_retina) if self.cursor_x >= half_width => return &mut right_retina.camera_state,
+      _ => return &mut self.camera_state,
+    }
+  }
+
+  /**
+   * Writes a captured frame to wherever it was requested, finishing the
+   * screenshot or sequence recording it was captured for. Recordings only
+   * take frames showing a `new_layer`
+   */
+  fn save_captured_frame(&mut self, frame: &CapturedFrame, new_layer: bool) {
+    if let Some(path) = self.screenshot_path.take() {
+      if let Err(e) = frame.save_png(&path) {
+        eprintln!("{:?}", e);
+      }
+    }
+
+    if new_layer == false {
+      return;
+    }
+
+    let layer_index = self.render_pipeline_state.voxel_scheduler.slab_index();
+    if let Some(recording) = &mut self.sequence_recording {
+      if let Err(e) = frame.save_png(&recording.layer_path(layer_index)) {
+        eprintln!("{:?}", e);
+      }
+      recording.mark_captured(layer_index);
+
+      if recording.is_complete() {
+        self.sequence_recording = None;
+      }
+    }
   }
 
   pub fn handle_events (
@@ -314,35 +1209,776 @@ impl GameState {
             return true;
           },
           WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+            self.update_refresh_rate(window);
             self.resize(**new_inner_size);
             return true;
           },
 
+          // The window may have moved onto a monitor with another refresh
+          // rate
+          WindowEvent::Moved(_) => {
+            self.update_refresh_rate(window);
+            return true;
+          },
+
           _ => return true,
         };
       },
 
+      // Comes right before each frame is rendered
+      Event::RedrawRequested(_) => {
+        if self.show_debug_ui {
+          self.debug_ui.sync_window(window);
+        }
+        return true;
+      },
+
       _ => return true,
     };
   }
 
-  fn iterate_volume_plane_instances_to_render(&mut self) {
-    let range_increment_amount = NUM_INSTANCES_PER_ROW * NUM_INSTANCES_PER_COL;
-    let range_end_max = self.render_pipeline_state.instance_buffer.instances.len() as u32;
-    let range_start_max = range_end_max - range_increment_amount;
+  /**
+   * Moves the flicker on a layer if the flicker clock says the next one is
+   * due by now, returning whether it did. Called once per presented frame,
+   * so at most one layer is shown per frame, and warns when the display
+   * starts falling behind
+   */
+  fn advance_flicker(&mut self) -> bool {
+    let now = Instant::now();
+    let frame_time = self.last_frame_time.map_or(Duration::ZERO, |last_frame_time| now - last_frame_time);
+    self.last_frame_time = Some(now);
 
-    let range_end_min = range_increment_amount;
-    let range_start_min = 0;
+    if self.enable_voxel_flicker == false {
+      return false;
+    }
 
-    if self.render_pipeline_state.instances_to_render_start + range_increment_amount <= range_start_max {
-      self.render_pipeline_state.instances_to_render_start += range_increment_amount;
-    } else {
-      self.render_pipeline_state.instances_to_render_start = range_start_min;
+    let was_falling_behind = self.flicker_clock.falling_behind();
+    self.flicker_clock.layers_per_volume = self.flicker_layer_count();
+    if self.flicker_clock.tick(frame_time) == false {
+      return false;
+    }
+
+    if self.flicker_clock.falling_behind() && was_falling_behind == false {
+      eprintln!(
+        "Voxel flicker needs {:.0} frames per second to show {} volumes per second, but the display can't keep up",
+        self.flicker_clock.required_frame_rate(),
+        self.flicker_clock.volumes_per_second(),
+      );
+    }
+    self.iterate_volume_plane_instances_to_render();
+    return true;
+  }
+
+  /**
+   * Records the frame just presented, which started rendering at
+   * `frame_start`, in the frame stats
+   */
+  fn record_frame_stats(&mut self, frame_start: Instant, new_layer: bool) {
+    let record = FrameRecord {
+      presented_at             : self.frame_stats.elapsed(),
+      cpu_time                 : frame_start.elapsed(),
+      layer                    : self.shown_flicker_layer(),
+      layer_count              : self.flicker_layer_count(),
+      new_layer,
+      skipped_layers           : if new_layer { self.flicker_clock.skipped_layers() } else { 0 },
+      repeated_layers          : if new_layer { self.flicker_clock.repeated_layers() } else { 0 },
+      target_volumes_per_second: self.flicker_clock.volumes_per_second(),
+    };
+    self.frame_stats.record(record);
+  }
+
+  pub fn frame_stats(&self) -> &FrameStats {
+    return &self.frame_stats;
+  }
+
+  /**
+   * Shows or hides the HUD, like key 8
+   */
+  pub fn set_show_hud(&mut self, show: bool) {
+    self.show_hud = show;
+  }
+
+  pub fn show_hud(&self) -> bool {
+    return self.show_hud;
+  }
+
+  /**
+   * Shows or hides the debug panel, like key 9
+   */
+  pub fn set_show_debug_ui(&mut self, show: bool) {
+    self.show_debug_ui = show;
+  }
+
+  /**
+   * Changes the colour frames are cleared to behind the voxels
+   */
+  pub fn set_background_color(&mut self, color: wgpu::Color) {
+    self.background_color = color;
+  }
+
+  pub fn background_color(&self) -> wgpu::Color {
+    return self.background_color;
+  }
+
+  /**
+   * Runs a frame of the debug panel and applies whatever it changed
+   */
+  fn run_debug_ui(&mut self) {
+    let size = self.render_state.size;
+    let previous = DebugSettings::from_game_state(self);
+    let mut settings = previous.clone();
+    self.debug_ui.run([size.width, size.height], &mut settings);
+
+    if settings != previous {
+      settings.apply(&previous, self);
     }
-    if self.render_pipeline_state.instances_to_render_end + range_increment_amount <= range_end_max {
-      self.render_pipeline_state.instances_to_render_end += range_increment_amount;
+  }
+
+  /**
+   * The HUD's text: the frame rate, the achieved and target volumes per
+   * second, the flicker layer shown, how the volume is rendered and the
+   * camera
+   */
+  pub fn hud_lines(&self) -> Vec<String> {
+    // Averaged over about a second at 60 Hz, so the numbers stay readable
+    let recent_stats = self.frame_stats.recent(60);
+    let layer_count = self.flicker_layer_count();
+
+    let flicker = if self.enable_voxel_flicker {
+      let vblank = if self.flicker_clock.vblank_rate().is_some() { " VBLANK LOCKED" } else { "" };
+      format!(
+        "VPS {:.1} / {:.1}{}",
+        recent_stats.achieved_volumes_per_second(),
+        self.flicker_clock.volumes_per_second(),
+        vblank,
+      )
     } else {
-      self.render_pipeline_state.instances_to_render_end = range_end_min;
+      format!("FLICKER OFF (0)  VPS {:.1}", self.flicker_clock.volumes_per_second())
+    };
+
+    // Turning flicker off keeps the layer it was on, rather than showing
+    // the whole volume
+    let layer = match self.shown_flicker_layer() {
+      Some(layer_index) => format!("LAYER {} / {}", layer_index + 1, layer_count),
+      None => format!("LAYER ALL / {}", layer_count),
+    };
+
+    let camera = &self.camera_state.camera;
+    return vec![
+      format!(
+        "FPS {:.1}  CPU {:.2} MS",
+        recent_stats.frame_rate(),
+        recent_stats.mean_cpu_time().as_secs_f64() * 1000.0,
+      ),
+      flicker,
+      layer,
+      format!(
+        "{}  {}  STEREO {}",
+        hud::label(self.render_mode),
+        hud::label(self.render_backend),
+        hud::label(self.stereo_camera_state.mode),
+      ),
+      format!(
+        "CAMERA YAW {:.0} PITCH {:.0} DISTANCE {:.2} FOV {:.0}",
+        camera.yaw.to_degrees(),
+        camera.pitch.to_degrees(),
+        camera.distance,
+        camera.fovy.to_degrees(),
+      ),
+    ];
+  }
+
+  /**
+   * Saves the recent frames' stats to `path` as CSV, like key F, and prints
+   * how the achieved volumes per second compare to the target
+   */
+  pub fn save_frame_stats(&self, path: &Path) {
+    if let Err(e) = self.frame_stats.save_csv(path) {
+      eprintln!("{:?}", e);
+      return;
+    }
+
+    eprintln!(
+      "Saved {} frames to {}: {:.1} fps, {:.1} of {:.1} volumes per second, {} skipped and {} repeated layers, {:.2} ms mean CPU time",
+      self.frame_stats.records().count(),
+      path.display(),
+      self.frame_stats.frame_rate(),
+      self.frame_stats.achieved_volumes_per_second(),
+      self.frame_stats.target_volumes_per_second(),
+      self.frame_stats.skipped_layers(),
+      self.frame_stats.repeated_layers(),
+      self.frame_stats.mean_cpu_time().as_secs_f64() * 1000.0,
+    );
+  }
+
+  fn iterate_volume_plane_instances_to_render(&mut self) {
+    self.render_pipeline_state.voxel_scheduler.advance();
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.voxel_scheduler.advance();
+    }
+  }
+
+  /**
+   * Turns flickering through the volume's layers on or off, like key 0
+   */
+  pub fn set_voxel_flicker(&mut self, enabled: bool) {
+    if enabled == false {
+      self.cancel_sequence_recording("voxel flicker was turned off");
+    }
+    self.enable_voxel_flicker = enabled;
+    self.flicker_clock.restart();
+  }
+
+  pub fn voxel_flicker(&self) -> bool {
+    return self.enable_voxel_flicker;
+  }
+
+  pub fn flicker_clock(&self) -> &FlickerClock {
+    return &self.flicker_clock;
+  }
+
+  /**
+   * The refresh rate of the window's monitor in Hz, if it's known
+   */
+  pub fn refresh_rate(&self) -> Option<f32> {
+    return self.render_state.refresh_rate;
+  }
+
+  /**
+   * Asks the window's monitor for its refresh rate again, eg. after the
+   * window moves to another monitor, re-locking the flicker to its vblanks
+   */
+  pub fn update_refresh_rate(&mut self, window: &Window) {
+    let refresh_rate = presentation::refresh_rate(window);
+    if refresh_rate == self.render_state.refresh_rate {
+      return;
+    }
+
+    self.render_state.refresh_rate = refresh_rate;
+    if self.lock_to_vblank {
+      self.lock_flicker_to_refresh_rate();
+    }
+  }
+
+  fn lock_flicker_to_refresh_rate(&mut self) {
+    self.flicker_clock.lock_to_vblank(self.render_state.refresh_rate);
+    if self.render_state.refresh_rate.is_none() {
+      eprintln!("The monitor's refresh rate is unknown, so flicker is timed by volumes per second");
+    }
+  }
+
+  /**
+   * Changes how many times a second every layer of the volume is shown, like
+   * keys 1 and 2
+   */
+  pub fn set_volumes_per_second(&mut self, volumes_per_second: f32) {
+    self.flicker_clock.set_volumes_per_second(volumes_per_second);
+  }
+
+  pub fn flicker_config(&self) -> FlickerConfig {
+    return self.render_pipeline_state.voxel_scheduler.config();
+  }
+
+  /**
+   * Changes how the volume is scheduled and swept through, restarting the
+   * sweep from its first layer
+   */
+  pub fn set_flicker_config(&mut self, config: FlickerConfig) {
+    if self.render_pipeline_state.voxel_scheduler.set_config(config) {
+      self.cancel_sequence_recording("the flicker layers were rescheduled");
+      let schedule = self.render_pipeline_state.voxel_scheduler.schedule(
+        &self.volume,
+        self.camera_state.view_point(),
+      );
+      self.render_pipeline_state.instance_buffer = InstanceBuffer::from_schedule(
+        &self.render_state.device,
+        &self.volume,
+        &schedule,
+      );
+    }
+
+    self.render_pipeline_state.voxel_scheduler.show_current_slab();
+
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.set_flicker_config(&self.render_state.device, &self.render_state.queue, config);
+    }
+  }
+
+  /**
+   * Shows the projected tesseract instead of the volume, like key T
+   */
+  pub fn set_show_tesseract(&mut self, show: bool) {
+    self.show_tesseract = show;
+  }
+
+  /**
+   * Turns the tesseract by `angle` radians in `plane`, like holding its
+   * rotation key
+   */
+  pub fn rotate_tesseract(&mut self, plane: RotationPlane, angle: f32) {
+    self.tesseract_model.rotate(plane, angle);
+  }
+
+  /**
+   * Changes how the tesseract is projected into 3D, like key 4
+   */
+  pub fn set_tesseract_projection(&mut self, projection: Projection4D) {
+    self.tesseract_model.projection = projection;
+  }
+
+  /**
+   * Shows a 3D cross-section of the hypervolume as the volume, like key H,
+   * or goes back to the volume shown before
+   */
+  pub fn set_hyperslicing(&mut self, enabled: bool) {
+    if enabled == self.enable_hyperslicing {
+      return;
+    }
+
+    if enabled {
+      self.set_binocular(false);
+    }
+    self.enable_hyperslicing = enabled;
+
+    if enabled {
+      self.volume_before_4d = Some(self.volume.clone());
+      self.show_hyperslice();
+      return;
+    }
+
+    if let Some(volume) = self.volume_before_4d.take() {
+      self.set_volume(volume);
+    }
+  }
+
+  /**
+   * Shows what each eye of the 4D eye pair sees of the hypervolume side by
+   * side, like key B, or goes back to the volume shown before. The left
+   * retina is the main view, and the right retina gets its own camera
+   */
+  pub fn set_binocular(&mut self, enabled: bool) {
+    if enabled == self.right_retina.is_some() {
+      return;
+    }
+
+    if enabled == false {
+      self.right_retina = None;
+      if let Some(volume) = self.volume_before_4d.take() {
+        self.set_volume(volume);
+      }
+      return;
+    }
+
+    self.set_hyperslicing(false);
+    self.set_stereo_mode(StereoMode::Off);
+    self.volume_before_4d = Some(self.volume.clone());
+
+    let [left, right] = self.retina_worker.march(self.eye_pair, self.hyperslicer.slice.orientation);
+    self.right_retina = Some(RetinaView::new(
+      &self.render_state.device,
+      self.render_state.size,
+      right,
+      self.flicker_config(),
+    ));
+    self.show_volume_keeping_layer(left);
+  }
+
+  /**
+   * Shows the scene in stereo 3D for a pair of 3D eyes, like key 3, or as a
+   * single view with [StereoMode::Off]. Turns the 4D eye pair off, since its
+   * retinas already take up both halves of the frame
+   */
+  pub fn set_stereo_mode(&mut self, mode: StereoMode) {
+    if mode != StereoMode::Off {
+      self.set_binocular(false);
+    }
+    self.stereo_camera_state.mode = mode;
+  }
+
+  pub fn stereo_mode(&self) -> StereoMode {
+    return self.stereo_camera_state.mode;
+  }
+
+  /**
+   * Changes how overlapping voxels are combined, like key 5, which cycles
+   * through opaque, weighted blended, additive and maximum intensity
+   */
+  pub fn set_render_mode(&mut self, mode: RenderMode) {
+    self.render_mode = mode;
+  }
+
+  pub fn render_mode(&self) -> RenderMode {
+    return self.render_mode;
+  }
+
+  /**
+   * Draws the volume as instanced cubes or by ray marching, like key 6
+   */
+  pub fn set_render_backend(&mut self, backend: RenderBackend) {
+    self.render_backend = backend;
+    if backend == RenderBackend::RayMarched {
+      self.ray_march_state.set_volume(&self.render_state.device, &self.render_state.queue, &self.volume);
+    }
+  }
+
+  pub fn render_backend(&self) -> RenderBackend {
+    return self.render_backend;
+  }
+
+  /**
+   * Colours voxels by their scalar values through `transfer_function`,
+   * spread over the volume's range of values, or by their own colours again
+   * with None
+   */
+  pub fn set_transfer_function(&mut self, transfer_function: Option<TransferFunction>) {
+    self.transfer_function = transfer_function;
+    self.transfer_function_preset = None;
+    self.update_transfer_function();
+  }
+
+  /**
+   * Colours voxels by one of the built-in transfer functions, like key 7
+   */
+  pub fn set_transfer_function_preset(&mut self, preset: Option<TransferFunctionPreset>) {
+    self.transfer_function = preset.map(|preset| preset.transfer_function());
+    self.transfer_function_preset = preset;
+    self.update_transfer_function();
+  }
+
+  pub fn transfer_function_preset(&self) -> Option<TransferFunctionPreset> {
+    return self.transfer_function_preset;
+  }
+
+  pub fn transfer_function(&self) -> Option<&TransferFunction> {
+    return self.transfer_function.as_ref();
+  }
+
+  /**
+   * Saves the transfer function to `path`, in the format loaded with
+   * `--transfer-function`
+   */
+  pub fn save_transfer_function(&self, path: &Path) {
+    let Some(transfer_function) = &self.transfer_function else {
+      eprintln!("There is no transfer function to save");
+      return;
+    };
+
+    match transfer_function.save(path) {
+      Ok(()) => eprintln!("Saved the transfer function to {}", path.display()),
+      Err(e) => eprintln!("Failed to save {}: {}", path.display(), e),
+    }
+  }
+
+  /**
+   * Uploads the transfer function, spread over the values of the current
+   * volume
+   */
+  fn update_transfer_function(&self) {
+    self.transfer_function_state.update(
+      &self.render_state.queue,
+      self.transfer_function.as_ref(),
+      self.volume.value_range(),
+    );
+  }
+
+  /**
+   * Moves the 3D eyes apart or changes where they converge
+   */
+  pub fn set_stereo_camera(&mut self, stereo_camera: StereoCamera) {
+    self.stereo_camera_state.stereo_camera = stereo_camera;
+  }
+
+  /**
+   * The main camera, which the left retina also sees through while the 4D
+   * eye pair is shown
+   */
+  pub fn camera_state(&self) -> &CameraState {
+    return &self.camera_state;
+  }
+
+  /**
+   * The cameras of the main view and the right retina, which always move
+   * the same way
+   */
+  fn camera_states(&mut self) -> impl Iterator<Item = &mut CameraState> {
+    return std::iter::once(&mut self.camera_state)
+      .chain(self.right_retina.iter_mut().map(|right_retina| &mut right_retina.camera_state));
+  }
+
+  /**
+   * Changes how fast the mouse turns and zooms the cameras
+   */
+  pub fn set_camera_speeds(&mut self, rotate_speed: f32, zoom_speed: f32) {
+    for camera_state in self.camera_states() {
+      camera_state.camera_controller.rotate_speed = rotate_speed;
+      camera_state.camera_controller.zoom_speed = zoom_speed;
+    }
+  }
+
+  /**
+   * Changes how near and far the cameras can be from their targets, moving
+   * them within the new bounds
+   */
+  pub fn set_camera_distance_bounds(&mut self, min_distance: Option<f32>, max_distance: Option<f32>) {
+    for camera_state in self.camera_states() {
+      camera_state.camera.bounds.min_distance = min_distance;
+      camera_state.camera.bounds.max_distance = max_distance;
+      camera_state.camera.set_distance(camera_state.camera.distance);
+    }
+  }
+
+  /**
+   * Moves the 4D eyes apart or changes where they converge
+   */
+  pub fn set_eye_pair(&mut self, eye_pair: EyePair4D) {
+    self.eye_pair = eye_pair;
+    if self.right_retina.is_some() {
+      let retinas = self.retina_worker.march(self.eye_pair, self.hyperslicer.slice.orientation);
+      self.show_retinas(retinas);
+    }
+  }
+
+  /**
+   * Shows newly marched retinas, eg. after the hypervolume turns
+   */
+  fn show_retinas(&mut self, [left, right]: [VoxelVolume; 2]) {
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.set_volume(&self.render_state.device, &self.render_state.queue, right);
+    }
+    self.show_volume_keeping_layer(left);
+  }
+
+  /**
+   * Replaces the 4D volume which is hypersliced or seen by the eye pair,
+   * keeping the hyperplane
+   */
+  pub fn set_hypervolume(&mut self, volume: HypervoxelVolume) {
+    self.hyperslicer.volume = volume;
+    self.retina_worker.set_volume(&self.hyperslicer.volume);
+    if self.enable_hyperslicing {
+      self.show_hyperslice();
+    }
+    if self.right_retina.is_some() {
+      let retinas = self.retina_worker.march(self.eye_pair, self.hyperslicer.slice.orientation);
+      self.show_retinas(retinas);
+    }
+  }
+
+  /**
+   * Replaces the hypervolume with a voxelised 4D shape, like key G, which
+   * steps through the regular polytopes, a duoprism, the spherinder and the
+   * hypersphere
+   */
+  pub fn set_hypershape(&mut self, shape: Hypershape) {
+    self.hypershape = shape;
+    self.set_hypervolume(shape.hypervolume());
+  }
+
+  /**
+   * Moves and turns the hyperplane the hypervolume is sliced by
+   */
+  pub fn set_hyperslice(&mut self, slice: HyperSlice) {
+    self.hyperslicer.slice = slice;
+    if self.enable_hyperslicing {
+      self.show_hyperslice();
+    }
+  }
+
+  /**
+   * Replaces the volume with the current hyperslice, staying on the same
+   * flicker layer so sweeping through W doesn't restart the flicker
+   */
+  fn show_hyperslice(&mut self) {
+    self.show_volume_keeping_layer(self.hyperslicer.slice_volume());
+  }
+
+  fn show_volume_keeping_layer(&mut self, volume: VoxelVolume) {
+    let slab_index = self.render_pipeline_state.voxel_scheduler.slab_index();
+    self.rebuild_volume_instances(volume);
+    self.render_pipeline_state.voxel_scheduler.show_slab(slab_index);
+  }
+
+  pub fn volume(&self) -> &VoxelVolume {
+    return &self.volume;
+  }
+
+  /**
+   * Replaces the rendered volume, rebuilding its instances, and restarts the
+   * flicker from its first layer
+   */
+  pub fn set_volume(&mut self, volume: VoxelVolume) {
+    self.rebuild_volume_instances(volume);
+    self.show_flicker_layer(0);
+  }
+
+  fn rebuild_volume_instances(&mut self, volume: VoxelVolume) {
+    self.cancel_sequence_recording("the flicker layers were rescheduled");
+    let schedule = self.render_pipeline_state.voxel_scheduler.schedule(
+      &volume,
+      self.camera_state.view_point(),
+    );
+    self.render_pipeline_state.instance_buffer.reorder(
+      &self.render_state.device,
+      &self.render_state.queue,
+      &volume,
+      &schedule,
+    );
+    if self.render_backend == RenderBackend::RayMarched {
+      self.ray_march_state.set_volume(&self.render_state.device, &self.render_state.queue, &volume);
+    }
+    self.volume = volume;
+
+    if self.transfer_function.is_some() {
+      self.update_transfer_function();
+    }
+  }
+
+  /**
+   * Replaces the rendered instances, eg. to show a single cube, and renders
+   * all of them at once
+   */
+  pub fn set_instances(&mut self, instances: Vec<Instance>) {
+    self.cancel_sequence_recording("the flicker layers were rescheduled");
+    self.render_pipeline_state.voxel_scheduler.schedule_single_layer(instances.len() as u32);
+    self.render_pipeline_state.instance_buffer = InstanceBuffer::from_instances(
+      &self.render_state.device,
+      instances,
+    );
+    self.show_flicker_layer(0);
+  }
+
+  /**
+   * Re-orders the voxels for a schedule which depends on the camera, eg.
+   * depth layers facing it, after the camera has turned
+   */
+  fn reschedule_camera_facing_voxels(&mut self) {
+    self.cancel_sequence_recording("the flicker layers were rescheduled");
+    let schedule = self.render_pipeline_state.voxel_scheduler.schedule(
+      &self.volume,
+      self.camera_state.view_point(),
+    );
+    self.render_pipeline_state.instance_buffer.reorder(
+      &self.render_state.device,
+      &self.render_state.queue,
+      &self.volume,
+      &schedule,
+    );
+
+    // Keep showing the same depth layer, if the volume still has it
+    let slab_index = self.render_pipeline_state.voxel_scheduler.slab_index();
+    self.show_flicker_layer(slab_index);
+  }
+
+  /**
+   * Renders only the layer at `layer_index`, as voxel flicker would when it
+   * reaches that layer, and continues the sweep from there
+   */
+  pub fn show_flicker_layer(&mut self, layer_index: u32) {
+    self.render_pipeline_state.voxel_scheduler.show_slab(layer_index);
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.voxel_scheduler.show_slab(layer_index);
+    }
+  }
+
+  /**
+   * The layer being rendered, or None while every layer is rendered at once
+   */
+  pub fn shown_flicker_layer(&self) -> Option<u32> {
+    return self.render_pipeline_state.voxel_scheduler.shown_slab();
+  }
+
+  /**
+   * Renders every layer of the volume at once
+   */
+  pub fn show_all_volume_planes(&mut self) {
+    self.render_pipeline_state.voxel_scheduler.show_all();
+    if let Some(right_retina) = &mut self.right_retina {
+      right_retina.voxel_scheduler.show_all();
     }
   }
+
+  /**
+   * The number of layers (slabs of planes or voxels) the volume is flickered
+   * through
+   */
+  pub fn flicker_layer_count(&self) -> u32 {
+    return self.render_pipeline_state.voxel_scheduler.slab_count();
+  }
+
+  /**
+   * Changes the flicker config from a key press, returning whether the key
+   * was used
+   *
+   * X, Y, Z flicker through planes along that axis, V through planes facing
+   * the camera, O cycles the voxel order, D cycles the sweep direction, and
+   * +/- change the slab thickness
+   */
+  fn handle_flicker_key(&mut self, key: VirtualKeyCode) -> bool {
+    let mut config = self.flicker_config();
+
+    match key {
+      VirtualKeyCode::X | VirtualKeyCode::Y | VirtualKeyCode::Z | VirtualKeyCode::V => {
+        config.order = VoxelOrder::Planes;
+        config.axis = match key {
+          VirtualKeyCode::X => SliceAxis::X,
+          VirtualKeyCode::Z => SliceAxis::Z,
+          VirtualKeyCode::V => SliceAxis::View,
+          _ => SliceAxis::Y,
+        };
+      },
+      VirtualKeyCode::O => config.order = config.order.next(),
+      VirtualKeyCode::D => config.direction = config.direction.next(),
+      VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => {
+        config.slab_thickness += 1;
+      },
+      VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+        config.slab_thickness = config.slab_thickness.saturating_sub(1).max(1);
+      },
+      _ => return false,
+    }
+
+    self.set_flicker_config(config);
+    return true;
+  }
+
+  /**
+   * Starts or stops turning the shown 4D object (the tesseract, or the
+   * hypervolume being sliced) from a key press or release, returning whether
+   * the key was used
+   *
+   * M, comma and period turn it in the XY, XZ and YZ planes, and J, K, L in
+   * the XW, YW and ZW planes. Holding shift turns the other way
+   */
+  fn handle_rotation_key(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+    let plane = match key {
+      VirtualKeyCode::M => RotationPlane::XY,
+      VirtualKeyCode::Comma => RotationPlane::XZ,
+      VirtualKeyCode::Period => RotationPlane::YZ,
+      VirtualKeyCode::J => RotationPlane::XW,
+      VirtualKeyCode::K => RotationPlane::YW,
+      VirtualKeyCode::L => RotationPlane::ZW,
+      _ => return false,
+    };
+
+    let plane_index = RotationPlane::ALL.iter()
+      .position(|other| *other == plane)
+      .unwrap_or(0);
+
+    self.spin_4d[plane_index] = match state {
+      ElementState::Released => 0.0,
+      ElementState::Pressed if self.modifiers.shift() => -1.0,
+      ElementState::Pressed => 1.0,
+    };
+    return true;
+  }
+
+  /**
+   * Seconds since the unix epoch, for naming captures uniquely
+   */
+  pub(crate) fn capture_timestamp() -> u64 {
+    return std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|duration| duration.as_secs())
+      .unwrap_or(0);
+  }
 }
@@ -0,0 +1,200 @@
+//! Text for the on-screen HUD, laid out with a built-in 5x7 pixel font so no
+//! font files or text rendering crates are needed.
+//!
+//! Each lit pixel of a glyph becomes a small quad, drawn over a translucent
+//! background behind the text. Letters are shown in upper case.
+
+use std::fmt::Debug;
+
+pub const GLYPH_WIDTH : u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/**
+ * The font pixels from the start of one glyph to the next, and from the top
+ * of one line to the next
+ */
+pub const GLYPH_ADVANCE: u32 = GLYPH_WIDTH + 1;
+pub const LINE_ADVANCE : u32 = GLYPH_HEIGHT + 3;
+
+/**
+ * How many screen pixels wide and tall each font pixel is drawn
+ */
+pub const HUD_SCALE: u32 = 2;
+
+/**
+ * The screen pixels between the edge of the window and the background, and
+ * between the background and the text
+ */
+pub const HUD_MARGIN: u32 = 8;
+
+pub const HUD_TEXT_COLOR      : [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+pub const HUD_BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+
+/**
+ * A corner of a HUD quad, in normalized device coordinates
+ */
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct HudVertex {
+  pub position: [f32; 2],
+  pub color   : [f32; 4],
+}
+
+impl HudVertex {
+  const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+
+  pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+    return wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<HudVertex>() as wgpu::BufferAddress,
+      step_mode   : wgpu::VertexStepMode::Vertex,
+      attributes  : &Self::ATTRIBUTES,
+    };
+  }
+}
+
+/**
+ * The rows of `character`'s glyph, top first, with the leftmost pixel in the
+ * highest of each row's 5 bits. Lower case letters use the upper case glyphs,
+ * and characters the font lacks are drawn as `?`
+ */
+pub fn glyph(character: char) -> [u8; 7] {
+  match character.to_ascii_uppercase() {
+    ' ' => return [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    '0' => return [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+    '1' => return [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+    '2' => return [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+    '3' => return [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+    '4' => return [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+    '5' => return [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+    '6' => return [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+    '7' => return [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+    '8' => return [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+    '9' => return [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+    'A' => return [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+    'B' => return [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+    'C' => return [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+    'D' => return [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+    'E' => return [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+    'F' => return [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+    'G' => return [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+    'H' => return [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+    'I' => return [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+    'J' => return [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+    'K' => return [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+    'L' => return [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+    'M' => return [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+    'N' => return [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+    'O' => return [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+    'P' => return [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+    'Q' => return [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+    'R' => return [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+    'S' => return [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+    'T' => return [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+    'U' => return [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+    'V' => return [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+    'W' => return [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+    'X' => return [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+    'Y' => return [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+    'Z' => return [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+    '.' => return [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+    ',' => return [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+    ':' => return [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+    '/' => return [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+    '-' => return [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+    '+' => return [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+    '=' => return [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+    '%' => return [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+    '(' => return [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+    ')' => return [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+    _ => return [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+  }
+}
+
+/**
+ * The size in screen pixels of `lines` drawn at [HUD_SCALE], without the
+ * background's margin
+ */
+pub fn text_size(lines: &[String]) -> [u32; 2] {
+  let longest_line = lines.iter().map(|line| line.chars().count() as u32).max().unwrap_or(0);
+  if longest_line == 0 {
+    return [0, 0];
+  }
+
+  let width = longest_line * GLYPH_ADVANCE - (GLYPH_ADVANCE - GLYPH_WIDTH);
+  let height = lines.len() as u32 * LINE_ADVANCE - (LINE_ADVANCE - GLYPH_HEIGHT);
+  return [width * HUD_SCALE, height * HUD_SCALE];
+}
+
+/**
+ * Lays `lines` out in the top left corner of a `screen_size` frame, as
+ * triangles for a background and a quad per lit font pixel
+ */
+pub fn layout_text(lines: &[String], screen_size: [u32; 2]) -> Vec<HudVertex> {
+  let mut vertices = Vec::new();
+  let [text_width, text_height] = text_size(lines);
+  if text_width == 0 {
+    return vertices;
+  }
+
+  let [screen_width, screen_height] = screen_size.map(|size| size.max(1) as f32);
+  let mut push_rect = |x: u32, y: u32, width: u32, height: u32, color: [f32; 4]| {
+    let left = x as f32 / screen_width * 2.0 - 1.0;
+    let right = (x + width) as f32 / screen_width * 2.0 - 1.0;
+    let top = 1.0 - y as f32 / screen_height * 2.0;
+    let bottom = 1.0 - (y + height) as f32 / screen_height * 2.0;
+
+    for position in [[left, top], [left, bottom], [right, bottom], [left, top], [right, bottom], [right, top]] {
+      vertices.push(HudVertex { position, color });
+    }
+  };
+
+  push_rect(
+    HUD_MARGIN,
+    HUD_MARGIN,
+    text_width + 2 * HUD_MARGIN,
+    text_height + 2 * HUD_MARGIN,
+    HUD_BACKGROUND_COLOR,
+  );
+
+  let text_origin = 2 * HUD_MARGIN;
+  for (line_index, line) in lines.iter().enumerate() {
+    let line_top = text_origin + line_index as u32 * LINE_ADVANCE * HUD_SCALE;
+
+    for (column, character) in line.chars().enumerate() {
+      let glyph_left = text_origin + column as u32 * GLYPH_ADVANCE * HUD_SCALE;
+
+      for (row, bits) in glyph(character).into_iter().enumerate() {
+        for pixel in 0..GLYPH_WIDTH {
+          if bits & (1 << (GLYPH_WIDTH - 1 - pixel)) == 0 {
+            continue;
+          }
+          push_rect(
+            glyph_left + pixel * HUD_SCALE,
+            line_top + row as u32 * HUD_SCALE,
+            HUD_SCALE,
+            HUD_SCALE,
+            HUD_TEXT_COLOR,
+          );
+        }
+      }
+    }
+  }
+
+  return vertices;
+}
+
+/**
+ * An enum variant's name split into upper case words for the HUD, eg.
+ * `WEIGHTED BLENDED` for [crate::render_pipeline_state::RenderMode::WeightedBlended]
+ */
+pub fn label(value: impl Debug) -> String {
+  let name = format!("{:?}", value);
+  let mut label = String::with_capacity(name.len() + 4);
+  for (index, character) in name.chars().enumerate() {
+    if index > 0 && character.is_ascii_uppercase() {
+      label.push(' ');
+    }
+    label.push(character.to_ascii_uppercase());
+  }
+  return label;
+}
@@ -0,0 +1,133 @@
+use wgpu::{Buffer, CommandEncoder, Device, Queue, RenderPipeline, SurfaceConfiguration, TextureView};
+
+use crate::hud::{self, HudVertex};
+
+/**
+ * Draws the HUD's text over a finished frame, in its own pass after the
+ * voxels
+ */
+pub struct HudState {
+  pipeline: RenderPipeline,
+
+  /// Grown whenever the text needs more vertices than it holds
+  vertex_buffer  : Buffer,
+  vertex_capacity: u64,
+  vertex_count   : u32,
+}
+
+impl HudState {
+
+  pub fn new(device: &Device, config: &SurfaceConfiguration) -> Self {
+    let pipeline = Self::configure_pipeline(device, config);
+
+    // Enough for the background and a few lines of text
+    let vertex_capacity = 4096;
+    let vertex_buffer = Self::create_vertex_buffer(device, vertex_capacity);
+
+    return Self {
+      pipeline,
+      vertex_buffer,
+      vertex_capacity,
+      vertex_count: 0,
+    };
+  }
+
+  /**
+   * Lays out `lines` for a `screen_size` frame and uploads them
+   */
+  pub fn update(&mut self, device: &Device, queue: &Queue, lines: &[String], screen_size: [u32; 2]) {
+    let vertices = hud::layout_text(lines, screen_size);
+
+    if vertices.len() as u64 > self.vertex_capacity {
+      self.vertex_capacity = (vertices.len() as u64).next_power_of_two();
+      self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+    }
+
+    queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+    self.vertex_count = vertices.len() as u32;
+  }
+
+  /**
+   * Draws the last uploaded text over whatever is already in `view`
+   */
+  pub fn encode(&self, encoder: &mut CommandEncoder, view: &TextureView) {
+    if self.vertex_count == 0 {
+      return;
+    }
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("HUD Pass"),
+
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+
+        ops: wgpu::Operations {
+          load : wgpu::LoadOp::Load,
+          store: true,
+        },
+      })],
+
+      depth_stencil_attachment: None,
+    });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    render_pass.draw(0..self.vertex_count, 0..1);
+  }
+
+  fn create_vertex_buffer(device: &Device, vertex_capacity: u64) -> Buffer {
+    return device.create_buffer(&wgpu::BufferDescriptor {
+      label             : Some("HUD vertex buffer"),
+      size              : vertex_capacity * std::mem::size_of::<HudVertex>() as u64,
+      usage             : wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+      mapped_at_creation: false,
+    });
+  }
+
+  fn configure_pipeline(device: &Device, config: &SurfaceConfiguration) -> RenderPipeline {
+    let shader = device.create_shader_module(
+      wgpu::include_wgsl!("hud.wgsl"),
+    );
+
+    let pipeline_layout = device.create_pipeline_layout(
+      &wgpu::PipelineLayoutDescriptor {
+        label               : Some("HUD pipeline layout"),
+        bind_group_layouts  : &[],
+        push_constant_ranges: &[],
+      },
+    );
+
+    return device.create_render_pipeline(
+      &wgpu::RenderPipelineDescriptor {
+        label : Some("HUD pipeline"),
+        layout: Some(&pipeline_layout),
+
+        vertex: wgpu::VertexState {
+          module      : &shader,
+          entry_point : "vs_main",
+          buffers     : &[HudVertex::desc()],
+        },
+
+        // The background is translucent, so the voxels show through it
+        fragment: Some(wgpu::FragmentState {
+          module      : &shader,
+          entry_point : "fs_main",
+          targets     : &[Some(wgpu::ColorTargetState {
+            format    : config.format,
+            blend     : Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+          })],
+        }),
+
+        primitive: wgpu::PrimitiveState::default(),
+
+        // Always on top, so never depth tested
+        depth_stencil: None,
+
+        multisample: wgpu::MultisampleState::default(),
+        multiview  : None,
+      },
+    );
+  }
+}
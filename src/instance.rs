@@ -1,22 +1,21 @@
-use cgmath::{Vector3, Quaternion, InnerSpace, Zero, Rotation3, Deg, Matrix4};
-use wgpu::{Device, Buffer, util::{DeviceExt, BufferInitDescriptor}, BufferUsages};
+use cgmath::{Vector3, Quaternion, Matrix4, One};
+use wgpu::{Device, Buffer, util::{DeviceExt, BufferInitDescriptor}, BufferUsages, Queue};
 
-pub const NUM_INSTANCES_PER_ROW : u32 = 3;
-pub const NUM_INSTANCES_PER_COL : u32 = 3;
-pub const NUM_INSTANCE_PLANES   : u32 = 3;
-
-const INSTANCES_OFFSET: cgmath::Vector3<f32> = cgmath::Vector3::new(
-  NUM_INSTANCES_PER_ROW as f32 * 0.1,
-  NUM_INSTANCE_PLANES as f32 * 0.1,
-  NUM_INSTANCES_PER_ROW as f32 * 0.1,
-);
+use crate::{cube_model::CUBE_SIZE, flicker_scheduler::FlickerSchedule, voxel_volume::VoxelVolume};
 
 /**
  * Defines the properties of different instances of objects/models
  */
+#[derive(Clone, Debug)]
 pub struct Instance {
   pub position: Vector3<f32>,
   pub rotation: Quaternion<f32>,
+  pub scale   : f32,
+  pub color   : [u8; 4], // [R, G, B, A]
+
+  /// The voxel's scalar value, coloured by the transfer function if one is
+  /// set
+  pub value   : f32,
 }
 
 /**
@@ -27,6 +26,8 @@ pub struct Instance {
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceRaw {
   pub model: [[f32; 4]; 4],
+  pub color: [u8; 4],
+  pub value: f32,
 }
 
 pub struct InstanceBuffer {
@@ -35,12 +36,29 @@ pub struct InstanceBuffer {
 }
 
 impl Instance {
+
+  /**
+   * An unrotated cube of the default size
+   */
+  pub fn at(position: Vector3<f32>) -> Self {
+    return Self {
+      position,
+      rotation: Quaternion::one(),
+      scale   : 1.0,
+      color   : [255, 255, 255, 255],
+      value   : 0.0,
+    };
+  }
+
   fn to_raw(&self) -> InstanceRaw {
     InstanceRaw {
       model: (
         Matrix4::from_translation(self.position)
         * Matrix4::from(self.rotation)
+        * Matrix4::from_scale(self.scale)
       ).into(),
+      color: self.color,
+      value: self.value,
     }
   }
 }
@@ -80,22 +98,32 @@ impl InstanceRaw {
           shader_location: 8,
           format: wgpu::VertexFormat::Float32x4,
         },
+        // The voxel's colour and opacity, normalised to 0..1 by the GPU
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+          shader_location: 9,
+          format: wgpu::VertexFormat::Unorm8x4,
+        },
+        // The voxel's scalar value, for the transfer function
+        wgpu::VertexAttribute {
+          offset: mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+          shader_location: 10,
+          format: wgpu::VertexFormat::Float32,
+        },
       ],
     }
   }
 }
 
 impl InstanceBuffer {
-  pub fn new(device: &Device) -> Self {
-    let (
-      instances,
-      buffer,
-    ) = Self::create_grid(
-      device,
-      Some(NUM_INSTANCES_PER_ROW),
-      Some(NUM_INSTANCE_PLANES),
-      Some(NUM_INSTANCES_PER_COL),
-    );
+
+  /**
+   * Builds one cube instance per occupied voxel of `volume`, sized to fill
+   * the voxel spacing, in the order `schedule` draws them
+   */
+  pub fn from_schedule(device: &Device, volume: &VoxelVolume, schedule: &FlickerSchedule) -> Self {
+    let instances = Self::scheduled_instances(volume, schedule);
+    let buffer = Self::create_buffer(device, &instances);
 
     return Self {
       instances,
@@ -104,70 +132,74 @@ impl InstanceBuffer {
   }
 
   /**
-   * x_max is left/right
-   * y_max is up/down
-   * z_max is forward/backward
-   * (x|y|z)_max must be > 0
+   * Re-orders the instances of `volume` for a new schedule, eg. when the
+   * camera moves during view-aligned slicing. The existing buffer is reused
+   * when the instance count hasn't changed
    */
-  fn create_grid(
+  pub fn reorder(
+    &mut self,
     device: &Device,
-    x_max: Option<u32>,
-    y_max: Option<u32>,
-    z_max: Option<u32>,
-  ) -> (
-    Vec<Instance>,
-    Buffer,
+    queue: &Queue,
+    volume: &VoxelVolume,
+    schedule: &FlickerSchedule,
   ) {
-    let instances =
-      (0..y_max.unwrap_or(1)).flat_map(|y| {
-        (0..z_max.unwrap_or(1)).flat_map(move |z| {
-          (0..x_max.unwrap_or(1)).map(move |x| {
-            let position = Vector3 {
-              // Individual instance position offsets
-              x: x as f32 * 0.2,
-              y: y as f32 * 0.2,
-              z: z as f32 * 0.2,
-            } - INSTANCES_OFFSET;
-  
-            let rotation = if position.is_zero() {
-              // this is needed so an object at (0, 0, 0) won't get scaled to zero
-              // as Quaternions can effect scale if they're not created correctly
-              Quaternion::from_axis_angle(
-                Vector3::unit_z(),
-                Deg(0.0)
-              )
-            } else {
-              Quaternion::from_axis_angle(
-                position.normalize(),
-                Deg(0.0)
-              )
-            };
-  
-            return Instance {
-              position,
-              rotation,
-            };
-          })
-        })
+    let instances = Self::scheduled_instances(volume, schedule);
+
+    if instances.len() == self.instances.len() {
+      let instance_data = instances
+        .iter()
+        .map(Instance::to_raw)
+        .collect::<Vec<_>>();
+      queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&instance_data));
+    } else {
+      self.buffer = Self::create_buffer(device, &instances);
+    }
+
+    self.instances = instances;
+  }
+
+  fn scheduled_instances(volume: &VoxelVolume, schedule: &FlickerSchedule) -> Vec<Instance> {
+    let scale = volume.spacing / CUBE_SIZE;
+
+    return schedule.voxel_order
+      .iter()
+      .filter_map(|[x, y, z]| {
+        let voxel = volume.get(*x, *y, *z)?;
+        return Some(Instance {
+          position: volume.position(*x, *y, *z),
+          rotation: Quaternion::one(),
+          scale,
+          color   : voxel.color,
+          value   : voxel.value,
+        });
       })
-      .collect::<Vec<_>>();
-  
+      .collect();
+  }
+
+  /**
+   * Uploads an arbitrary set of instances, eg. a single cube
+   */
+  pub fn from_instances(device: &Device, instances: Vec<Instance>) -> Self {
+    let buffer = Self::create_buffer(device, &instances);
+
+    return Self {
+      instances,
+      buffer,
+    };
+  }
+
+  fn create_buffer(device: &Device, instances: &[Instance]) -> Buffer {
     let instance_data = instances
       .iter()
       .map(Instance::to_raw)
       .collect::<Vec<_>>();
-  
-    let instance_buffer = device.create_buffer_init(
+
+    return device.create_buffer_init(
       &BufferInitDescriptor {
         label   : Some("Instance buffer"),
         contents: bytemuck::cast_slice(&instance_data),
-        usage   : BufferUsages::VERTEX,
+        usage   : BufferUsages::VERTEX | BufferUsages::COPY_DST,
       },
     );
-  
-    return (
-      instances,
-      instance_buffer,
-    );
   }
 }
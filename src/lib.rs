@@ -1,9 +1,29 @@
 #![allow(non_snake_case)]
+// Explicit returns and `== false` checks are the house style
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
 
 pub mod game_state;
 pub mod render_state;
 pub mod camera;
 pub mod render_pipeline_state;
 pub mod cube_model;
+pub mod debug_ui;
 pub mod instance;
 pub mod extras;
+pub mod flicker;
+pub mod flicker_clock;
+pub mod flicker_scheduler;
+pub mod frame_capture;
+pub mod frame_stats;
+pub mod hud;
+pub mod hud_state;
+pub mod oit_state;
+pub mod presentation;
+pub mod ray_march_state;
+pub mod transfer_function;
+pub mod transfer_function_state;
+pub mod voxel_volume;
+pub mod volume_loaders;
+pub mod four_d;
+pub mod retina_view;
@@ -1,9 +1,18 @@
 #![allow(non_snake_case)]
+// Explicit returns and `== false` checks are the house style
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
 
-use game_loop::{game_loop, winit::{event_loop::EventLoop, window::Window, event::{Event, WindowEvent, KeyboardInput, ElementState, VirtualKeyCode}}};
-use rust_wgpu_3D_vision::game_state::GameState;
+use game_loop::{game_loop, winit::{event_loop::EventLoop, window::Window, event::Event}};
+use rust_wgpu_3D_vision::{game_state::GameState, presentation::{self, PresentationConfig}, transfer_function::TransferFunction, volume_loaders::{self, raw::{self, RawVolumeDescription}}, voxel_volume::{ScalarWindowSettings, DEFAULT_VOLUME_EXTENT}};
+use std::path::Path;
 
-static TARGET_FPS: u32 = 60;
+/**
+ * How often the scene (the cameras and 4D objects) is updated. Frames are
+ * presented as often as the display allows, and voxel flicker is timed by
+ * its own [rust_wgpu_3D_vision::flicker_clock::FlickerClock]
+ */
+static UPDATES_PER_SECOND: u32 = 60;
 
 fn main() {
   pollster::block_on(run());
@@ -22,25 +31,20 @@ pub async fn run() {
     event_loop,
     window,
     game_state,
-    TARGET_FPS,
+    UPDATES_PER_SECOND,
     0.1,
     |g| {
       g.game.update();
-
-      // We are rendering here, because this block updates at the speed of
-      // TARGET_FPS, which is the speed we want to render layers of voxels
-      // in order to utilize consistent flicker fusion for 3D volumes
+    },
+    |g| {
+      // Presents a frame as often as the display allows, which the flicker
+      // clock shows each layer of voxels for as long as it's due
       let render_error = g.game.render() == false;
       if render_error {
         g.exit();
       };
     },
-    |_g| {
-      // This block updates faster than TARGET_FPS, which is not suitable
-      // for our current implementation of rendering voxel layers
-    },
     |g, event| {
-      detect_change_framerate(g, event);
       detect_exit_request(g, event);
     },
   );
@@ -57,8 +61,14 @@ async fn game_init() -> (
   .unwrap();
   window.set_title("3D Vision Renderer");
 
-  let game_state = GameState::new(&window).await;
-  
+  let mut args = std::env::args().collect::<Vec<_>>();
+  let presentation = presentation_arguments(&mut args);
+
+  let mut game_state = GameState::new(&window, presentation).await;
+  load_transfer_function_argument(&mut game_state, &mut args);
+  let scalar_window = window_arguments(&mut args);
+  load_volume_argument(&mut game_state, &args, &scalar_window);
+
   return (
     event_loop,
     window,
@@ -66,6 +76,122 @@ async fn game_init() -> (
   );
 }
 
+/**
+ * Replaces the default volume with the file given as the first argument, eg.
+ * `cargo run -- model.vox`, `cargo run -- scan.nrrd`, or for raw volumes the
+ * dimensions and sample type too: `cargo run -- scan.raw 256x256x128 u16`.
+ * Scalar volumes are coloured with `window`
+ */
+fn load_volume_argument(game_state: &mut GameState, args: &[String], window: &ScalarWindowSettings) {
+  let Some(path) = args.get(1) else {
+    return;
+  };
+
+  let loaded_volume = match (args.get(2), args.get(3)) {
+    (Some(dimensions), sample_type) => {
+      RawVolumeDescription::parse(dimensions, sample_type.map_or("u8", |s| s.as_str()))
+        .and_then(|description| raw::load_raw(Path::new(path), &description, window))
+    },
+
+    _ => volume_loaders::load_volume(Path::new(path), window),
+  };
+
+  match loaded_volume {
+    Ok(mut volume) => {
+      volume.fit_to_extent(DEFAULT_VOLUME_EXTENT);
+      game_state.set_volume(volume);
+    },
+
+    Err(e) => {
+      eprintln!("Failed to load {}: {}", path, e);
+    },
+  }
+}
+
+/**
+ * Reads the values shown as black and white from `--window MIN:MAX`, and the
+ * value voxels must be above to be shown from `--threshold T`, removing them
+ * from `args`. Either is chosen from the volume's values when not given
+ */
+fn window_arguments(args: &mut Vec<String>) -> ScalarWindowSettings {
+  let mut window = ScalarWindowSettings::default();
+
+  if let Some(flag_index) = args.iter().position(|arg| arg == "--window") {
+    let range = args.get(flag_index + 1).cloned().unwrap_or_default();
+    args.drain(flag_index..(flag_index + 2).min(args.len()));
+
+    match ScalarWindowSettings::parse_range(&range) {
+      Some(range) => window.range = Some(range),
+      None => eprintln!("Invalid window {:?}, expected MIN:MAX with MIN below MAX", range),
+    }
+  }
+
+  if let Some(flag_index) = args.iter().position(|arg| arg == "--threshold") {
+    let threshold = args.get(flag_index + 1).cloned().unwrap_or_default();
+    args.drain(flag_index..(flag_index + 2).min(args.len()));
+
+    match threshold.parse::<f32>() {
+      Ok(threshold) => window.threshold = Some(threshold),
+      Err(_) => eprintln!("Invalid threshold {:?}, expected a number", threshold),
+    }
+  }
+
+  return window;
+}
+
+/**
+ * Reads how frames are presented from `--present-mode fifo|mailbox|immediate`
+ * and `--lock-to-vblank`, which shows one flicker layer per vblank, removing
+ * them from `args`
+ */
+fn presentation_arguments(args: &mut Vec<String>) -> PresentationConfig {
+  let mut presentation = PresentationConfig::default();
+
+  if let Some(flag_index) = args.iter().position(|arg| arg == "--lock-to-vblank") {
+    args.remove(flag_index);
+    presentation.lock_flicker_to_vblank = true;
+  }
+
+  if let Some(flag_index) = args.iter().position(|arg| arg == "--present-mode") {
+    let name = args.get(flag_index + 1).cloned().unwrap_or_default();
+    args.drain(flag_index..(flag_index + 2).min(args.len()));
+
+    match presentation::parse_present_mode(&name) {
+      Some(present_mode) => presentation.present_mode = present_mode,
+      None => eprintln!("Unknown present mode {:?}, expected fifo, mailbox or immediate", name),
+    }
+  }
+
+  return presentation;
+}
+
+/**
+ * Colours the volume with the transfer function file given after
+ * `--transfer-function`, eg. `cargo run -- scan.nrrd --transfer-function
+ * bone.tf`, removing both from `args`
+ */
+fn load_transfer_function_argument(game_state: &mut GameState, args: &mut Vec<String>) {
+  let Some(flag_index) = args.iter().position(|arg| arg == "--transfer-function") else {
+    return;
+  };
+  let Some(path) = args.get(flag_index + 1).cloned() else {
+    eprintln!("--transfer-function needs a file");
+    args.remove(flag_index);
+    return;
+  };
+  args.drain(flag_index..flag_index + 2);
+
+  match TransferFunction::load(Path::new(&path)) {
+    Ok(transfer_function) => {
+      game_state.set_transfer_function(Some(transfer_function));
+    },
+
+    Err(e) => {
+      eprintln!("Failed to load {}: {}", path, e);
+    },
+  }
+}
+
 fn detect_exit_request(
   g: &mut game_loop::GameLoop<GameState,
   game_loop::Time, Window>, event: &Event<()>,
@@ -74,32 +200,3 @@ fn detect_exit_request(
     g.exit();
   };
 }
-
-fn detect_change_framerate(
-  g: &mut game_loop::GameLoop<GameState,
-  game_loop::Time, Window>, event: &Event<()>,
-) {
-  match event {
-    Event::WindowEvent {
-      event: WindowEvent::KeyboardInput {
-        input: KeyboardInput {
-          state: ElementState::Pressed,
-          virtual_keycode,
-          ..
-        },
-        ..
-      },
-      ..
-    } => {
-      if virtual_keycode == &Some(VirtualKeyCode::Key1) {
-        g.set_updates_per_second(&g.updates_per_second - 10);
-      }
-
-      if virtual_keycode == &Some(VirtualKeyCode::Key2) {
-        g.set_updates_per_second(&g.updates_per_second + 10);
-      }
-    }
-
-    _ => {}
-  }
-}
@@ -0,0 +1,28 @@
+@group(0) @binding(0)
+var accum_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var weight_texture: texture_2d<f32>;
+
+// A triangle covering the whole frame
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+  let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+  let pixel = vec2<i32>(position.xy);
+  let accum = textureLoad(accum_texture, pixel, 0);
+  let revealage = accum.a;
+  let weight = textureLoad(weight_texture, pixel, 0).r;
+
+  // Nothing was drawn here
+  if (revealage >= 1.0) {
+    discard;
+  }
+
+  // The weighted average colour, covering as much as the voxels together do
+  let color = accum.rgb / max(weight, 1e-5);
+  return vec4<f32>(color, 1.0 - revealage);
+}
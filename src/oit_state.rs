@@ -0,0 +1,255 @@
+use wgpu::{BindGroup, BindGroupLayout, CommandEncoder, Device, RenderPass, RenderPipeline, SurfaceConfiguration, TextureView};
+
+use crate::render_pipeline_state::{Texture, ANAGLYPH_WRITE_MASKS};
+
+/**
+ * The sum of every translucent voxel's weighted, premultiplied colour, and in
+ * alpha the product of their transparencies (the revealage): how much of the
+ * background still shows through
+ */
+pub const ACCUM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/**
+ * The sum of every translucent voxel's weighted alpha, which the colours are
+ * divided by to average them
+ */
+pub const WEIGHT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+/**
+ * The targets and composite pass for weighted blended order-independent
+ * transparency. Voxels are first accumulated into the targets in any order,
+ * then the composite pass averages them over the frame
+ */
+pub struct OitState {
+  pub composite_pipeline          : RenderPipeline,
+
+  /// Composite only the left eye's red or the right eye's green and blue,
+  /// for anaglyph stereo
+  pub anaglyph_composite_pipelines: [RenderPipeline; 2],
+
+  bind_group_layout: BindGroupLayout,
+  accum_view       : TextureView,
+  weight_view      : TextureView,
+  bind_group       : BindGroup,
+}
+
+impl OitState {
+
+  pub fn new(device: &Device, config: &SurfaceConfiguration) -> Self {
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label  : Some("OIT composite bind group layout"),
+      entries: &[0, 1].map(|binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty        : wgpu::BindingType::Texture {
+          sample_type   : wgpu::TextureSampleType::Float { filterable: false },
+          view_dimension: wgpu::TextureViewDimension::D2,
+          multisampled  : false,
+        },
+        count     : None,
+      }),
+    });
+
+    let composite_pipeline = Self::configure_composite_pipeline(
+      device,
+      &bind_group_layout,
+      config,
+      wgpu::ColorWrites::ALL,
+    );
+    let anaglyph_composite_pipelines = ANAGLYPH_WRITE_MASKS.map(|write_mask| Self::configure_composite_pipeline(
+      device,
+      &bind_group_layout,
+      config,
+      write_mask,
+    ));
+
+    let (accum_view, weight_view, bind_group) = Self::create_targets(device, config, &bind_group_layout);
+
+    return Self {
+      composite_pipeline,
+      anaglyph_composite_pipelines,
+      bind_group_layout,
+      accum_view,
+      weight_view,
+      bind_group,
+    };
+  }
+
+  /**
+   * Recreates the targets to match the frame size
+   */
+  pub fn resize(&mut self, device: &Device, config: &SurfaceConfiguration) {
+    (self.accum_view, self.weight_view, self.bind_group) = Self::create_targets(
+      device,
+      config,
+      &self.bind_group_layout,
+    );
+  }
+
+  /**
+   * How voxels are blended into the targets: colours are summed, and each
+   * voxel's transparency multiplies the alpha. Both targets blend the same
+   * way, since not every adapter can blend targets independently
+   */
+  pub fn accumulate_targets() -> [Option<wgpu::ColorTargetState>; 2] {
+    let blend = wgpu::BlendState {
+      color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation : wgpu::BlendOperation::Add,
+      },
+      alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation : wgpu::BlendOperation::Add,
+      },
+    };
+
+    return [ACCUM_FORMAT, WEIGHT_FORMAT].map(|format| Some(wgpu::ColorTargetState {
+      format,
+      blend     : Some(blend),
+      write_mask: wgpu::ColorWrites::ALL,
+    }));
+  }
+
+  /**
+   * Starts a pass accumulating voxels into the cleared targets
+   */
+  pub fn begin_accumulate_pass<'a>(&'a self, encoder: &'a mut CommandEncoder) -> RenderPass<'a> {
+    return encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("OIT accumulate pass"),
+      color_attachments: &[
+        Some(wgpu::RenderPassColorAttachment {
+          view          : &self.accum_view,
+          resolve_target: None,
+          ops           : wgpu::Operations {
+            load : wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            store: true,
+          },
+        }),
+        Some(wgpu::RenderPassColorAttachment {
+          view          : &self.weight_view,
+          resolve_target: None,
+          ops           : wgpu::Operations {
+            load : wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: true,
+          },
+        }),
+      ],
+      depth_stencil_attachment: None,
+    });
+  }
+
+  /**
+   * Draws the accumulated voxels over the frame, with a composite pipeline
+   * already set
+   */
+  pub fn composite<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+
+  fn create_targets(
+    device: &Device,
+    config: &SurfaceConfiguration,
+    bind_group_layout: &BindGroupLayout,
+  ) -> (TextureView, TextureView, BindGroup) {
+    let create_view = |label, format| {
+      let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+          width: config.width,
+          height: config.height,
+          depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+          | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: Default::default(),
+      });
+      return texture.create_view(&wgpu::TextureViewDescriptor::default());
+    };
+
+    let accum_view = create_view("OIT accum texture", ACCUM_FORMAT);
+    let weight_view = create_view("OIT weight texture", WEIGHT_FORMAT);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label  : Some("OIT composite bind group"),
+      layout : bind_group_layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding : 0,
+          resource: wgpu::BindingResource::TextureView(&accum_view),
+        },
+        wgpu::BindGroupEntry {
+          binding : 1,
+          resource: wgpu::BindingResource::TextureView(&weight_view),
+        },
+      ],
+    });
+
+    return (accum_view, weight_view, bind_group);
+  }
+
+  fn configure_composite_pipeline(
+    device: &Device,
+    bind_group_layout: &BindGroupLayout,
+    config: &SurfaceConfiguration,
+    write_mask: wgpu::ColorWrites,
+  ) -> RenderPipeline {
+    let shader = device.create_shader_module(
+      wgpu::include_wgsl!("oit_composite.wgsl"),
+    );
+
+    let pipeline_layout = device.create_pipeline_layout(
+      &wgpu::PipelineLayoutDescriptor {
+        label               : Some("OIT composite pipeline layout"),
+        bind_group_layouts  : &[bind_group_layout],
+        push_constant_ranges: &[],
+      },
+    );
+
+    return device.create_render_pipeline(
+      &wgpu::RenderPipelineDescriptor {
+        label : Some("OIT composite pipeline"),
+        layout: Some(&pipeline_layout),
+
+        // A single triangle covering the frame, made in the shader
+        vertex: wgpu::VertexState {
+          module      : &shader,
+          entry_point : "vs_main",
+          buffers     : &[],
+        },
+
+        // Blends the averaged voxels over the background by how much of it
+        // they cover
+        fragment: Some(wgpu::FragmentState {
+          module      : &shader,
+          entry_point : "fs_main",
+          targets     : &[Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend : Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask,
+          })],
+        }),
+
+        primitive: wgpu::PrimitiveState::default(),
+
+        // Drawn in the same pass layout as the voxels, but never depth tested
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format              : Texture::DEPTH_FORMAT,
+          depth_write_enabled : false,
+          depth_compare       : wgpu::CompareFunction::Always,
+          stencil             : wgpu::StencilState::default(),
+          bias                : wgpu::DepthBiasState::default(),
+        }),
+
+        multisample: wgpu::MultisampleState::default(),
+        multiview  : None,
+      },
+    );
+  }
+}
@@ -0,0 +1,70 @@
+use game_loop::winit::window::Window;
+use wgpu::PresentMode;
+
+/**
+ * How frames are presented to the window, chosen when it's created
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PresentationConfig {
+  /// Fifo waits for each vblank; Mailbox replaces queued frames without
+  /// tearing; Immediate shows frames straight away, and may tear
+  pub present_mode: PresentMode,
+
+  /// Shows exactly one flicker layer per vblank, instead of timing layers by
+  /// volumes per second. Only possible with [PresentMode::Fifo], where every
+  /// presented frame lasts one vblank
+  pub lock_flicker_to_vblank: bool,
+}
+
+impl Default for PresentationConfig {
+  fn default() -> Self {
+    return Self {
+      present_mode          : PresentMode::Fifo,
+      lock_flicker_to_vblank: false,
+    };
+  }
+}
+
+impl PresentationConfig {
+
+  /**
+   * Whether each flicker layer can be locked to a vblank when frames are
+   * presented with `present_mode`
+   */
+  pub fn locks_flicker_to_vblank(&self, present_mode: PresentMode) -> bool {
+    return self.lock_flicker_to_vblank && present_mode == PresentMode::Fifo;
+  }
+}
+
+/**
+ * The present mode named `name` (`fifo`, `mailbox` or `immediate`), as given
+ * on the command line
+ */
+pub fn parse_present_mode(name: &str) -> Option<PresentMode> {
+  match name.to_ascii_lowercase().as_str() {
+    "fifo" => return Some(PresentMode::Fifo),
+    "mailbox" => return Some(PresentMode::Mailbox),
+    "immediate" => return Some(PresentMode::Immediate),
+    _ => return None,
+  }
+}
+
+/**
+ * The requested present mode if the surface supports it, otherwise
+ * [PresentMode::Fifo], which every surface does
+ */
+pub fn choose_present_mode(requested: PresentMode, supported: &[PresentMode]) -> PresentMode {
+  if supported.contains(&requested) {
+    return requested;
+  }
+  return PresentMode::Fifo;
+}
+
+/**
+ * The refresh rate in Hz of the monitor the window is on, if the platform
+ * reports it
+ */
+pub fn refresh_rate(window: &Window) -> Option<f32> {
+  let millihertz = window.current_monitor()?.refresh_rate_millihertz()?;
+  return Some(millihertz as f32 / 1000.0);
+}
@@ -0,0 +1,142 @@
+struct Camera {
+  view_pos: vec4<f32>,
+  view_proj: mat4x4<f32>,
+  inverse_view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct RayMarch {
+  // The corner of the volume with the smallest coordinates, and in w the
+  // distance between samples
+  volume_min: vec4<f32>,
+  // The size of the volume, and in w the distance between voxel centres
+  volume_size: vec4<f32>,
+  window_normal: vec4<f32>,
+  // The nearest and furthest depths along the normal which are shown
+  window_depths: vec4<f32>,
+};
+@group(1) @binding(0)
+var volume: texture_3d<f32>;
+@group(1) @binding(1)
+var<uniform> ray_march: RayMarch;
+// The scalar value of each voxel, and whether it's occupied
+@group(1) @binding(2)
+var values: texture_3d<f32>;
+
+struct TransferFunction {
+  value_range: vec2<f32>,
+  enabled: u32,
+};
+@group(2) @binding(0)
+var transfer_lut: texture_1d<f32>;
+@group(2) @binding(1)
+var<uniform> transfer_function: TransferFunction;
+
+// The colour and opacity the transfer function gives a scalar value
+fn transfer(value: f32) -> vec4<f32> {
+  let range = transfer_function.value_range;
+  let position = clamp((value - range.x) / max(range.y - range.x, 1e-6), 0.0, 1.0);
+  let size = i32(textureDimensions(transfer_lut));
+  return textureLoad(transfer_lut, i32(round(position * f32(size - 1))), 0);
+}
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  // Where the pixel's ray crosses the near and far planes, in homogeneous
+  // world coordinates, which interpolate linearly across the frame
+  @location(0) near: vec4<f32>,
+  @location(1) far: vec4<f32>,
+};
+
+// A single triangle covering the viewport
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+  let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+  let ndc = uv * 2.0 - 1.0;
+
+  var output: VertexOutput;
+  output.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+  output.near = camera.inverse_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+  output.far = camera.inverse_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+  return output;
+}
+
+// The part of the ray from `origin` along `direction` between two parallel
+// planes, as distances along it
+fn between_planes(
+  origin: vec3<f32>,
+  direction: vec3<f32>,
+  normal: vec3<f32>,
+  depths: vec2<f32>,
+) -> vec2<f32> {
+  let origin_depth = dot(origin, normal);
+  let speed = dot(direction, normal);
+  if (abs(speed) < 1e-6) {
+    if (origin_depth >= depths.x && origin_depth <= depths.y) {
+      return vec2<f32>(0.0, 1e30);
+    }
+    return vec2<f32>(1.0, 0.0);
+  }
+
+  let crossings = (depths - origin_depth) / speed;
+  return vec2<f32>(min(crossings.x, crossings.y), max(crossings.x, crossings.y));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let origin = in.near.xyz / in.near.w;
+  let direction = normalize(in.far.xyz / in.far.w - origin);
+
+  // Clips the ray to the volume's box, then to the flicker's depth window
+  var span = vec2<f32>(0.0, 1e30);
+  for (var axis = 0; axis < 3; axis++) {
+    let normal = vec3<f32>(f32(axis == 0), f32(axis == 1), f32(axis == 2));
+    let box_min = dot(ray_march.volume_min.xyz, normal);
+    let box_span = between_planes(
+      origin,
+      direction,
+      normal,
+      vec2<f32>(box_min, box_min + dot(ray_march.volume_size.xyz, normal)),
+    );
+    span = vec2<f32>(max(span.x, box_span.x), min(span.y, box_span.y));
+  }
+  let window_span = between_planes(
+    origin,
+    direction,
+    ray_march.window_normal.xyz,
+    ray_march.window_depths.xy,
+  );
+  span = vec2<f32>(max(span.x, window_span.x), min(span.y, window_span.y));
+  if (span.x >= span.y) {
+    discard;
+  }
+
+  let step_length = ray_march.volume_min.w;
+  let dimensions = vec3<i32>(textureDimensions(volume));
+  let step_count = min(i32(ceil((span.y - span.x) / step_length)), 4096);
+
+  // Composites the samples front to back, premultiplied, stopping once
+  // nothing behind them can show through
+  var color = vec4<f32>(0.0);
+  for (var i = 0; i < step_count && color.a < 0.99; i++) {
+    let t = span.x + (f32(i) + 0.5) * step_length;
+    let position = (origin + direction * t - ray_march.volume_min.xyz) / ray_march.volume_size.xyz;
+    let voxel = clamp(vec3<i32>(floor(position * vec3<f32>(dimensions))), vec3<i32>(0), dimensions - 1);
+    var texel = textureLoad(volume, voxel, 0);
+    if (transfer_function.enabled != 0u) {
+      let value = textureLoad(values, voxel, 0);
+      texel = transfer(value.r) * value.g;
+    }
+
+    // A voxel's alpha is its opacity across the whole voxel, so each sample
+    // only covers the share of it the step crosses
+    let alpha = 1.0 - pow(1.0 - min(texel.a, 0.9999), step_length / ray_march.volume_size.w);
+    color += (1.0 - color.a) * vec4<f32>(texel.rgb * alpha, alpha);
+  }
+
+  if (color.a <= 0.0) {
+    discard;
+  }
+  return color;
+}
@@ -0,0 +1,339 @@
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, SurfaceConfiguration, util::DeviceExt};
+
+use crate::{
+  flicker::DepthWindow,
+  render_pipeline_state::{ModePipelines, Texture, ANAGLYPH_WRITE_MASKS},
+  voxel_volume::VoxelVolume,
+};
+
+/**
+ * The number of samples taken through each voxel a ray crosses
+ */
+pub const SAMPLES_PER_VOXEL: f32 = 2.0;
+
+/**
+ * Where the volume is and which part of it is shown, for the ray marching
+ * shader
+ */
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RayMarchUniform {
+  /// The corner of the volume with the smallest coordinates, and in w the
+  /// distance between samples
+  pub volume_min   : [f32; 4],
+
+  /// The size of the volume, and in w the distance between voxel centres
+  pub volume_size  : [f32; 4],
+
+  pub window_normal: [f32; 4],
+
+  /// The nearest and furthest depths along the window's normal which are
+  /// shown, in x and y
+  pub window_depths: [f32; 4],
+}
+
+/**
+ * Draws the volume by marching rays through it as a 3D texture, instead of
+ * drawing a cube per voxel. The voxels' colours are composited front to back
+ * along each ray, and flicker layers are shown by clipping the rays to the
+ * layers' [DepthWindow]
+ */
+pub struct RayMarchState {
+  pub pipelines: ModePipelines,
+
+  bind_group_layout: BindGroupLayout,
+  uniform_buffer   : Buffer,
+  bind_group       : BindGroup,
+}
+
+impl RayMarchUniform {
+
+  /**
+   * Places `volume`'s voxels as cubes filling the spacing around their
+   * centres, like the instanced cubes, showing only `window`
+   */
+  pub fn new(volume: &VoxelVolume, window: &DepthWindow) -> Self {
+    let half_voxel = volume.spacing / 2.0;
+    let [x, y, z] = volume.dimensions.map(|d| d as f32 * volume.spacing);
+
+    return Self {
+      volume_min   : [
+        volume.origin.x - half_voxel,
+        volume.origin.y - half_voxel,
+        volume.origin.z - half_voxel,
+        volume.spacing / SAMPLES_PER_VOXEL,
+      ],
+      volume_size  : [x, y, z, volume.spacing],
+      window_normal: [window.normal.x, window.normal.y, window.normal.z, 0.0],
+      window_depths: [window.depths.start, window.depths.end, 0.0, 0.0],
+    };
+  }
+}
+
+impl RayMarchState {
+
+  pub fn new(
+    device: &Device,
+    queue: &Queue,
+    camera_bind_group_layout: &BindGroupLayout,
+    transfer_function_bind_group_layout: &BindGroupLayout,
+    config: &SurfaceConfiguration,
+    volume: &VoxelVolume,
+  ) -> Self {
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label  : Some("Ray march bind group layout"),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding   : 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty        : wgpu::BindingType::Texture {
+            sample_type   : wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D3,
+            multisampled  : false,
+          },
+          count     : None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding   : 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty        : wgpu::BindingType::Buffer {
+            ty                : wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size  : None,
+          },
+          count     : None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding   : 2,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty        : wgpu::BindingType::Texture {
+            sample_type   : wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D3,
+            multisampled  : false,
+          },
+          count     : None,
+        },
+      ],
+    });
+
+    let bind_group_layouts = [camera_bind_group_layout, &bind_group_layout, transfer_function_bind_group_layout];
+    let pipelines = ModePipelines {
+      full_color: Self::configure_pipeline(
+        device,
+        &bind_group_layouts,
+        config,
+        wgpu::ColorWrites::ALL,
+      ),
+      anaglyph  : ANAGLYPH_WRITE_MASKS.map(|write_mask| Self::configure_pipeline(
+        device,
+        &bind_group_layouts,
+        config,
+        write_mask,
+      )),
+    };
+
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label   : Some("Ray march uniform buffer"),
+      contents: bytemuck::cast_slice(&[<RayMarchUniform as bytemuck::Zeroable>::zeroed()]),
+      usage   : wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let bind_group = Self::upload_volume(device, queue, &bind_group_layout, &uniform_buffer, volume);
+
+    return Self {
+      pipelines,
+      bind_group_layout,
+      uniform_buffer,
+      bind_group,
+    };
+  }
+
+  /**
+   * Uploads the voxels of `volume` to be marched through
+   */
+  pub fn set_volume(&mut self, device: &Device, queue: &Queue, volume: &VoxelVolume) {
+    self.bind_group = Self::upload_volume(
+      device,
+      queue,
+      &self.bind_group_layout,
+      &self.uniform_buffer,
+      volume,
+    );
+  }
+
+  /**
+   * Places the volume and shows only the part of it in `window`
+   */
+  pub fn update(&self, queue: &Queue, volume: &VoxelVolume, window: &DepthWindow) {
+    queue.write_buffer(
+      &self.uniform_buffer,
+      0,
+      bytemuck::cast_slice(&[RayMarchUniform::new(volume, window)]),
+    );
+  }
+
+  /**
+   * Marches through the volume seen through a camera, with one of the
+   * [RayMarchState::pipelines] and the transfer function already set
+   */
+  pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>, camera_bind_group: &'a BindGroup) {
+    render_pass.set_bind_group(0, camera_bind_group, &[]);
+    render_pass.set_bind_group(1, &self.bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+  }
+
+  /**
+   * Creates textures holding the colours of `volume`'s voxels, with empty
+   * voxels fully transparent, and their values for the transfer function,
+   * and binds them for marching through
+   */
+  fn upload_volume(
+    device: &Device,
+    queue: &Queue,
+    bind_group_layout: &BindGroupLayout,
+    uniform_buffer: &Buffer,
+    volume: &VoxelVolume,
+  ) -> BindGroup {
+    let [width, height, depth] = volume.dimensions.map(|d| d.max(1));
+    let size = wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: depth,
+    };
+
+    let create_view = |label, format, bytes_per_texel: u32, texels: &[u8]| {
+      let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D3,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+          | wgpu::TextureUsages::COPY_DST,
+        view_formats: Default::default(),
+      });
+
+      queue.write_texture(
+        wgpu::ImageCopyTexture {
+          texture: &texture,
+          mip_level: 0,
+          origin: wgpu::Origin3d::ZERO,
+          aspect: wgpu::TextureAspect::All,
+        },
+        texels,
+        wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: std::num::NonZeroU32::new(width * bytes_per_texel),
+          rows_per_image: std::num::NonZeroU32::new(height),
+        },
+        size,
+      );
+      return texture.create_view(&wgpu::TextureViewDescriptor::default());
+    };
+
+    // The textures are laid out along x, then y, then z, unlike the volume.
+    // Values are paired with 1 where the voxel is occupied
+    let texel_count = (width * height * depth) as usize;
+    let mut colors = vec![[0u8; 4]; texel_count];
+    let mut values = vec![[0.0f32; 2]; texel_count];
+    for ([x, y, z], voxel) in volume.occupied_voxels() {
+      let texel = ((z * height + y) * width + x) as usize;
+      colors[texel] = voxel.color;
+      values[texel] = [voxel.value, 1.0];
+    }
+
+    let color_view = create_view(
+      "Ray march volume texture",
+      wgpu::TextureFormat::Rgba8Unorm,
+      4,
+      bytemuck::cast_slice(&colors),
+    );
+    let value_view = create_view(
+      "Ray march value texture",
+      wgpu::TextureFormat::Rg32Float,
+      8,
+      bytemuck::cast_slice(&values),
+    );
+    return device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label  : Some("Ray march bind group"),
+      layout : bind_group_layout,
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding : 0,
+          resource: wgpu::BindingResource::TextureView(&color_view),
+        },
+        wgpu::BindGroupEntry {
+          binding : 1,
+          resource: uniform_buffer.as_entire_binding(),
+        },
+        wgpu::BindGroupEntry {
+          binding : 2,
+          resource: wgpu::BindingResource::TextureView(&value_view),
+        },
+      ],
+    });
+  }
+
+  /**
+   * `bind_group_layouts` are the camera's, the volume's and the transfer
+   * function's
+   */
+  fn configure_pipeline(
+    device: &Device,
+    bind_group_layouts: &[&BindGroupLayout],
+    config: &SurfaceConfiguration,
+    write_mask: wgpu::ColorWrites,
+  ) -> RenderPipeline {
+    let shader = device.create_shader_module(
+      wgpu::include_wgsl!("ray_march.wgsl"),
+    );
+
+    let pipeline_layout = device.create_pipeline_layout(
+      &wgpu::PipelineLayoutDescriptor {
+        label               : Some("Ray march pipeline layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+      },
+    );
+
+    return device.create_render_pipeline(
+      &wgpu::RenderPipelineDescriptor {
+        label : Some("Ray march pipeline"),
+        layout: Some(&pipeline_layout),
+
+        // A single triangle covering the viewport, made in the shader
+        vertex: wgpu::VertexState {
+          module      : &shader,
+          entry_point : "vs_main",
+          buffers     : &[],
+        },
+
+        // The rays' colours are premultiplied by how much they cover
+        fragment: Some(wgpu::FragmentState {
+          module      : &shader,
+          entry_point : "fs_main",
+          targets     : &[Some(wgpu::ColorTargetState {
+            format: config.format,
+            blend : Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            write_mask,
+          })],
+        }),
+
+        primitive: wgpu::PrimitiveState::default(),
+
+        // Drawn in the same pass layout as the voxels, but never depth tested
+        depth_stencil: Some(wgpu::DepthStencilState {
+          format              : Texture::DEPTH_FORMAT,
+          depth_write_enabled : false,
+          depth_compare       : wgpu::CompareFunction::Always,
+          stencil             : wgpu::StencilState::default(),
+          bias                : wgpu::DepthBiasState::default(),
+        }),
+
+        multisample: wgpu::MultisampleState::default(),
+        multiview  : None,
+      },
+    );
+  }
+}
@@ -1,13 +1,80 @@
 use wgpu::{RenderPipeline, Device, BindGroupLayout, SurfaceConfiguration};
 
-use crate::instance::{InstanceBuffer, InstanceRaw, NUM_INSTANCES_PER_ROW, NUM_INSTANCES_PER_COL};
+use crate::{
+  camera::stereo_camera::StereoEye,
+  flicker::FlickerConfig,
+  flicker_scheduler::{ViewPoint, VoxelScheduler},
+  instance::{InstanceBuffer, InstanceRaw},
+  oit_state::OitState,
+  voxel_volume::VoxelVolume,
+};
+
+/**
+ * The colours the left and right eye are drawn in for anaglyph stereo: red,
+ * and cyan (green and blue)
+ */
+pub const ANAGLYPH_WRITE_MASKS: [wgpu::ColorWrites; 2] = [
+  wgpu::ColorWrites::RED.union(wgpu::ColorWrites::ALPHA),
+  wgpu::ColorWrites::GREEN.union(wgpu::ColorWrites::BLUE),
+];
+
+/**
+ * How overlapping voxels are combined
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+  /// Depth tested, so nearer voxels cover the ones behind them
+  Opaque,
+
+  /// Weighted blended order-independent transparency: every voxel stays
+  /// visible through the ones in front of it
+  WeightedBlended,
+
+  /// Not depth tested, adding up the colours of every voxel along each
+  /// pixel, like an X-ray
+  Additive,
+
+  /// Not depth tested, showing the brightest voxel along each pixel
+  /// (maximum intensity projection)
+  MaximumIntensity,
+}
+
+/**
+ * How the volume is turned into pixels
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderBackend {
+  /// A cube instance per voxel, combined by the [RenderMode]
+  InstancedCubes,
+
+  /// Rays marched through the volume as a 3D texture, see
+  /// [crate::ray_march_state::RayMarchState]
+  RayMarched,
+}
+
+/**
+ * The pipelines drawing voxels straight into the frame in one render mode:
+ * one for all colours, and one for each eye's colours in anaglyph stereo
+ */
+pub struct ModePipelines {
+  pub full_color: RenderPipeline,
+  pub anaglyph  : [RenderPipeline; 2],
+}
 
 pub struct RenderPipelineState {
-  pub render_pipeline : RenderPipeline,
+  pub opaque_pipelines           : ModePipelines,
+  pub additive_pipelines         : ModePipelines,
+  pub maximum_intensity_pipelines: ModePipelines,
+
+  /// Accumulates translucent voxels into the [crate::oit_state::OitState]
+  /// targets, for [RenderMode::WeightedBlended]
+  pub accumulate_pipeline: RenderPipeline,
+
   pub instance_buffer : InstanceBuffer,
   pub depth_texture   : Texture,
-  pub instances_to_render_start: u32,
-  pub instances_to_render_end: u32,
+
+  /// Decides which of the instances are drawn each frame
+  pub voxel_scheduler : VoxelScheduler,
 }
 
 #[repr(C)]
@@ -23,18 +90,81 @@ pub struct Texture {
   pub sampler : wgpu::Sampler,
 }
 
+impl RenderMode {
+
+  pub fn next(&self) -> Self {
+    match self {
+      RenderMode::Opaque => return RenderMode::WeightedBlended,
+      RenderMode::WeightedBlended => return RenderMode::Additive,
+      RenderMode::Additive => return RenderMode::MaximumIntensity,
+      RenderMode::MaximumIntensity => return RenderMode::Opaque,
+    }
+  }
+}
+
+impl RenderBackend {
+
+  pub fn next(&self) -> Self {
+    match self {
+      RenderBackend::InstancedCubes => return RenderBackend::RayMarched,
+      RenderBackend::RayMarched => return RenderBackend::InstancedCubes,
+    }
+  }
+}
+
+impl ModePipelines {
+
+  /**
+   * The pipeline drawing all colours, or only `anaglyph_eye`'s
+   */
+  pub fn for_eye(&self, anaglyph_eye: Option<StereoEye>) -> &RenderPipeline {
+    match anaglyph_eye {
+      Some(StereoEye::Left) => return &self.anaglyph[0],
+      Some(StereoEye::Right) => return &self.anaglyph[1],
+      None => return &self.full_color,
+    }
+  }
+}
+
 impl RenderPipelineState {
 
   pub fn new(
     device: &Device,
     camera_bind_group_layout: &BindGroupLayout,
+    transfer_function_bind_group_layout: &BindGroupLayout,
     config: &SurfaceConfiguration,
+    volume: &VoxelVolume,
+    view_point: ViewPoint,
   ) -> Self {
+    let bind_group_layouts = [camera_bind_group_layout, transfer_function_bind_group_layout];
+
+    let mode_pipelines = |mode| ModePipelines {
+      full_color: Self::configure_render_pipeline(
+        device,
+        &bind_group_layouts,
+        config,
+        mode,
+        wgpu::ColorWrites::ALL,
+      ),
+      anaglyph  : ANAGLYPH_WRITE_MASKS.map(|write_mask| Self::configure_render_pipeline(
+        device,
+        &bind_group_layouts,
+        config,
+        mode,
+        write_mask,
+      )),
+    };
 
-    let render_pipeline = Self::configure_render_pipeline(
+    let opaque_pipelines = mode_pipelines(RenderMode::Opaque);
+    let additive_pipelines = mode_pipelines(RenderMode::Additive);
+    let maximum_intensity_pipelines = mode_pipelines(RenderMode::MaximumIntensity);
+
+    let accumulate_pipeline = Self::configure_render_pipeline(
       device,
-      camera_bind_group_layout,
+      &bind_group_layouts,
       config,
+      RenderMode::WeightedBlended,
+      wgpu::ColorWrites::ALL,
     );
 
     let depth_texture = Texture::create_depth_texture(
@@ -43,23 +173,45 @@ impl RenderPipelineState {
       "depth_texture",
     );
 
-    let instance_buffer = InstanceBuffer::new(&device);
-    let instances_to_render_start = 0;
-    let instances_to_render_end = NUM_INSTANCES_PER_ROW * NUM_INSTANCES_PER_COL;
+    let mut voxel_scheduler = VoxelScheduler::new(FlickerConfig::default());
+    let schedule = voxel_scheduler.schedule(volume, view_point);
+    let instance_buffer = InstanceBuffer::from_schedule(device, volume, &schedule);
+    voxel_scheduler.show_slab(0);
 
     return Self {
-      render_pipeline,
+      opaque_pipelines,
+      additive_pipelines,
+      maximum_intensity_pipelines,
+      accumulate_pipeline,
       instance_buffer,
       depth_texture,
-      instances_to_render_start,
-      instances_to_render_end,
+      voxel_scheduler,
     };
   }
 
+  /**
+   * The pipeline drawing voxels in `mode`, in all colours or only
+   * `anaglyph_eye`'s. In [RenderMode::WeightedBlended] the voxels are
+   * accumulated, and the eye's colours are picked when compositing them
+   */
+  pub fn pipeline(&self, mode: RenderMode, anaglyph_eye: Option<StereoEye>) -> &RenderPipeline {
+    match mode {
+      RenderMode::Opaque => return self.opaque_pipelines.for_eye(anaglyph_eye),
+      RenderMode::WeightedBlended => return &self.accumulate_pipeline,
+      RenderMode::Additive => return self.additive_pipelines.for_eye(anaglyph_eye),
+      RenderMode::MaximumIntensity => return self.maximum_intensity_pipelines.for_eye(anaglyph_eye),
+    }
+  }
+
+  /**
+   * `bind_group_layouts` are the camera's and the transfer function's
+   */
   fn configure_render_pipeline(
     device: &Device,
-    camera_bind_group_layout: &BindGroupLayout,
+    bind_group_layouts: &[&BindGroupLayout],
     config: &SurfaceConfiguration,
+    mode: RenderMode,
+    write_mask: wgpu::ColorWrites,
   ) -> RenderPipeline {
     let shader = device.create_shader_module(
       wgpu::include_wgsl!("shader.wgsl"),
@@ -68,11 +220,65 @@ impl RenderPipelineState {
     let render_pipeline_layout = device.create_pipeline_layout(
       &wgpu::PipelineLayoutDescriptor {
         label               : Some("Render pipeline layout"),
-        bind_group_layouts  : &[camera_bind_group_layout],
+        bind_group_layouts,
         push_constant_ranges: &[],
       },
     );
   
+    // Voxels drawn straight into the frame are blended into it by the mode,
+    // and only depth tested when opaque
+    let direct = |blend: wgpu::BlendState, depth_tested: bool| (
+      "fs_main",
+      vec![Some(wgpu::ColorTargetState {
+
+        // Uses the `surface` format so copying to it is easy
+        format: config.format,
+        blend : Some(blend),
+
+        // Tells `wgpu` which colors to write to, usually all of red,
+        // blue, green and alpha
+        write_mask,
+      })],
+      Some(wgpu::DepthStencilState {
+        format              : Texture::DEPTH_FORMAT,
+        depth_write_enabled : depth_tested,
+        depth_compare       : if depth_tested { wgpu::CompareFunction::Less } else { wgpu::CompareFunction::Always },
+        stencil             : wgpu::StencilState::default(),
+        bias                : wgpu::DepthBiasState::default(),
+      }),
+    );
+
+    let (fragment_entry_point, targets, depth_stencil) = match mode {
+
+      // Blends translucent voxels over the pixel data behind them
+      RenderMode::Opaque => direct(wgpu::BlendState::ALPHA_BLENDING, true),
+
+      // Voxels are summed in any order, so nothing is depth tested
+      RenderMode::WeightedBlended => (
+        "fs_accumulate",
+        OitState::accumulate_targets().to_vec(),
+        None,
+      ),
+
+      RenderMode::Additive => direct(wgpu::BlendState {
+        color: wgpu::BlendComponent {
+          src_factor: wgpu::BlendFactor::SrcAlpha,
+          dst_factor: wgpu::BlendFactor::One,
+          operation : wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+      }, false),
+
+      RenderMode::MaximumIntensity => {
+        let max = wgpu::BlendComponent {
+          src_factor: wgpu::BlendFactor::One,
+          dst_factor: wgpu::BlendFactor::One,
+          operation : wgpu::BlendOperation::Max,
+        };
+        direct(wgpu::BlendState { color: max, alpha: max }, false)
+      },
+    };
+
     let render_pipeline = device.create_render_pipeline(
       &wgpu::RenderPipelineDescriptor {
         label : Some("Render pipeline"),
@@ -91,24 +297,12 @@ impl RenderPipelineState {
           ],
         },
   
-        // Stores color data to the `surface`
+        // Stores color data to the `surface`, or to the transparency
+        // accumulation targets
         fragment: Some(wgpu::FragmentState {
           module      : &shader,
-          entry_point : "fs_main",
-  
-          // Tells `wgpu` what color outputs it should set up. Currently,
-          // we only need one for the surface.
-          targets: &[Some(wgpu::ColorTargetState {
-  
-            // Uses the `surface` format so copying to it is easy
-            format: config.format,
-  
-            // Tells the blending to replace old pixel data with new data
-            blend: Some(wgpu::BlendState::REPLACE),
-  
-            // Tells `wgpu` to write to all colors: red, blue, green, alpha
-            write_mask: wgpu::ColorWrites::ALL,
-          })],
+          entry_point : fragment_entry_point,
+          targets     : &targets,
         }),
   
         primitive: wgpu::PrimitiveState {
@@ -128,13 +322,7 @@ impl RenderPipelineState {
           conservative      : false,
         },
   
-        depth_stencil : Some(wgpu::DepthStencilState {
-          format              : Texture::DEPTH_FORMAT,
-          depth_write_enabled : true,
-          depth_compare       : wgpu::CompareFunction::Less,
-          stencil             : wgpu::StencilState::default(),
-          bias                : wgpu::DepthBiasState::default(),
-        }),
+        depth_stencil,
   
         multisample: wgpu::MultisampleState {
           count : 1,
@@ -1,36 +1,155 @@
 
 use game_loop::winit::{dpi::PhysicalSize, window::Window};
-use wgpu::{SurfaceConfiguration, Surface, Device, Queue};
+use wgpu::{SurfaceConfiguration, Surface, Device, Queue, SurfaceError, SurfaceTexture, TextureView};
+
+use crate::presentation::{self, PresentationConfig};
+
+/**
+ * The format of the offscreen colour texture used in headless mode
+ */
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 
 pub struct RenderState {
   pub size    : PhysicalSize<u32>,
-  pub surface : Surface,
+  pub target  : RenderTarget,
   pub device  : Device,
   pub queue   : Queue,
   pub config  : SurfaceConfiguration,
+
+  /// The refresh rate of the window's monitor in Hz, if it's known
+  pub refresh_rate: Option<f32>,
+}
+
+/**
+ * Where frames are rendered to; selected when the [RenderState] is created
+ */
+pub enum RenderTarget {
+  /// A window surface which is presented on screen
+  Surface(Surface),
+
+  /// A texture which is never presented, for rendering without a window
+  Offscreen(wgpu::Texture),
+}
+
+/**
+ * A frame acquired from the [RenderTarget], ready to be drawn into
+ */
+pub struct RenderFrame {
+  pub view        : TextureView,
+  surface_texture : Option<SurfaceTexture>,
 }
 
 impl RenderState {
 
-  pub async fn new(window: &Window) -> Self {
+  pub async fn new(window: &Window, presentation: &PresentationConfig) -> Self {
     let (
       size,
       surface,
       device,
       queue,
       config,
-    ) = Self::configure_surface(window).await;
+    ) = Self::configure_surface(window, presentation).await;
 
     return Self {
       size,
-      surface,
+      target: RenderTarget::Surface(surface),
+      device,
+      queue,
+      config,
+      refresh_rate: presentation::refresh_rate(window),
+    };
+  }
+
+  /**
+   * Creates a [RenderState] which renders into an offscreen texture instead
+   * of a window surface, using the software fallback adapter
+   */
+  pub async fn new_headless(size: PhysicalSize<u32>) -> Self {
+    let (
+      texture,
+      device,
+      queue,
+      config,
+    ) = Self::configure_offscreen(size).await;
+
+    return Self {
+      size,
+      target: RenderTarget::Offscreen(texture),
       device,
       queue,
       config,
+      refresh_rate: None,
     };
   }
 
-  async fn configure_surface(window: &Window) -> (
+  pub fn is_headless(&self) -> bool {
+    return matches!(self.target, RenderTarget::Offscreen(_));
+  }
+
+  /**
+   * Applies a new size to the render target
+   */
+  pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+    self.size = new_size;
+    self.config.width = new_size.width;
+    self.config.height = new_size.height;
+
+    match &mut self.target {
+      RenderTarget::Surface(surface) => {
+        surface.configure(&self.device, &self.config);
+      },
+
+      RenderTarget::Offscreen(texture) => {
+        *texture = Self::create_offscreen_texture(&self.device, &self.config);
+      },
+    }
+  }
+
+  /**
+   * Gets the texture to draw the next frame into
+   */
+  pub fn acquire_frame(&self) -> Result<RenderFrame, SurfaceError> {
+    match &self.target {
+      RenderTarget::Surface(surface) => {
+        let output = surface.get_current_texture()?;
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        return Ok(RenderFrame {
+          view,
+          surface_texture: Some(output),
+        });
+      },
+
+      RenderTarget::Offscreen(texture) => {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        return Ok(RenderFrame {
+          view,
+          surface_texture: None,
+        });
+      },
+    }
+  }
+
+  async fn request_device(adapter: &wgpu::Adapter) -> (
+    wgpu::Device,
+    wgpu::Queue,
+  ) {
+    return adapter.request_device(
+      &wgpu::DeviceDescriptor {
+        features: wgpu::Features::empty(),
+        limits  : if cfg!(target_arch = "wasm32") {
+          wgpu::Limits::downlevel_webgl2_defaults()
+        } else {
+          wgpu::Limits::default()
+        },
+        label: None,
+      },
+      None,
+    ).await.unwrap();
+  }
+
+  async fn configure_surface(window: &Window, presentation: &PresentationConfig) -> (
     PhysicalSize<u32>,
     wgpu::Surface,
     wgpu::Device,
@@ -41,7 +160,7 @@ impl RenderState {
       * The window size
       */
     let size = window.inner_size();
-  
+
     /*
       * The handle to the GPU
       */
@@ -49,12 +168,12 @@ impl RenderState {
       backends: wgpu::Backends::all(),
       dx12_shader_compiler: Default::default(),
     });
-  
+
     /*
       * This ensures the surface only lives as long as its parent window
       */
     let surface = unsafe { instance.create_surface(window) }.unwrap();
-  
+
     let adapter = instance.request_adapter(
       &wgpu::RequestAdapterOptions {
         power_preference      : wgpu::PowerPreference::default(),
@@ -62,39 +181,124 @@ impl RenderState {
         force_fallback_adapter: true,
       },
     ).await.unwrap();
-  
-    let (device, queue) = adapter.request_device(
-      &wgpu::DeviceDescriptor {
-        features: wgpu::Features::empty(),
-        limits  : if cfg!(target_arch = "wasm32") {
-          wgpu::Limits::downlevel_webgl2_defaults()
-        } else {
-          wgpu::Limits::default()
-        },
-        label: None,
-      },
-      None,
-    ).await.unwrap();
-  
+
+    let (device, queue) = Self::request_device(&adapter).await;
+
     let surface_caps = surface.get_capabilities(&adapter);
-  
+
     let surface_format = surface_caps.formats.iter()
       .copied()
-      .filter(|f| f.describe().srgb)
-      .next()
+      .find(|f| f.describe().srgb)
       .unwrap_or(surface_caps.formats[0]);
-  
+
+    let present_mode = presentation::choose_present_mode(
+      presentation.present_mode,
+      &surface_caps.present_modes,
+    );
+    if present_mode != presentation.present_mode {
+      eprintln!(
+        "{:?} presentation isn't supported, using {:?}",
+        presentation.present_mode,
+        present_mode,
+      );
+    }
+
     let config = wgpu::SurfaceConfiguration {
       usage       : wgpu::TextureUsages::RENDER_ATTACHMENT,
       format      : surface_format,
       width       : size.width,
       height      : size.height,
-      present_mode: surface_caps.present_modes[0],
-      alpha_mode: surface_caps.alpha_modes[0],
+      present_mode,
+      alpha_mode  : surface_caps.alpha_modes[0],
       view_formats: vec![],
     };
-  
+
     surface.configure(&device, &config);
     return (size, surface, device, queue, config);
   }
+
+  async fn configure_offscreen(size: PhysicalSize<u32>) -> (
+    wgpu::Texture,
+    wgpu::Device,
+    wgpu::Queue,
+    SurfaceConfiguration,
+  ) {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+      backends: wgpu::Backends::all(),
+      dx12_shader_compiler: Default::default(),
+    });
+
+    /*
+      * There is no surface to be compatible with, so any adapter will do;
+      * prefer the software one so this works on machines without a GPU
+      */
+    let adapter = match instance.request_adapter(
+      &wgpu::RequestAdapterOptions {
+        power_preference      : wgpu::PowerPreference::default(),
+        compatible_surface    : None,
+        force_fallback_adapter: true,
+      },
+    ).await {
+      Some(adapter) => adapter,
+      None => instance.request_adapter(
+        &wgpu::RequestAdapterOptions {
+          power_preference      : wgpu::PowerPreference::default(),
+          compatible_surface    : None,
+          force_fallback_adapter: false,
+        },
+      ).await.expect("No adapter available for headless rendering"),
+    };
+
+    let (device, queue) = Self::request_device(&adapter).await;
+
+    /*
+      * The surface configuration is kept so the pipeline and depth texture
+      * can be built the same way as with a window
+      */
+    let config = wgpu::SurfaceConfiguration {
+      usage       : wgpu::TextureUsages::RENDER_ATTACHMENT,
+      format      : OFFSCREEN_FORMAT,
+      width       : size.width,
+      height      : size.height,
+      present_mode: wgpu::PresentMode::Fifo,
+      alpha_mode  : wgpu::CompositeAlphaMode::Opaque,
+      view_formats: vec![],
+    };
+
+    let texture = Self::create_offscreen_texture(&device, &config);
+    return (texture, device, queue, config);
+  }
+
+  fn create_offscreen_texture(
+    device: &Device,
+    config: &SurfaceConfiguration,
+  ) -> wgpu::Texture {
+    return device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Offscreen texture"),
+      size: wgpu::Extent3d {
+        width: config.width,
+        height: config.height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: config.format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: Default::default(),
+    });
+  }
+}
+
+impl RenderFrame {
+
+  /**
+   * Shows the frame on screen; offscreen frames stay in their texture
+   */
+  pub fn present(self) {
+    if let Some(surface_texture) = self.surface_texture {
+      surface_texture.present();
+    }
+  }
 }
@@ -0,0 +1,91 @@
+use game_loop::winit::dpi::PhysicalSize;
+use wgpu::{Device, Queue};
+
+use crate::{
+  camera::camera_state::CameraState,
+  flicker::FlickerConfig,
+  flicker_scheduler::VoxelScheduler,
+  instance::InstanceBuffer,
+  voxel_volume::VoxelVolume,
+};
+
+/**
+ * A second voxel view, drawn beside the main one with its own camera, volume
+ * and flicker, eg. for the right eye's retina of a 4D eye pair
+ */
+pub struct RetinaView {
+  pub camera_state   : CameraState,
+  pub volume         : VoxelVolume,
+  pub instance_buffer: InstanceBuffer,
+  pub voxel_scheduler: VoxelScheduler,
+}
+
+impl RetinaView {
+
+  /**
+   * Creates a view of `volume`, flickering through it as `config` describes
+   */
+  pub fn new(
+    device: &Device,
+    size: PhysicalSize<u32>,
+    volume: VoxelVolume,
+    config: FlickerConfig,
+  ) -> Self {
+    let camera_state = CameraState::new(device, size);
+
+    let mut voxel_scheduler = VoxelScheduler::new(config);
+    let schedule = voxel_scheduler.schedule(&volume, camera_state.view_point());
+    let instance_buffer = InstanceBuffer::from_schedule(device, &volume, &schedule);
+    voxel_scheduler.show_slab(0);
+
+    return Self {
+      camera_state,
+      volume,
+      instance_buffer,
+      voxel_scheduler,
+    };
+  }
+
+  /**
+   * Replaces the volume, staying on the same flicker layer
+   */
+  pub fn set_volume(&mut self, device: &Device, queue: &Queue, volume: VoxelVolume) {
+    let schedule = self.voxel_scheduler.schedule(&volume, self.camera_state.view_point());
+    self.instance_buffer.reorder(device, queue, &volume, &schedule);
+    self.volume = volume;
+    self.voxel_scheduler.show_slab(self.voxel_scheduler.slab_index());
+  }
+
+  /**
+   * Changes how the volume is flickered, to match the main view
+   */
+  pub fn set_flicker_config(&mut self, device: &Device, queue: &Queue, config: FlickerConfig) {
+    if self.voxel_scheduler.set_config(config) {
+      let schedule = self.voxel_scheduler.schedule(&self.volume, self.camera_state.view_point());
+      self.instance_buffer.reorder(device, queue, &self.volume, &schedule);
+    }
+    self.voxel_scheduler.show_current_slab();
+  }
+
+  /**
+   * Updates the camera, re-sorting the voxels if they follow it
+   */
+  pub fn update(&mut self, device: &Device, queue: &Queue, aspect: f32) {
+    self.camera_state.camera.aspect = aspect;
+    self.camera_state.camera.update();
+    self.camera_state.camera_uniform.update_view_proj(&self.camera_state.camera);
+
+    let view_point = self.camera_state.view_point();
+    if self.voxel_scheduler.needs_rescheduling(view_point) {
+      let schedule = self.voxel_scheduler.schedule(&self.volume, view_point);
+      self.instance_buffer.reorder(device, queue, &self.volume, &schedule);
+      self.voxel_scheduler.show_slab(self.voxel_scheduler.slab_index());
+    }
+
+    queue.write_buffer(
+      &self.camera_state.camera_buffer,
+      0,
+      bytemuck::cast_slice(&[self.camera_state.camera_uniform]),
+    );
+  }
+}
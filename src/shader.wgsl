@@ -6,13 +6,32 @@ struct Camera {
 @group(0) @binding(0)
 var<uniform> camera: Camera;
 
+struct TransferFunction {
+  value_range: vec2<f32>,
+  enabled: u32,
+};
+@group(1) @binding(0)
+var transfer_lut: texture_1d<f32>;
+@group(1) @binding(1)
+var<uniform> transfer_function: TransferFunction;
+
+// The colour and opacity the transfer function gives a scalar value
+fn transfer(value: f32) -> vec4<f32> {
+  let range = transfer_function.value_range;
+  let position = clamp((value - range.x) / max(range.y - range.x, 1e-6), 0.0, 1.0);
+  let size = i32(textureDimensions(transfer_lut));
+  return textureLoad(transfer_lut, i32(round(position * f32(size - 1))), 0);
+}
+
 struct VertexInput {
   @location(0) position: vec3<f32>,
   @location(1) color: vec3<f32>,
 };
 struct VertexOutput {
   @builtin(position) clip_position: vec4<f32>,
-  @location(0) color: vec3<f32>,
+  @location(0) color: vec4<f32>,
+  // The distance in front of the camera, for weighting transparent voxels
+  @location(1) view_depth: f32,
 };
 
 struct InstanceInput {
@@ -20,6 +39,8 @@ struct InstanceInput {
   @location(6) model_matrix_1: vec4<f32>,
   @location(7) model_matrix_2: vec4<f32>,
   @location(8) model_matrix_3: vec4<f32>,
+  @location(9) color: vec4<f32>,
+  @location(10) value: f32,
 };
 
 @vertex
@@ -35,13 +56,21 @@ fn vs_main(
   );
 
   var output: VertexOutput;
-  output.color = model.color;
+  // The vertex colours only shade the voxel's own colour slightly, so the
+  // faces of each cube stay distinguishable
+  var color = instance.color;
+  if (transfer_function.enabled != 0u) {
+    color = transfer(instance.value);
+  }
+  let shade = vec3<f32>(0.75) + 0.5 * model.color;
+  output.color = vec4<f32>(color.rgb * shade, color.a);
 
   output.clip_position = (
     camera.view_proj
       * model_matrix
       * vec4<f32>(model.position, 1.0)
   );
+  output.view_depth = output.clip_position.w;
 
   return output;
 }
@@ -49,5 +78,38 @@ fn vs_main(
 // Fragment shader
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-  return vec4<f32>(in.color, 1.0);
+  // Fully transparent voxels must not hide the voxels behind them
+  if (in.color.a <= 0.0) {
+    discard;
+  }
+  return in.color;
+}
+
+// Weighted blended order-independent transparency
+struct AccumulateOutput {
+  @location(0) accum: vec4<f32>,
+  @location(1) weight: vec4<f32>,
+};
+
+@fragment
+fn fs_accumulate(in: VertexOutput) -> AccumulateOutput {
+  let alpha = in.color.a;
+  if (alpha <= 0.0) {
+    discard;
+  }
+
+  // Nearer voxels weigh more, so they still look in front of the ones behind
+  // them without hiding them (McGuire and Bavoil, 2013)
+  let depth = in.view_depth;
+  let weight = clamp(
+    10.0 / (1e-5 + pow(depth / 5.0, 2.0) + pow(depth / 200.0, 6.0)),
+    1e-2,
+    3e3,
+  );
+
+  // The colours are summed, while the alpha multiplies the revealage
+  var output: AccumulateOutput;
+  output.accum = vec4<f32>(in.color.rgb * alpha * weight, alpha);
+  output.weight = vec4<f32>(alpha * weight);
+  return output;
 }
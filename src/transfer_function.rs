@@ -0,0 +1,298 @@
+//! Transfer functions, mapping the scalar values of a volume to colours and
+//! opacities.
+//!
+//! They are saved as plain text, one control point per line: its position in
+//! the volume's value range (0 at the smallest value, 1 at the largest)
+//! followed by red, green, blue and alpha, all from 0 to 1. Blank lines and
+//! anything after a `#` are ignored:
+//!
+//! ```text
+//! # position red green blue alpha
+//! 0.0  0.0 0.0 0.0 0.0
+//! 0.5  1.0 0.5 0.0 0.4
+//! 1.0  1.0 1.0 1.0 1.0
+//! ```
+
+use std::{fmt, io, path::Path};
+
+/**
+ * The number of entries in the lookup texture the renderers sample a
+ * transfer function from
+ */
+pub const TRANSFER_FUNCTION_LUT_SIZE: u32 = 256;
+
+/**
+ * The colour and opacity at one position of a [TransferFunction]
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ControlPoint {
+  /// Where in the volume's value range the point is, from 0 to 1
+  pub position: f32,
+
+  /// [R, G, B, A], from 0 to 1
+  pub color: [f32; 4],
+}
+
+/**
+ * A piecewise linear mapping from scalar values to colours and opacities,
+ * through control points ordered by position. Values before the first point
+ * or after the last take that point's colour
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferFunction {
+  points: Vec<ControlPoint>,
+}
+
+/**
+ * The built-in transfer functions, picked with key 7
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TransferFunctionPreset {
+  /// Black to white, fully opaque, like the default scalar window
+  Greyscale,
+
+  /// Black to white, with low values fading out so the inside shows through
+  Translucent,
+
+  /// Black through red and yellow to white, like heated metal
+  Hot,
+
+  /// Blue through green to red, with low values fading out
+  Rainbow,
+}
+
+/**
+ * Why a transfer function file could not be loaded
+ */
+#[derive(Debug)]
+pub enum TransferFunctionError {
+  /// The file could not be read
+  Io(io::Error),
+
+  /// The file was read, but a line isn't a valid control point
+  Format(String),
+}
+
+impl ControlPoint {
+
+  pub const fn new(position: f32, color: [f32; 4]) -> Self {
+    return Self {
+      position,
+      color,
+    };
+  }
+}
+
+impl TransferFunction {
+
+  /**
+   * A transfer function through `points`, which are sorted by position
+   */
+  pub fn new(mut points: Vec<ControlPoint>) -> Self {
+    points.sort_by(|a, b| a.position.total_cmp(&b.position));
+    return Self { points };
+  }
+
+  pub fn points(&self) -> &[ControlPoint] {
+    return &self.points;
+  }
+
+  /**
+   * Adds `point`, keeping the points ordered by position
+   */
+  pub fn add_point(&mut self, point: ControlPoint) {
+    let index = self.points.partition_point(|other| other.position <= point.position);
+    self.points.insert(index, point);
+  }
+
+  /**
+   * Replaces the point at `index`, which moves to keep the points ordered if
+   * its position changed past another's
+   */
+  pub fn set_point(&mut self, index: usize, point: ControlPoint) {
+    self.points.remove(index);
+    self.add_point(point);
+  }
+
+  pub fn remove_point(&mut self, index: usize) -> ControlPoint {
+    return self.points.remove(index);
+  }
+
+  /**
+   * A point halfway across the widest gap between the points, or between
+   * them and either end of the value range, coloured as the function
+   * already is there
+   */
+  pub fn new_point(&self) -> ControlPoint {
+    let positions = std::iter::once(0.0)
+      .chain(self.points.iter().map(|point| point.position))
+      .chain(std::iter::once(1.0))
+      .collect::<Vec<_>>();
+    let widest_gap = positions
+      .windows(2)
+      .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+      .unwrap();
+
+    let position = (widest_gap[0] + widest_gap[1]) / 2.0;
+    return ControlPoint::new(position, self.sample(position));
+  }
+
+  /**
+   * The colour and opacity at `position` in the volume's value range,
+   * interpolated between the control points either side of it
+   */
+  pub fn sample(&self, position: f32) -> [f32; 4] {
+    let Some(first) = self.points.first() else {
+      return [0.0; 4];
+    };
+
+    let next = self.points.partition_point(|point| point.position <= position);
+    if next == 0 {
+      return first.color;
+    }
+    if next == self.points.len() {
+      return self.points[next - 1].color;
+    }
+
+    let (before, after) = (self.points[next - 1], self.points[next]);
+    let t = (position - before.position) / (after.position - before.position);
+    return std::array::from_fn(|channel| {
+      before.color[channel] + (after.color[channel] - before.color[channel]) * t
+    });
+  }
+
+  /**
+   * Samples the transfer function at `size` evenly spaced positions from 0
+   * to 1, for uploading as a lookup texture
+   */
+  pub fn lookup_table(&self, size: u32) -> Vec<[u8; 4]> {
+    let steps = size.saturating_sub(1).max(1) as f32;
+    return (0..size)
+      .map(|i| self.sample(i as f32 / steps).map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8))
+      .collect();
+  }
+
+  /**
+   * Reads a transfer function from the text format described in the module
+   * docs
+   */
+  pub fn parse(text: &str) -> Result<Self, TransferFunctionError> {
+    let mut points = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+      let content = line.split('#').next().unwrap_or("").trim();
+      if content.is_empty() {
+        continue;
+      }
+
+      let numbers = content
+        .split_whitespace()
+        .map(|number| number.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TransferFunctionError::Format(format!("line {}: {}", line_index + 1, e)))?;
+
+      let [position, red, green, blue, alpha] = numbers[..] else {
+        return Err(TransferFunctionError::Format(format!(
+          "line {}: expected a position and 4 colour channels, found {} numbers",
+          line_index + 1,
+          numbers.len(),
+        )));
+      };
+      points.push(ControlPoint::new(position, [red, green, blue, alpha]));
+    }
+
+    if points.is_empty() {
+      return Err(TransferFunctionError::Format("no control points".to_string()));
+    }
+    return Ok(Self::new(points));
+  }
+
+  pub fn load(path: &Path) -> Result<Self, TransferFunctionError> {
+    let text = std::fs::read_to_string(path)?;
+    return Self::parse(&text);
+  }
+
+  pub fn save(&self, path: &Path) -> io::Result<()> {
+    return std::fs::write(path, self.to_string());
+  }
+}
+
+impl fmt::Display for TransferFunction {
+  /// Writes the control points in the text format they're loaded from
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "# position red green blue alpha")?;
+    for point in &self.points {
+      let [red, green, blue, alpha] = point.color;
+      writeln!(f, "{} {} {} {} {}", point.position, red, green, blue, alpha)?;
+    }
+    return Ok(());
+  }
+}
+
+impl TransferFunctionPreset {
+
+  pub const ALL: [TransferFunctionPreset; 4] = [
+    TransferFunctionPreset::Greyscale,
+    TransferFunctionPreset::Translucent,
+    TransferFunctionPreset::Hot,
+    TransferFunctionPreset::Rainbow,
+  ];
+
+  pub fn next(&self) -> Self {
+    match self {
+      TransferFunctionPreset::Greyscale => return TransferFunctionPreset::Translucent,
+      TransferFunctionPreset::Translucent => return TransferFunctionPreset::Hot,
+      TransferFunctionPreset::Hot => return TransferFunctionPreset::Rainbow,
+      TransferFunctionPreset::Rainbow => return TransferFunctionPreset::Greyscale,
+    }
+  }
+
+  pub fn transfer_function(&self) -> TransferFunction {
+    let points = match self {
+      TransferFunctionPreset::Greyscale => vec![
+        ControlPoint::new(0.0, [0.0, 0.0, 0.0, 1.0]),
+        ControlPoint::new(1.0, [1.0, 1.0, 1.0, 1.0]),
+      ],
+      TransferFunctionPreset::Translucent => vec![
+        ControlPoint::new(0.0, [0.0, 0.0, 0.0, 0.0]),
+        ControlPoint::new(1.0, [1.0, 1.0, 1.0, 1.0]),
+      ],
+      TransferFunctionPreset::Hot => vec![
+        ControlPoint::new(0.0, [0.0, 0.0, 0.0, 1.0]),
+        ControlPoint::new(0.4, [1.0, 0.0, 0.0, 1.0]),
+        ControlPoint::new(0.8, [1.0, 1.0, 0.0, 1.0]),
+        ControlPoint::new(1.0, [1.0, 1.0, 1.0, 1.0]),
+      ],
+      TransferFunctionPreset::Rainbow => vec![
+        ControlPoint::new(0.0, [0.0, 0.0, 1.0, 0.0]),
+        ControlPoint::new(0.5, [0.0, 1.0, 0.0, 0.5]),
+        ControlPoint::new(1.0, [1.0, 0.0, 0.0, 1.0]),
+      ],
+    };
+    return TransferFunction::new(points);
+  }
+}
+
+impl fmt::Display for TransferFunctionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TransferFunctionError::Io(e) => write!(f, "could not read transfer function: {}", e),
+      TransferFunctionError::Format(message) => write!(f, "invalid transfer function: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for TransferFunctionError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      TransferFunctionError::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for TransferFunctionError {
+  fn from(e: io::Error) -> Self {
+    return TransferFunctionError::Io(e);
+  }
+}
@@ -0,0 +1,173 @@
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue, Texture, util::DeviceExt};
+
+use crate::transfer_function::{TransferFunction, TRANSFER_FUNCTION_LUT_SIZE};
+
+/**
+ * Whether voxels are coloured by the transfer function, and the range of
+ * scalar values it spans
+ */
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TransferFunctionUniform {
+  /// The values mapped to the start and end of the lookup texture
+  pub value_range: [f32; 2],
+
+  /// 1 to colour voxels from their values, 0 to keep their own colours
+  pub enabled    : u32,
+  _padding       : u32,
+}
+
+/**
+ * A transfer function's 1D lookup texture, bound for both the instanced and
+ * ray-marched renderers to colour voxels by their scalar values
+ */
+pub struct TransferFunctionState {
+  pub bind_group_layout: BindGroupLayout,
+
+  /// Colours voxels by the transfer function, while one is set
+  pub bind_group: BindGroup,
+
+  /// Always keeps the voxels' own colours, for models which have no scalar
+  /// values, like the tesseract
+  pub passthrough_bind_group: BindGroup,
+
+  lut_texture   : Texture,
+  uniform_buffer: Buffer,
+}
+
+impl TransferFunctionUniform {
+
+  pub fn new(value_range: (f32, f32), enabled: bool) -> Self {
+    return Self {
+      value_range: [value_range.0, value_range.1],
+      enabled    : enabled as u32,
+      _padding   : 0,
+    };
+  }
+}
+
+impl TransferFunctionState {
+
+  /**
+   * Starts with no transfer function, so voxels keep their own colours
+   */
+  pub fn new(device: &Device) -> Self {
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label  : Some("Transfer function bind group layout"),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding   : 0,
+          visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+          ty        : wgpu::BindingType::Texture {
+            sample_type   : wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D1,
+            multisampled  : false,
+          },
+          count     : None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding   : 1,
+          visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+          ty        : wgpu::BindingType::Buffer {
+            ty                : wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size  : None,
+          },
+          count     : None,
+        },
+      ],
+    });
+
+    let lut_texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("Transfer function lookup texture"),
+      size: wgpu::Extent3d {
+        width: TRANSFER_FUNCTION_LUT_SIZE,
+        height: 1,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D1,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING
+        | wgpu::TextureUsages::COPY_DST,
+      view_formats: Default::default(),
+    });
+    let lut_view = lut_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let create_uniform_bind_group = |label, uniform: TransferFunctionUniform| {
+      let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label   : Some(label),
+        contents: bytemuck::cast_slice(&[uniform]),
+        usage   : wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+      });
+      let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label  : Some(label),
+        layout : &bind_group_layout,
+        entries: &[
+          wgpu::BindGroupEntry {
+            binding : 0,
+            resource: wgpu::BindingResource::TextureView(&lut_view),
+          },
+          wgpu::BindGroupEntry {
+            binding : 1,
+            resource: buffer.as_entire_binding(),
+          },
+        ],
+      });
+      return (buffer, bind_group);
+    };
+
+    let disabled = TransferFunctionUniform::new((0.0, 1.0), false);
+    let (uniform_buffer, bind_group) = create_uniform_bind_group("Transfer function", disabled);
+    let (_, passthrough_bind_group) = create_uniform_bind_group("Passthrough transfer function", disabled);
+
+    return Self {
+      bind_group_layout,
+      bind_group,
+      passthrough_bind_group,
+      lut_texture,
+      uniform_buffer,
+    };
+  }
+
+  /**
+   * Colours voxels by `transfer_function`, spread over the scalar values in
+   * `value_range`, or by their own colours again without one
+   */
+  pub fn update(
+    &self,
+    queue: &Queue,
+    transfer_function: Option<&TransferFunction>,
+    value_range: (f32, f32),
+  ) {
+    if let Some(transfer_function) = transfer_function {
+      let lookup_table = transfer_function.lookup_table(TRANSFER_FUNCTION_LUT_SIZE);
+      queue.write_texture(
+        wgpu::ImageCopyTexture {
+          texture: &self.lut_texture,
+          mip_level: 0,
+          origin: wgpu::Origin3d::ZERO,
+          aspect: wgpu::TextureAspect::All,
+        },
+        bytemuck::cast_slice(&lookup_table),
+        wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: std::num::NonZeroU32::new(TRANSFER_FUNCTION_LUT_SIZE * 4),
+          rows_per_image: None,
+        },
+        wgpu::Extent3d {
+          width: TRANSFER_FUNCTION_LUT_SIZE,
+          height: 1,
+          depth_or_array_layers: 1,
+        },
+      );
+    }
+
+    queue.write_buffer(
+      &self.uniform_buffer,
+      0,
+      bytemuck::cast_slice(&[TransferFunctionUniform::new(value_range, transfer_function.is_some())]),
+    );
+  }
+}
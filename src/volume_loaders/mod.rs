@@ -0,0 +1,77 @@
+
+pub mod vox;
+pub mod raw;
+pub mod nrrd;
+
+use std::{fmt, io, path::Path};
+
+use crate::voxel_volume::{ScalarWindowSettings, VoxelVolume};
+
+/**
+ * Why a volume file could not be loaded
+ */
+#[derive(Debug)]
+pub enum VolumeLoadError {
+  /// The file could not be read
+  Io(io::Error),
+
+  /// The file was read, but its contents are not a valid volume
+  Format(String),
+
+  /// The file extension doesn't match any supported format
+  UnsupportedFormat(String),
+}
+
+/**
+ * Loads a volume from `path`, choosing the format from the file extension.
+ * Scalar volumes are coloured with `window`, while `.vox` models keep their
+ * palette colours. Files holding multiple models only return the first. Raw
+ * volumes need their layout described, so are loaded with [raw::load_raw]
+ * instead
+ */
+pub fn load_volume(path: &Path, window: &ScalarWindowSettings) -> Result<VoxelVolume, VolumeLoadError> {
+  let extension = path.extension()
+    .and_then(|extension| extension.to_str())
+    .unwrap_or("")
+    .to_ascii_lowercase();
+
+  match extension.as_str() {
+    "vox" => {
+      let models = vox::load_vox(path)?;
+      return models.into_iter()
+        .next()
+        .ok_or_else(|| VolumeLoadError::Format("no models in file".to_string()));
+    },
+
+    "nrrd" | "nhdr" => return nrrd::load_nrrd(path, window),
+
+    _ => return Err(VolumeLoadError::UnsupportedFormat(extension)),
+  }
+}
+
+impl fmt::Display for VolumeLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VolumeLoadError::Io(e) => write!(f, "could not read volume: {}", e),
+      VolumeLoadError::Format(message) => write!(f, "invalid volume: {}", message),
+      VolumeLoadError::UnsupportedFormat(extension) => {
+        write!(f, "unsupported volume format: {:?}", extension)
+      },
+    }
+  }
+}
+
+impl std::error::Error for VolumeLoadError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      VolumeLoadError::Io(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for VolumeLoadError {
+  fn from(e: io::Error) -> Self {
+    return VolumeLoadError::Io(e);
+  }
+}
@@ -0,0 +1,151 @@
+//! A loader for 3D scalar NRRD volumes (`.nrrd` with attached data, or
+//! `.nhdr` with a detached `data file`).
+//!
+//! Only raw encoding of `uchar`, `ushort` and `float` samples is supported.
+//! Spacings are ignored, so anisotropic voxels are shown as cubes. See
+//! <http://teem.sourceforge.net/nrrd/format.html>
+
+use std::path::Path;
+
+use crate::voxel_volume::{ScalarWindowSettings, VoxelVolume};
+
+use super::{raw::{self, ByteOrder, RawVolumeDescription, SampleType}, VolumeLoadError};
+
+/**
+ * Loads an NRRD volume, coloured with `window`
+ */
+pub fn load_nrrd(path: &Path, window: &ScalarWindowSettings) -> Result<VoxelVolume, VolumeLoadError> {
+  let bytes = std::fs::read(path)?;
+  let header = parse_header(&bytes)?;
+
+  match &header.data_file {
+    Some(data_file) => {
+      let data_path = path.parent().unwrap_or(Path::new("")).join(data_file);
+      let data = std::fs::read(data_path)?;
+      return raw::parse_raw(&data, &header.description, window);
+    },
+
+    None => return raw::parse_raw(&bytes[header.data_offset..], &header.description, window),
+  }
+}
+
+/**
+ * Parses an NRRD file whose data is attached after its header
+ */
+pub fn parse_nrrd(bytes: &[u8], window: &ScalarWindowSettings) -> Result<VoxelVolume, VolumeLoadError> {
+  let header = parse_header(bytes)?;
+  if header.data_file.is_some() {
+    return Err(format_error("data is in a detached file"));
+  }
+
+  return raw::parse_raw(&bytes[header.data_offset..], &header.description, window);
+}
+
+struct NrrdHeader {
+  description: RawVolumeDescription,
+
+  /// Where attached data starts
+  data_offset: usize,
+
+  /// The detached data file, relative to the header
+  data_file: Option<String>,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<NrrdHeader, VolumeLoadError> {
+  if bytes.starts_with(b"NRRD") == false {
+    return Err(format_error("missing NRRD magic"));
+  }
+
+  let mut sample_type = None;
+  let mut dimension = None;
+  let mut sizes = None;
+  let mut byte_order = ByteOrder::Little;
+  let mut data_file = None;
+
+  let mut offset = 0;
+  let mut is_magic_line = true;
+  // Detached headers may end without a blank line
+  while offset < bytes.len() {
+    let line_end = bytes[offset..]
+      .iter()
+      .position(|byte| *byte == b'\n')
+      .map(|position| offset + position)
+      .unwrap_or(bytes.len());
+    let line = std::str::from_utf8(&bytes[offset..line_end])
+      .map_err(|_| format_error("header is not text"))?
+      .trim_end_matches('\r');
+    offset = (line_end + 1).min(bytes.len());
+
+    // A blank line separates the header from attached data
+    if line.is_empty() {
+      break;
+    }
+    if is_magic_line || line.starts_with('#') || line.contains(":=") {
+      is_magic_line = false;
+      continue;
+    }
+
+    let Some((field, value)) = line.split_once(": ") else {
+      return Err(format_error(&format!("malformed header line {:?}", line)));
+    };
+
+    match field.to_ascii_lowercase().as_str() {
+      "type" => sample_type = Some(parse_sample_type(value)?),
+
+      "dimension" => dimension = value.trim().parse::<u32>().ok(),
+
+      "sizes" => sizes = Some(value
+        .split_whitespace()
+        .map(|size| size.parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format_error("invalid sizes"))?),
+
+      "endian" => byte_order = match value.trim() {
+        "big" => ByteOrder::Big,
+        _ => ByteOrder::Little,
+      },
+
+      "encoding" if value.trim() != "raw" => {
+        return Err(format_error(&format!("unsupported encoding {:?}", value.trim())));
+      },
+
+      "data file" | "datafile" => data_file = Some(value.trim().to_string()),
+
+      _ => {},
+    }
+  }
+
+  if dimension != Some(3) {
+    return Err(format_error("only 3D volumes are supported"));
+  }
+
+  let dimensions = match sizes.as_deref() {
+    Some([x, y, z]) => [*x, *y, *z],
+    _ => return Err(format_error("sizes must list three sizes")),
+  };
+
+  return Ok(NrrdHeader {
+    description: RawVolumeDescription {
+      dimensions,
+      sample_type: sample_type.ok_or_else(|| format_error("missing type"))?,
+      byte_order,
+    },
+    data_offset: offset,
+    data_file,
+  });
+}
+
+fn parse_sample_type(value: &str) -> Result<SampleType, VolumeLoadError> {
+  match value.trim() {
+    "uchar" | "unsigned char" | "uint8" | "uint8_t" => return Ok(SampleType::U8),
+    "ushort" | "unsigned short" | "unsigned short int" | "uint16" | "uint16_t" => {
+      return Ok(SampleType::U16);
+    },
+    "float" => return Ok(SampleType::F32),
+    other => return Err(format_error(&format!("unsupported type {:?}", other))),
+  }
+}
+
+fn format_error(message: &str) -> VolumeLoadError {
+  return VolumeLoadError::Format(message.to_string());
+}
@@ -0,0 +1,173 @@
+//! A loader for headerless `.raw` scalar volumes, whose layout is given
+//! separately.
+//!
+//! Samples are stored x-fastest, then y, then z (the slice axis), as written
+//! by most medical imaging tools. The slice axis becomes the volume's y axis,
+//! so a stack of axial slices is flickered through slice by slice.
+
+use std::path::Path;
+
+use crate::voxel_volume::{ScalarWindowSettings, VoxelVolume, DEFAULT_VOXEL_SPACING};
+
+use super::VolumeLoadError;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SampleType {
+  U8,
+  U16,
+  F32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+  Little,
+  Big,
+}
+
+/**
+ * Describes how the samples of a raw volume are laid out
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RawVolumeDescription {
+  /// The number of samples along [x, y, z] of the file
+  pub dimensions : [u32; 3],
+  pub sample_type: SampleType,
+  pub byte_order : ByteOrder,
+}
+
+/**
+ * Loads a raw volume, coloured with `window`
+ */
+pub fn load_raw(
+  path: &Path,
+  description: &RawVolumeDescription,
+  window: &ScalarWindowSettings,
+) -> Result<VoxelVolume, VolumeLoadError> {
+  let bytes = std::fs::read(path)?;
+  return parse_raw(&bytes, description, window);
+}
+
+/**
+ * Decodes raw samples into a volume, coloured with `window`. Use
+ * [VoxelVolume::apply_window] to choose another window later
+ */
+pub fn parse_raw(
+  bytes: &[u8],
+  description: &RawVolumeDescription,
+  window: &ScalarWindowSettings,
+) -> Result<VoxelVolume, VolumeLoadError> {
+  let [size_x, size_y, size_z] = description.dimensions;
+  let sample_count = size_x as usize * size_y as usize * size_z as usize;
+  let byte_count = sample_count * description.sample_type.size();
+
+  if bytes.len() < byte_count {
+    return Err(VolumeLoadError::Format(format!(
+      "expected {} bytes of samples, found {}",
+      byte_count,
+      bytes.len(),
+    )));
+  }
+
+  // The file's z-major layout is already the volume's y-major layout once
+  // the file's z axis becomes the volume's y axis
+  let values = decode_samples(&bytes[..byte_count], description);
+
+  let mut volume = VoxelVolume::from_values(
+    [size_x, size_z, size_y],
+    DEFAULT_VOXEL_SPACING,
+    values,
+  );
+  volume.apply_window(&window.window_for(&volume));
+  return Ok(volume);
+}
+
+fn decode_samples(bytes: &[u8], description: &RawVolumeDescription) -> Vec<f32> {
+  let little_endian = description.byte_order == ByteOrder::Little;
+
+  match description.sample_type {
+    SampleType::U8 => {
+      return bytes.iter().map(|sample| *sample as f32).collect();
+    },
+
+    SampleType::U16 => {
+      return bytes.chunks_exact(2).map(|sample| {
+        let sample = [sample[0], sample[1]];
+        if little_endian {
+          u16::from_le_bytes(sample) as f32
+        } else {
+          u16::from_be_bytes(sample) as f32
+        }
+      }).collect();
+    },
+
+    SampleType::F32 => {
+      return bytes.chunks_exact(4).map(|sample| {
+        let sample = [sample[0], sample[1], sample[2], sample[3]];
+        if little_endian {
+          f32::from_le_bytes(sample)
+        } else {
+          f32::from_be_bytes(sample)
+        }
+      }).collect();
+    },
+  }
+}
+
+impl SampleType {
+
+  /**
+   * The size of one sample in bytes
+   */
+  pub fn size(&self) -> usize {
+    match self {
+      SampleType::U8 => return 1,
+      SampleType::U16 => return 2,
+      SampleType::F32 => return 4,
+    }
+  }
+
+  /**
+   * Parses `u8`, `u16` or `f32`
+   */
+  pub fn parse(name: &str) -> Option<Self> {
+    match name {
+      "u8" => return Some(SampleType::U8),
+      "u16" => return Some(SampleType::U16),
+      "f32" => return Some(SampleType::F32),
+      _ => return None,
+    }
+  }
+}
+
+impl RawVolumeDescription {
+
+  /**
+   * Parses dimensions written as `WIDTHxHEIGHTxDEPTH` with a sample type,
+   * eg. `256x256x128` and `u16`. Samples are assumed to be little endian
+   */
+  pub fn parse(dimensions: &str, sample_type: &str) -> Result<Self, VolumeLoadError> {
+    let sizes = dimensions
+      .split('x')
+      .map(|size| size.trim().parse::<u32>())
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|e| VolumeLoadError::Format(format!("invalid dimensions {:?}: {}", dimensions, e)))?;
+
+    let dimensions = match sizes[..] {
+      [x, y, z] if x > 0 && y > 0 && z > 0 => [x, y, z],
+      _ => return Err(VolumeLoadError::Format(format!(
+        "dimensions must be three sizes like 256x256x128, not {:?}",
+        dimensions,
+      ))),
+    };
+
+    let sample_type = SampleType::parse(sample_type).ok_or_else(|| {
+      VolumeLoadError::Format(format!("sample type must be u8, u16 or f32, not {:?}", sample_type))
+    })?;
+
+    return Ok(Self {
+      dimensions,
+      sample_type,
+      byte_order: ByteOrder::Little,
+    });
+  }
+}
@@ -0,0 +1,205 @@
+//! A loader for MagicaVoxel `.vox` files.
+//!
+//! A file is a `VOX ` header followed by a `MAIN` chunk, whose children are a
+//! `SIZE` + `XYZI` chunk pair per model and an optional `RGBA` palette. Scene
+//! graph, material and layer chunks are skipped. See
+//! <https://github.com/ephtracy/voxel-model/blob/master/MagicaVoxel-file-format-vox.txt>
+
+use std::path::Path;
+
+use crate::voxel_volume::{Voxel, VoxelVolume, DEFAULT_VOXEL_SPACING};
+
+use super::VolumeLoadError;
+
+const MAGIC: &[u8; 4] = b"VOX ";
+
+/**
+ * The most voxels a model can have along each axis, as MagicaVoxel stores
+ * voxel coordinates in a byte. Larger sizes only come from corrupt files
+ */
+pub const MAX_MODEL_SIZE: u32 = 256;
+
+/// A palette indexed by the colour index stored with each voxel. Index 0 is
+/// never used by voxels
+pub type Palette = [[u8; 4]; 256];
+
+struct Chunk<'a> {
+  id      : [u8; 4],
+  content : &'a [u8],
+  children: &'a [u8],
+}
+
+/**
+ * Loads every model in a `.vox` file as its own volume
+ */
+pub fn load_vox(path: &Path) -> Result<Vec<VoxelVolume>, VolumeLoadError> {
+  let bytes = std::fs::read(path)?;
+  return parse_vox(&bytes);
+}
+
+/**
+ * Parses the contents of a `.vox` file into one volume per model.
+ *
+ * MagicaVoxel is z-up, so its z axis becomes the volume's y axis, and its y
+ * axis is flipped into the volume's z axis to keep the model's handedness.
+ * Voxels are coloured from the file's palette (or the default one) and keep
+ * their colour index as their scalar value
+ */
+pub fn parse_vox(bytes: &[u8]) -> Result<Vec<VoxelVolume>, VolumeLoadError> {
+  if bytes.len() < 8 || &bytes[0..4] != MAGIC {
+    return Err(format_error("missing VOX header"));
+  }
+
+  let (main, _) = read_chunk(&bytes[8..])?;
+  if &main.id != b"MAIN" {
+    return Err(format_error("first chunk is not MAIN"));
+  }
+
+  let mut sizes = Vec::new();
+  let mut voxel_lists = Vec::new();
+  let mut palette = default_palette();
+
+  let mut remaining = main.children;
+  while remaining.is_empty() == false {
+    let (chunk, rest) = read_chunk(remaining)?;
+    remaining = rest;
+
+    match &chunk.id {
+      b"SIZE" => sizes.push([
+        read_u32(chunk.content, 0)?,
+        read_u32(chunk.content, 4)?,
+        read_u32(chunk.content, 8)?,
+      ]),
+
+      b"XYZI" => voxel_lists.push(chunk.content),
+
+      b"RGBA" => palette = read_palette(chunk.content)?,
+
+      _ => {},
+    }
+  }
+
+  if sizes.len() != voxel_lists.len() {
+    return Err(format_error("SIZE and XYZI chunks don't pair up"));
+  }
+
+  return sizes.iter()
+    .zip(voxel_lists)
+    .map(|(size, voxel_list)| build_volume(*size, voxel_list, &palette))
+    .collect();
+}
+
+/**
+ * The palette MagicaVoxel uses when a file has no `RGBA` chunk: a 6x6x6
+ * colour cube without black, then red, green, blue and grey ramps
+ */
+pub fn default_palette() -> Palette {
+  let mut palette = [[0; 4]; 256];
+  let mut index = 1;
+
+  for r in (0..6u8).rev() {
+    for g in (0..6).rev() {
+      for b in (0..6).rev() {
+        if r + g + b == 0 {
+          continue;
+        }
+        palette[index] = [r * 0x33, g * 0x33, b * 0x33, 255];
+        index += 1;
+      }
+    }
+  }
+
+  let ramp = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+  for channels in [[true, false, false], [false, true, false], [false, false, true], [true, true, true]] {
+    for level in ramp {
+      palette[index] = [
+        if channels[0] { level } else { 0 },
+        if channels[1] { level } else { 0 },
+        if channels[2] { level } else { 0 },
+        255,
+      ];
+      index += 1;
+    }
+  }
+
+  return palette;
+}
+
+fn build_volume(
+  size: [u32; 3],
+  voxel_list: &[u8],
+  palette: &Palette,
+) -> Result<VoxelVolume, VolumeLoadError> {
+  if size.iter().any(|axis_size| *axis_size > MAX_MODEL_SIZE) {
+    return Err(format_error(&format!("model size {:?} is larger than {}", size, MAX_MODEL_SIZE)));
+  }
+
+  let [size_x, size_y, size_z] = size;
+  let mut volume = VoxelVolume::new([size_x, size_z, size_y], DEFAULT_VOXEL_SPACING);
+
+  let voxel_count = read_u32(voxel_list, 0)? as usize;
+  let voxel_bytes = voxel_count.checked_mul(4)
+    .and_then(|length| voxel_list.get(4..4 + length))
+    .ok_or_else(|| format_error("XYZI chunk is shorter than its voxel count"))?;
+
+  for voxel in voxel_bytes.chunks_exact(4) {
+    let [x, y, z, color_index] = [voxel[0], voxel[1], voxel[2], voxel[3]];
+    let [x, y, z] = [x, y, z].map(u32::from);
+    if x >= size_x || y >= size_y || z >= size_z {
+      return Err(format_error(&format!("voxel {:?} is outside the model size {:?}", [x, y, z], size)));
+    }
+
+    volume.set(
+      x,
+      z,
+      size_y - 1 - y,
+      Voxel::filled(palette[color_index as usize], color_index as f32),
+    );
+  }
+
+  return Ok(volume);
+}
+
+fn read_palette(content: &[u8]) -> Result<Palette, VolumeLoadError> {
+  if content.len() < 255 * 4 {
+    return Err(format_error("RGBA chunk is too short"));
+  }
+
+  // The chunk's first entry is colour index 1; its last entry is unused
+  let mut palette = [[0; 4]; 256];
+  for (i, entry) in content.chunks_exact(4).take(255).enumerate() {
+    palette[i + 1] = [entry[0], entry[1], entry[2], entry[3]];
+  }
+  return Ok(palette);
+}
+
+/**
+ * Reads the chunk at the start of `bytes`, returning it and the bytes after it
+ */
+fn read_chunk(bytes: &[u8]) -> Result<(Chunk<'_>, &[u8]), VolumeLoadError> {
+  let content_size = read_u32(bytes, 4)? as usize;
+  let children_size = read_u32(bytes, 8)? as usize;
+  let content_end = 12 + content_size;
+  let children_end = content_end + children_size;
+
+  if bytes.len() < children_end {
+    return Err(format_error("chunk is longer than the file"));
+  }
+
+  let chunk = Chunk {
+    id      : [bytes[0], bytes[1], bytes[2], bytes[3]],
+    content : &bytes[12..content_end],
+    children: &bytes[content_end..children_end],
+  };
+  return Ok((chunk, &bytes[children_end..]));
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, VolumeLoadError> {
+  let le_bytes = bytes.get(offset..offset + 4)
+    .ok_or_else(|| format_error("unexpected end of file"))?;
+  return Ok(u32::from_le_bytes([le_bytes[0], le_bytes[1], le_bytes[2], le_bytes[3]]));
+}
+
+fn format_error(message: &str) -> VolumeLoadError {
+  return VolumeLoadError::Format(message.to_string());
+}
@@ -0,0 +1,348 @@
+use cgmath::Vector3;
+
+/**
+ * The size of the volume shown when the renderer starts
+ */
+pub const DEFAULT_VOLUME_DIMENSIONS: [u32; 3] = [3, 3, 3];
+
+/**
+ * The distance between the centres of neighbouring voxels when the renderer
+ * starts
+ */
+pub const DEFAULT_VOXEL_SPACING: f32 = 0.2;
+
+/**
+ * The width of the default volume, which loaded volumes are scaled to fit
+ */
+pub const DEFAULT_VOLUME_EXTENT: f32 = DEFAULT_VOXEL_SPACING * 3.0;
+
+/**
+ * The payload of a single voxel
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Voxel {
+  /// Empty voxels are skipped when building instances
+  pub occupied: bool,
+
+  /// [R, G, B, A]
+  pub color: [u8; 4],
+
+  /// A scalar sample, eg. the density of a medical scan
+  pub value: f32,
+}
+
+/**
+ * A 3D grid of voxels, sized at runtime
+ *
+ * Voxels are stored y-major (then z, then x), so each horizontal plane is a
+ * contiguous run; this is the order planes are flickered through
+ */
+#[derive(Clone, Debug)]
+pub struct VoxelVolume {
+  /// The number of voxels along [x, y, z]
+  pub dimensions: [u32; 3],
+
+  /// The distance between the centres of neighbouring voxels
+  pub spacing: f32,
+
+  /// The position of the voxel at index (0, 0, 0)
+  pub origin: Vector3<f32>,
+
+  voxels: Vec<Voxel>,
+}
+
+/**
+ * Maps scalar voxel values to greyscale colours, like the window/level
+ * controls of a medical image viewer
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScalarWindow {
+  /// Values at or below this are black
+  pub min: f32,
+
+  /// Values at or above this are white
+  pub max: f32,
+
+  /// Voxels with values at or below this are left empty, so they never
+  /// reach the instance buffer
+  pub threshold: f32,
+}
+
+/**
+ * The parts of a [ScalarWindow] chosen before a volume is loaded, eg. on the
+ * command line. Whatever is left as None spans the volume's values, as
+ * [ScalarWindow::spanning] does
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ScalarWindowSettings {
+  /// The values shown as black and white, as [min, max]
+  pub range    : Option<[f32; 2]>,
+  pub threshold: Option<f32>,
+}
+
+impl ScalarWindow {
+
+  /**
+   * A window spanning every value in `volume`, skipping only the voxels at
+   * the minimum (usually the background). A volume with a single value has
+   * no background, so every voxel is kept
+   */
+  pub fn spanning(volume: &VoxelVolume) -> Self {
+    let (min, max) = volume.value_range();
+
+    return Self {
+      min,
+      max,
+      threshold: if max > min { min } else { f32::NEG_INFINITY },
+    };
+  }
+
+  /**
+   * The brightness of `value` in 0..1
+   */
+  pub fn normalize(&self, value: f32) -> f32 {
+    if self.max <= self.min {
+      return if value >= self.max { 1.0 } else { 0.0 };
+    }
+    return ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+  }
+}
+
+impl ScalarWindowSettings {
+
+  /**
+   * The window to colour `volume` with
+   */
+  pub fn window_for(&self, volume: &VoxelVolume) -> ScalarWindow {
+    let mut window = ScalarWindow::spanning(volume);
+    if let Some([min, max]) = self.range {
+      window.min = min;
+      window.max = max;
+    }
+    if let Some(threshold) = self.threshold {
+      window.threshold = threshold;
+    }
+    return window;
+  }
+
+  /**
+   * Parses a window range written as `MIN:MAX`, eg. `100:1500`, where MIN is
+   * below MAX
+   */
+  pub fn parse_range(range: &str) -> Option<[f32; 2]> {
+    let (min, max) = range.split_once(':')?;
+    let min = min.trim().parse::<f32>().ok()?;
+    let max = max.trim().parse::<f32>().ok()?;
+    if (min < max) == false {
+      return None;
+    }
+    return Some([min, max]);
+  }
+}
+
+impl Voxel {
+
+  pub const EMPTY: Voxel = Voxel {
+    occupied: false,
+    color   : [0, 0, 0, 0],
+    value   : 0.0,
+  };
+
+  pub const fn filled(color: [u8; 4], value: f32) -> Self {
+    return Self {
+      occupied: true,
+      color,
+      value,
+    };
+  }
+}
+
+impl Default for Voxel {
+  fn default() -> Self {
+    return Self::EMPTY;
+  }
+}
+
+impl VoxelVolume {
+
+  /**
+   * Creates a volume of empty voxels at [VoxelVolume::centered_origin]
+   */
+  pub fn new(dimensions: [u32; 3], spacing: f32) -> Self {
+    let voxel_count = dimensions.iter().map(|d| *d as usize).product();
+
+    return Self {
+      dimensions,
+      spacing,
+      origin: Self::centered_origin(dimensions, spacing),
+      voxels: vec![Voxel::EMPTY; voxel_count],
+    };
+  }
+
+  /**
+   * Creates a volume where every voxel is occupied, like the original grid
+   */
+  pub fn filled(dimensions: [u32; 3], spacing: f32) -> Self {
+    let mut volume = Self::new(dimensions, spacing);
+    volume.voxels.fill(Voxel::filled([255, 255, 255, 255], 1.0));
+    return volume;
+  }
+
+  /**
+   * Creates a fully occupied volume whose voxels are coloured by position,
+   * red along x, green along y and blue along z
+   */
+  pub fn gradient(dimensions: [u32; 3], spacing: f32) -> Self {
+    let mut volume = Self::new(dimensions, spacing);
+
+    for i in 0..volume.voxels.len() {
+      let coordinates = volume.coordinates(i);
+      let mut color = [255; 4];
+      for axis in 0..3 {
+        let steps = dimensions[axis].saturating_sub(1).max(1);
+        color[axis] = (64 + coordinates[axis] * 191 / steps) as u8;
+      }
+      volume.voxels[i] = Voxel::filled(color, 1.0);
+    }
+
+    return volume;
+  }
+
+  /**
+   * The origin the original grid was laid out from: the first voxel's
+   * centre is at `-dimensions * spacing / 2`, so the voxel centres sit half
+   * a voxel towards the negative corner, rather than exactly around the
+   * world origin
+   */
+  pub fn centered_origin(dimensions: [u32; 3], spacing: f32) -> Vector3<f32> {
+    return -Vector3::new(
+      dimensions[0] as f32,
+      dimensions[1] as f32,
+      dimensions[2] as f32,
+    ) * spacing / 2.0;
+  }
+
+  /**
+   * Rescales the spacing so the largest dimension spans `extent`, and moves
+   * the volume back to [VoxelVolume::centered_origin]
+   */
+  pub fn fit_to_extent(&mut self, extent: f32) {
+    let largest_dimension = self.dimensions.iter().copied().max().unwrap_or(1).max(1);
+    self.spacing = extent / largest_dimension as f32;
+    self.origin = Self::centered_origin(self.dimensions, self.spacing);
+  }
+
+  /**
+   * Creates a volume from scalar samples stored y-major, like the voxels
+   */
+  pub fn from_values(dimensions: [u32; 3], spacing: f32, values: Vec<f32>) -> Self {
+    let mut volume = Self::new(dimensions, spacing);
+    assert_eq!(values.len(), volume.voxels.len(), "Wrong number of values for the volume dimensions");
+
+    for (voxel, value) in volume.voxels.iter_mut().zip(values) {
+      voxel.value = value;
+    }
+    return volume;
+  }
+
+  /**
+   * The smallest and largest scalar value of any voxel
+   */
+  pub fn value_range(&self) -> (f32, f32) {
+    return self.voxels.iter().fold((f32::MAX, f32::MIN), |(min, max), voxel| {
+      (min.min(voxel.value), max.max(voxel.value))
+    });
+  }
+
+  /**
+   * Recolours every voxel from its scalar value, and empties the voxels at or
+   * below the window's threshold. Values are kept, so a new window can be
+   * applied at any time
+   */
+  pub fn apply_window(&mut self, window: &ScalarWindow) {
+    for voxel in &mut self.voxels {
+      let brightness = (window.normalize(voxel.value) * 255.0).round() as u8;
+      voxel.color = [brightness, brightness, brightness, 255];
+      voxel.occupied = voxel.value > window.threshold;
+    }
+  }
+
+  pub fn voxel_count(&self) -> usize {
+    return self.voxels.len();
+  }
+
+  pub fn voxels(&self) -> &[Voxel] {
+    return &self.voxels;
+  }
+
+  /**
+   * Returns the storage index of a voxel, or None if it's out of bounds
+   */
+  pub fn index(&self, x: u32, y: u32, z: u32) -> Option<usize> {
+    let [x_max, y_max, z_max] = self.dimensions;
+    if x >= x_max || y >= y_max || z >= z_max {
+      return None;
+    }
+
+    return Some(((y * z_max + z) * x_max + x) as usize);
+  }
+
+  /**
+   * The inverse of [VoxelVolume::index]
+   */
+  pub fn coordinates(&self, index: usize) -> [u32; 3] {
+    let [x_max, _, z_max] = self.dimensions;
+    let index = index as u32;
+
+    return [
+      index % x_max,
+      index / x_max / z_max,
+      index / x_max % z_max,
+    ];
+  }
+
+  pub fn get(&self, x: u32, y: u32, z: u32) -> Option<&Voxel> {
+    return self.index(x, y, z).map(|i| &self.voxels[i]);
+  }
+
+  pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> Option<&mut Voxel> {
+    return self.index(x, y, z).map(move |i| &mut self.voxels[i]);
+  }
+
+  /**
+   * Sets a voxel; out of bounds coordinates are ignored
+   */
+  pub fn set(&mut self, x: u32, y: u32, z: u32, voxel: Voxel) {
+    if let Some(existing) = self.get_mut(x, y, z) {
+      *existing = voxel;
+    }
+  }
+
+  /**
+   * The world position of the centre of a voxel
+   */
+  pub fn position(&self, x: u32, y: u32, z: u32) -> Vector3<f32> {
+    return self.origin + Vector3::new(
+      x as f32,
+      y as f32,
+      z as f32,
+    ) * self.spacing;
+  }
+
+  /**
+   * Iterates all occupied voxels in storage order, with their coordinates
+   */
+  pub fn occupied_voxels(&self) -> impl Iterator<Item = ([u32; 3], &Voxel)> {
+    return self.voxels
+      .iter()
+      .enumerate()
+      .filter(|(_, voxel)| voxel.occupied)
+      .map(|(i, voxel)| (self.coordinates(i), voxel));
+  }
+}
+
+impl Default for VoxelVolume {
+  fn default() -> Self {
+    return Self::gradient(DEFAULT_VOLUME_DIMENSIONS, DEFAULT_VOXEL_SPACING);
+  }
+}
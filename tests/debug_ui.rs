@@ -0,0 +1,93 @@
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use std::path::PathBuf;
+
+use game_loop::winit::dpi::PhysicalSize;
+use rust_wgpu_3D_vision::{
+  debug_ui::{DebugSettings, DebugUi},
+  game_state::GameState,
+  render_pipeline_state::RenderMode,
+  render_state::RenderState,
+  transfer_function::{ControlPoint, TransferFunction, TransferFunctionPreset},
+};
+
+fn create_game_state() -> GameState {
+  return pollster::block_on(GameState::new_headless(PhysicalSize::new(320, 240)));
+}
+
+#[test]
+fn debug_panel_lays_out_without_changing_settings() {
+  // Only one device is made at a time
+  let settings = DebugSettings::from_game_state(&create_game_state());
+
+  let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(320, 240)));
+  let mut debug_ui = DebugUi::new(&render_state.device, render_state.config.format);
+  let mut edited_settings = settings.clone();
+  debug_ui.run([320, 240], &mut edited_settings);
+
+  // egui sizes windows in their first frame before showing them
+  let primitives = debug_ui.run([320, 240], &mut edited_settings);
+  assert!(primitives.is_empty() == false);
+  assert_eq!(edited_settings, settings);
+  assert!(debug_ui.wants_pointer_input() == false);
+}
+
+#[test]
+fn changed_debug_settings_are_applied_live() {
+  let mut game_state = create_game_state();
+  let previous = DebugSettings::from_game_state(&game_state);
+
+  let mut settings = previous.clone();
+  settings.volume_dimensions = [4, 2, 5];
+  settings.voxel_flicker = true;
+  settings.flicker.slab_thickness = 2;
+  settings.min_distance = 1.5;
+  settings.max_distance = Some(3.0);
+  settings.render_mode = RenderMode::Additive;
+  settings.transfer_function_preset = Some(TransferFunctionPreset::Hot);
+  settings.transfer_function = Some(TransferFunctionPreset::Hot.transfer_function());
+  settings.background_color = [1.0, 0.5, 0.0];
+  settings.show_hud = false;
+  settings.apply(&previous, &mut game_state);
+
+  assert_eq!(DebugSettings::from_game_state(&game_state), settings);
+  assert_eq!(game_state.volume().dimensions, [4, 2, 5]);
+
+  // Spacing alone keeps the volume, placed again around its new spacing
+  let previous = DebugSettings::from_game_state(&game_state);
+  let mut settings = previous.clone();
+  settings.voxel_spacing = 0.5;
+  settings.apply(&previous, &mut game_state);
+  assert_eq!(game_state.volume().dimensions, [4, 2, 5]);
+  assert_eq!(game_state.volume().spacing, 0.5);
+}
+
+#[test]
+fn edited_transfer_functions_replace_the_preset_and_can_be_saved() {
+  let mut game_state = create_game_state();
+  game_state.set_transfer_function_preset(Some(TransferFunctionPreset::Hot));
+  let previous = DebugSettings::from_game_state(&game_state);
+
+  let mut settings = previous.clone();
+  let transfer_function = settings.transfer_function.as_mut().unwrap();
+  transfer_function.set_point(1, ControlPoint::new(0.3, [0.0, 0.0, 1.0, 0.5]));
+  transfer_function.add_point(transfer_function.new_point());
+  let edited = transfer_function.clone();
+  settings.apply(&previous, &mut game_state);
+
+  assert_eq!(game_state.transfer_function(), Some(&edited));
+  assert_eq!(game_state.transfer_function_preset(), None);
+
+  let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("edited.tf");
+  game_state.save_transfer_function(&path);
+  assert_eq!(TransferFunction::load(&path).unwrap(), edited);
+}
+
+#[test]
+fn debug_panel_is_drawn_over_the_frame() {
+  let mut game_state = create_game_state();
+  game_state.set_show_debug_ui(true);
+  assert!(game_state.render());
+  assert!(game_state.render());
+}
@@ -0,0 +1,261 @@
+//! Tests for the order voxel flicker sweeps through a volume, and how fast.
+
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use std::time::Duration;
+
+use cgmath::{Vector3, Zero};
+use rust_wgpu_3D_vision::{
+  flicker::{FlickerConfig, PlaneBinning, PlaneSweep, SliceAxis, SweepDirection, VoxelOrder},
+  flicker_clock::{FlickerClock, MIN_VOLUMES_PER_SECOND},
+  flicker_scheduler::{hilbert_index, morton_index, ViewPoint, VoxelScheduler},
+  voxel_volume::VoxelVolume,
+};
+
+fn sweep_order(config: FlickerConfig, slab_count: u32, frames: usize) -> Vec<u32> {
+  let mut sweep = PlaneSweep::new(config);
+  let mut order = vec![sweep.current_slab(slab_count)];
+  for _ in 1..frames {
+    order.push(sweep.advance(slab_count));
+  }
+  return order;
+}
+
+#[test]
+fn sweep_directions() {
+  let mut config = FlickerConfig::default();
+  assert_eq!(sweep_order(config, 3, 6), vec![0, 1, 2, 0, 1, 2]);
+
+  config.direction = SweepDirection::Descending;
+  assert_eq!(sweep_order(config, 3, 6), vec![2, 1, 0, 2, 1, 0]);
+
+  config.direction = SweepDirection::PingPong;
+  assert_eq!(sweep_order(config, 3, 6), vec![0, 1, 2, 1, 0, 1]);
+  assert_eq!(sweep_order(config, 1, 3), vec![0, 0, 0]);
+}
+
+#[test]
+fn slabs_group_neighbouring_planes() {
+  let config = FlickerConfig {
+    order         : VoxelOrder::Planes,
+    axis          : SliceAxis::X,
+    direction     : SweepDirection::Ascending,
+    slab_thickness: 2,
+  };
+
+  assert_eq!(config.slab_count(5), 3);
+  assert_eq!(config.slab_planes(0, 5), 0..2);
+  assert_eq!(config.slab_planes(2, 5), 4..5);
+}
+
+#[test]
+fn jumping_to_a_slab_continues_the_sweep_from_it() {
+  let mut sweep = PlaneSweep::new(FlickerConfig {
+    direction: SweepDirection::PingPong,
+    ..FlickerConfig::default()
+  });
+
+  sweep.jump_to_slab(2, 4);
+  assert_eq!(sweep.current_slab(4), 2);
+  assert_eq!(sweep.advance(4), 3);
+  assert_eq!(sweep.advance(4), 2);
+}
+
+#[test]
+fn view_aligned_planes_start_nearest_the_eye() {
+  let volume = VoxelVolume::filled([3, 3, 3], 0.2);
+
+  let facing_z = PlaneBinning::along_view(&volume, Vector3::new(0.0, 0.0, 5.0), Vector3::zero());
+  assert_eq!(facing_z.plane_count, 3);
+  assert_eq!(facing_z.plane(volume.position(1, 1, 2)), 0);
+  assert_eq!(facing_z.plane(volume.position(1, 1, 0)), 2);
+
+  // Looking along a diagonal, each depth layer cuts across all three axes
+  let diagonal = PlaneBinning::along_view(&volume, Vector3::new(5.0, 5.0, 5.0), Vector3::zero());
+  assert!(diagonal.plane_count > 3);
+  assert_eq!(diagonal.plane(volume.position(2, 2, 2)), 0);
+  assert_eq!(diagonal.plane(volume.position(0, 0, 0)), diagonal.plane_count - 1);
+
+  let along_x = PlaneBinning::along_axis(SliceAxis::X, &volume);
+  assert_eq!(along_x.plane(volume.position(2, 0, 1)), 2);
+}
+
+fn default_view_point() -> ViewPoint {
+  return ViewPoint {
+    eye   : Vector3::new(0.0, 0.0, 5.0),
+    target: Vector3::zero(),
+  };
+}
+
+#[test]
+fn every_voxel_order_schedules_each_voxel_once() {
+  let volume = VoxelVolume::filled([3, 4, 5], 0.2);
+  let mut expected = volume.occupied_voxels().map(|(coordinates, _)| coordinates).collect::<Vec<_>>();
+  expected.sort();
+
+  let mut order = VoxelOrder::Planes;
+  loop {
+    let schedule = order.scheduler(SliceAxis::Y).schedule(&volume, default_view_point());
+
+    let mut scheduled = schedule.voxel_order.clone();
+    scheduled.sort();
+    assert_eq!(scheduled, expected, "{:?}", order);
+
+    let layer_count = match order {
+      VoxelOrder::Planes => 4,
+      _ => expected.len(),
+    };
+    assert_eq!(schedule.layer_ranges.len(), layer_count, "{:?}", order);
+
+    order = order.next();
+    if order == VoxelOrder::Planes {
+      break;
+    }
+  }
+}
+
+#[test]
+fn random_order_is_a_repeatable_shuffle() {
+  let volume = VoxelVolume::filled([4, 4, 4], 0.2);
+  let raster = VoxelOrder::Raster.scheduler(SliceAxis::Y).schedule(&volume, default_view_point());
+  let random = VoxelOrder::Random.scheduler(SliceAxis::Y).schedule(&volume, default_view_point());
+  let random_again = VoxelOrder::Random.scheduler(SliceAxis::Y).schedule(&volume, default_view_point());
+
+  assert_eq!(random.voxel_order, random_again.voxel_order);
+  assert!(random.voxel_order != raster.voxel_order);
+}
+
+#[test]
+fn space_filling_curves() {
+  assert_eq!(morton_index([1, 0, 0]), 1);
+  assert_eq!(morton_index([0, 1, 0]), 2);
+  assert_eq!(morton_index([0, 0, 1]), 4);
+  assert_eq!(morton_index([2, 0, 0]), 8);
+
+  // Consecutive cells of a Hilbert curve are always neighbours
+  let mut cells = (0..512u32)
+    .map(|i| [i % 8, (i / 8) % 8, i / 64])
+    .collect::<Vec<_>>();
+  cells.sort_by_key(|cell| hilbert_index(*cell, 3));
+  assert_eq!(hilbert_index(cells[511], 3), 511);
+  for pair in cells.windows(2) {
+    let distance = (0..3).map(|axis| pair[0][axis].abs_diff(pair[1][axis])).sum::<u32>();
+    assert_eq!(distance, 1, "{:?}", pair);
+  }
+}
+
+#[test]
+fn voxel_scheduler_draws_one_slab_at_a_time() {
+  let volume = VoxelVolume::filled([2, 2, 2], 0.2);
+  let mut scheduler = VoxelScheduler::new(FlickerConfig {
+    order         : VoxelOrder::Raster,
+    slab_thickness: 3,
+    ..FlickerConfig::default()
+  });
+  scheduler.schedule(&volume, default_view_point());
+
+  assert_eq!(scheduler.slab_count(), 3);
+  scheduler.show_slab(0);
+  assert_eq!(scheduler.instances_to_render(), 0..3);
+  scheduler.advance();
+  assert_eq!(scheduler.instances_to_render(), 3..6);
+  scheduler.advance();
+  assert_eq!(scheduler.instances_to_render(), 6..8);
+  scheduler.show_all();
+  assert_eq!(scheduler.instances_to_render(), 0..8);
+
+  // Only view-aligned planes follow the camera
+  assert!(scheduler.needs_rescheduling(default_view_point()) == false);
+  assert!(scheduler.set_config(FlickerConfig { axis: SliceAxis::View, ..FlickerConfig::default() }));
+  scheduler.schedule(&volume, default_view_point());
+  assert!(scheduler.needs_rescheduling(default_view_point()) == false);
+  assert!(scheduler.needs_rescheduling(ViewPoint {
+    eye: Vector3::new(1e-4, 0.0, 5.0),
+    ..default_view_point()
+  }) == false);
+  assert!(scheduler.needs_rescheduling(ViewPoint {
+    eye: Vector3::new(5.0, 0.0, 0.0),
+    ..default_view_point()
+  }));
+}
+
+#[test]
+fn depth_windows_cover_the_shown_planes() {
+  let volume = VoxelVolume::filled([3, 3, 3], 0.2);
+  let mut scheduler = VoxelScheduler::new(FlickerConfig::default());
+  scheduler.schedule(&volume, default_view_point());
+
+  let assert_window = |scheduler: &VoxelScheduler, start: f32, end: f32| {
+    let window = scheduler.depth_window(&volume, default_view_point());
+    assert_eq!(window.normal, Vector3::unit_y());
+    assert!((window.depths.start - start).abs() < 1e-5, "{:?}", window);
+    assert!((window.depths.end - end).abs() < 1e-5, "{:?}", window);
+  };
+
+  // Horizontal planes, the middle one's voxels centred at y = -0.1
+  scheduler.show_slab(1);
+  assert_window(&scheduler, -0.2, 0.0);
+  scheduler.show_all();
+  assert_window(&scheduler, -0.4, 0.2);
+
+  // Other orders cover the same share of the planes as of their layers
+  scheduler.set_config(FlickerConfig { order: VoxelOrder::Raster, ..FlickerConfig::default() });
+  scheduler.schedule(&volume, default_view_point());
+  scheduler.show_slab(9);
+  assert_window(&scheduler, -0.2, -0.2 + 0.2 / 9.0);
+}
+
+#[test]
+fn flicker_clock_shows_each_layer_for_its_share_of_a_volume() {
+  // 10 volumes of 5 layers a second need 50 frames a second
+  let mut clock = FlickerClock::new(10.0, 5);
+  assert_eq!(clock.required_frame_rate(), 50.0);
+  assert_eq!(clock.layer_duration(), Duration::from_millis(20));
+  assert!(clock.can_keep_up(60.0));
+  assert!(clock.can_keep_up(30.0) == false);
+
+  // A 100 Hz display shows each layer for two frames
+  let frame = Duration::from_millis(10);
+  let shown = (0..10).map(|_| clock.tick(frame)).collect::<Vec<_>>();
+  assert_eq!(shown.iter().filter(|shown| **shown).count(), 5);
+  assert!(clock.falling_behind() == false);
+
+  // A layer per frame at most, even when frames come too slowly
+  assert!(clock.tick(Duration::from_millis(70)));
+  assert!(clock.falling_behind());
+  assert_eq!(clock.skipped_layers(), 2);
+  assert!(clock.tick(Duration::from_millis(10)) == false);
+}
+
+#[test]
+fn volumes_per_second_has_a_lower_bound() {
+  let mut clock = FlickerClock::new(2.0, 3);
+  clock.set_volumes_per_second(clock.volumes_per_second() - 10.0);
+  assert_eq!(clock.volumes_per_second(), MIN_VOLUMES_PER_SECOND);
+  assert_eq!(FlickerClock::new(-5.0, 3).volumes_per_second(), MIN_VOLUMES_PER_SECOND);
+}
+
+#[test]
+fn vblank_lock_shows_a_layer_per_vblank() {
+  let mut clock = FlickerClock::new(2.0, 4);
+  clock.lock_to_vblank(Some(120.0));
+  assert_eq!(clock.volumes_per_second(), 30.0);
+  assert_eq!(clock.required_frame_rate(), 120.0);
+
+  // Every frame shows the next layer, however long it took
+  let vblank = Duration::from_secs_f32(1.0 / 120.0);
+  assert!((0..8).all(|_| clock.tick(vblank)));
+  assert!(clock.falling_behind() == false);
+
+  // A frame which missed a vblank showed its layer twice, but didn't skip
+  // the next one
+  assert!(clock.tick(vblank * 2));
+  assert!(clock.falling_behind());
+  assert_eq!(clock.repeated_layers(), 1);
+  assert_eq!(clock.skipped_layers(), 0);
+
+  // Unlocking goes back to the volumes per second
+  clock.lock_to_vblank(None);
+  assert_eq!(clock.volumes_per_second(), 2.0);
+}
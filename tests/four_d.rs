@@ -0,0 +1,329 @@
+//! Tests for 4D geometry and its projection into 3D.
+
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use cgmath::{InnerSpace, Vector3, Vector4};
+use rust_wgpu_3D_vision::voxel_volume::Voxel;
+use rust_wgpu_3D_vision::four_d::{
+  eye_pair::{Eye, EyePair4D},
+  hypershape::Hypershape,
+  hyperslice::{HyperSlice, WSweep},
+  hypervoxel_volume::HypervoxelVolume,
+  polytope::{Polytope, RegularPolytope},
+  projection::Projection4D,
+  retina_worker::RetinaWorker,
+  tesseract_model::TesseractModel,
+  transform::{RotationPlane, Transform4D},
+};
+
+#[test]
+fn tesseract_topology() {
+  let vertices = TesseractModel::unit_vertices();
+  let edges = TesseractModel::edges();
+  let faces = TesseractModel::faces();
+
+  assert_eq!(vertices.len(), 16);
+  assert_eq!(edges.len(), 32);
+  assert_eq!(faces.len(), 24);
+
+  for [a, b] in &edges {
+    assert_eq!((vertices[*a as usize] - vertices[*b as usize]).magnitude(), 2.0);
+  }
+
+  // Each corner of a face is joined to the next by an edge
+  for face in &faces {
+    for corner in 0..4 {
+      let a = face[corner];
+      let b = face[(corner + 1) % 4];
+      assert!(edges.contains(&[a.min(b), a.max(b)]), "{:?}", face);
+    }
+  }
+
+  // Every vertex meets four edges
+  for vertex in 0..16 {
+    assert_eq!(edges.iter().filter(|edge| edge.contains(&vertex)).count(), 4);
+  }
+}
+
+#[test]
+fn projections() {
+  let near = Vector4::new(1.0, -1.0, 1.0, 1.0);
+  let far = Vector4::new(1.0, -1.0, 1.0, -1.0);
+
+  assert_eq!(Projection4D::Orthographic.project(near), Vector3::new(1.0, -1.0, 1.0));
+  assert_eq!(Projection4D::Orthographic.project(far), Vector3::new(1.0, -1.0, 1.0));
+
+  let perspective = Projection4D::Perspective { viewer_w: 3.0 };
+  assert_eq!(perspective.project(near), Vector3::new(1.5, -1.5, 1.5));
+  assert_eq!(perspective.project(far), Vector3::new(0.75, -0.75, 0.75));
+  assert_eq!(perspective.project(Vector4::new(1.0, 0.0, 0.0, 0.0)), Vector3::new(1.0, 0.0, 0.0));
+}
+
+fn assert_close(actual: Vector4<f32>, expected: Vector4<f32>) {
+  assert!((actual - expected).magnitude() < 1e-5, "{:?} != {:?}", actual, expected);
+}
+
+#[test]
+fn rotations_turn_one_axis_towards_another() {
+  let quarter_turn = std::f32::consts::FRAC_PI_2;
+
+  let mut transform = Transform4D::default();
+  transform.rotate(RotationPlane::XW, quarter_turn);
+  assert_close(transform.apply(Vector4::unit_x()), Vector4::unit_w());
+  assert_close(transform.apply(Vector4::unit_w()), -Vector4::unit_x());
+  assert_close(transform.apply(Vector4::unit_y()), Vector4::unit_y());
+
+  // Rotations in the same plane add up
+  let mut halves = Transform4D::default();
+  halves.rotate(RotationPlane::YZ, quarter_turn / 2.0);
+  halves.rotate(RotationPlane::YZ, quarter_turn / 2.0);
+  assert_close(halves.apply(Vector4::unit_y()), Vector4::unit_z());
+
+  transform.translation = Vector4::new(0.0, 1.0, 0.0, 0.0);
+  assert_close(transform.apply(Vector4::unit_x()), Vector4::new(0.0, 1.0, 0.0, 1.0));
+}
+
+#[test]
+fn composed_rotations_stay_rigid() {
+  let mut transform = Transform4D::from_plane_angles([0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+  for step in 0..10_000 {
+    transform.rotate(RotationPlane::ALL[step % 6], 0.013);
+  }
+
+  let vertices = TesseractModel::unit_vertices();
+  for [a, b] in TesseractModel::edges() {
+    let edge = transform.apply(vertices[a as usize]) - transform.apply(vertices[b as usize]);
+    assert!((edge.magnitude() - 2.0).abs() < 1e-4);
+  }
+}
+
+#[test]
+fn hypervolume_indexing_round_trips() {
+  let volume = HypervoxelVolume::new([2, 3, 4, 5], 0.1);
+  assert_eq!(volume.voxel_count(), 120);
+
+  for i in 0..volume.voxel_count() {
+    let [x, y, z, w] = volume.coordinates(i);
+    assert_eq!(volume.index(x, y, z, w), Some(i));
+  }
+  assert_eq!(volume.index(0, 0, 0, 5), None);
+
+  // Voxel centres sit symmetrically around the origin
+  assert_close(volume.position(1, 2, 3, 4), -volume.position(0, 0, 0, 0));
+}
+
+#[test]
+fn hyperslices_pick_the_voxels_at_w() {
+  let volume = HypervoxelVolume::gradient([3, 3, 3, 3], 0.1);
+  let blue_at = |slice: &HyperSlice| slice.slice(&volume).get(1, 1, 1).unwrap().color[2];
+
+  let mut slice = HyperSlice::default();
+  assert_eq!(blue_at(&slice), volume.get(1, 1, 1, 1).unwrap().color[2]);
+
+  slice.w = 0.1;
+  assert_eq!(blue_at(&slice), volume.get(1, 1, 1, 2).unwrap().color[2]);
+
+  // Beyond the volume the slice is empty
+  slice.w = 0.2;
+  assert_eq!(slice.slice(&volume).occupied_voxels().count(), 0);
+  assert_eq!(HyperSlice::default().w_range(&volume), (-0.1, 0.1));
+}
+
+#[test]
+fn turned_hyperslices_cut_across_w() {
+  let volume = HypervoxelVolume::gradient([3, 3, 3, 3], 0.1);
+  let mut slice = HyperSlice::default();
+  slice.orientation.rotate(RotationPlane::XW, std::f32::consts::FRAC_PI_2);
+
+  // Turning the volume's +X onto +W brings its -W onto +X, so blue falls
+  // along the slice's x axis while red stays constant
+  let sliced = slice.slice(&volume);
+  let low = sliced.get(0, 1, 1).unwrap().color;
+  let high = sliced.get(2, 1, 1).unwrap().color;
+  assert!(high[2] < low[2]);
+  assert_eq!(high[0], low[0]);
+}
+
+#[test]
+fn w_sweeps_bounce_between_the_ends_of_the_volume() {
+  let volume = HypervoxelVolume::gradient([3, 3, 3, 3], 0.1);
+  let mut slice = HyperSlice::default();
+  let mut sweep = WSweep::new(0.04);
+
+  let mut highest = f32::MIN;
+  let mut lowest = f32::MAX;
+  for _ in 0..20 {
+    sweep.advance(&mut slice, &volume);
+    highest = highest.max(slice.w);
+    lowest = lowest.min(slice.w);
+  }
+
+  assert_eq!(highest, 0.1);
+  assert_eq!(lowest, -0.1);
+}
+
+#[test]
+fn eyes_see_the_hypervolume_from_either_side() {
+  let volume = HypervoxelVolume::default();
+  let orientation = Transform4D::default();
+
+  // Without separation both eyes are the same eye
+  let cyclops = EyePair4D { separation: 0.0, ..EyePair4D::default() };
+  let [left, right] = cyclops.retinas(&volume, &orientation);
+  assert!(left.occupied_voxels().count() > 0);
+  assert_eq!(left.voxels(), right.voxels());
+
+  let eye_pair = EyePair4D::default();
+  let left_eye = Eye::Left.position(&eye_pair);
+  let right_eye = Eye::Right.position(&eye_pair);
+  assert_close(left_eye, Vector4::new(-0.1, 0.0, 0.0, 1.0));
+  assert_close(right_eye, Vector4::new(0.1, 0.0, 0.0, 1.0));
+
+  // A hypervolume which is symmetric in X looks mirrored to the other eye
+  let mut lopsided = HypervoxelVolume::new(volume.dimensions, volume.spacing);
+  lopsided.set(3, 2, 2, 2, Voxel::filled([255; 4], 1.0));
+  lopsided.set(1, 2, 2, 2, Voxel::filled([255; 4], 1.0));
+  lopsided.set(4, 1, 2, 2, Voxel::filled([255; 4], 1.0));
+  lopsided.set(0, 1, 2, 2, Voxel::filled([255; 4], 1.0));
+  let [left, right] = eye_pair.retinas(&lopsided, &orientation);
+  let side = left.dimensions[0];
+  let mut mirrored_left: Vec<[u32; 3]> = left
+    .occupied_voxels()
+    .map(|([x, y, z], _)| [side - 1 - x, y, z])
+    .collect();
+  let mut right_occupied: Vec<[u32; 3]> = right.occupied_voxels().map(|(coordinates, _)| coordinates).collect();
+  assert!(!mirrored_left.is_empty());
+  mirrored_left.sort();
+  right_occupied.sort();
+  assert_eq!(mirrored_left, right_occupied);
+}
+
+#[test]
+fn eyes_converge_on_the_same_retina_point() {
+  let volume = HypervoxelVolume::default();
+  let eye_pair = EyePair4D::default();
+
+  // The centre hypervoxel is at the convergence distance from the eyes
+  let mut centre = HypervoxelVolume::new(volume.dimensions, volume.spacing);
+  centre.set(2, 2, 2, 2, Voxel::filled([255; 4], 1.0));
+
+  for retina in eye_pair.retinas(&centre, &Transform4D::default()) {
+    let middle = retina.dimensions[0] / 2;
+    assert!(retina.get(middle, middle, middle).unwrap().occupied);
+  }
+}
+
+#[test]
+fn retina_worker_marches_the_latest_turn_once() {
+  let volume = HypervoxelVolume::default();
+  let eye_pair = EyePair4D::default();
+  let mut worker = RetinaWorker::new(&volume);
+
+  let mut orientation = Transform4D::default();
+  orientation.rotate(RotationPlane::XW, 0.3);
+  worker.request(eye_pair, orientation);
+
+  let mut marched = None;
+  for _ in 0..500 {
+    marched = worker.poll();
+    if marched.is_some() {
+      break;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(10));
+  }
+  let [left, right] = marched.expect("The retinas were never marched");
+  let [expected_left, expected_right] = eye_pair.retinas(&volume, &orientation);
+  assert_eq!(left.voxels(), expected_left.voxels());
+  assert_eq!(right.voxels(), expected_right.voxels());
+
+  // Nothing is marched again until the orientation changes
+  worker.request(eye_pair, orientation);
+  std::thread::sleep(std::time::Duration::from_millis(50));
+  assert!(worker.poll().is_none());
+
+  // Retinas marched straight away replace any still being marched
+  orientation.rotate(RotationPlane::YW, 0.3);
+  worker.request(eye_pair, orientation);
+  let [left, _] = worker.march(eye_pair, Transform4D::default());
+  std::thread::sleep(std::time::Duration::from_millis(50));
+  assert!(worker.poll().is_none());
+  assert_eq!(left.voxels(), eye_pair.retinas(&volume, &Transform4D::default())[0].voxels());
+}
+
+#[test]
+fn regular_polytopes_have_the_right_parts() {
+  // Vertices, edges, cells and vertices per cell
+  let expected = [
+    (RegularPolytope::FiveCell, 5, 10, 5, 4),
+    (RegularPolytope::Tesseract, 16, 32, 8, 8),
+    (RegularPolytope::SixteenCell, 8, 24, 16, 4),
+    (RegularPolytope::TwentyFourCell, 24, 96, 24, 6),
+    (RegularPolytope::OneHundredTwentyCell, 600, 1200, 120, 20),
+    (RegularPolytope::SixHundredCell, 120, 720, 600, 4),
+  ];
+
+  for (regular, vertices, edges, cells, cell_vertices) in expected {
+    let polytope = regular.polytope();
+    assert_eq!(polytope.vertices.len(), vertices, "{:?}", regular);
+    assert_eq!(polytope.edges.len(), edges, "{:?}", regular);
+    assert_eq!(polytope.cells.len(), cells, "{:?}", regular);
+    for cell in &polytope.cells {
+      assert_eq!(cell.vertices.len(), cell_vertices, "{:?}", regular);
+    }
+
+    // Every vertex is the same distance from the centre
+    let radius = polytope.circumradius();
+    for vertex in &polytope.vertices {
+      assert!((vertex.magnitude() - radius).abs() < 1e-3 * radius, "{:?}", regular);
+    }
+  }
+}
+
+#[test]
+fn duoprisms_are_products_of_polygons() {
+  let duoprism = Polytope::duoprism(3, 5);
+  assert_eq!(duoprism.vertices.len(), 15);
+  assert_eq!(duoprism.edges.len(), 30);
+  assert_eq!(duoprism.cells.len(), 8);
+
+  // Triangular prisms around the pentagon, pentagonal prisms around the
+  // triangle
+  let mut cell_sizes: Vec<usize> = duoprism.cells.iter().map(|cell| cell.vertices.len()).collect();
+  cell_sizes.sort();
+  assert_eq!(cell_sizes, [6, 6, 6, 6, 6, 10, 10, 10]);
+
+  // The 4-4 duoprism is a tesseract
+  let square_duoprism = Polytope::duoprism(4, 4);
+  assert_eq!(square_duoprism.edges.len(), 32);
+  assert_eq!(square_duoprism.cells.len(), 8);
+}
+
+#[test]
+fn hypershapes_voxelize_inside_the_volume() {
+  let dimensions = [9; 4];
+  let spacing = 0.25;
+  let centre = |volume: &HypervoxelVolume| volume.get(4, 4, 4, 4).unwrap().occupied;
+  let corner = |volume: &HypervoxelVolume| volume.get(0, 0, 0, 0).unwrap().occupied;
+
+  let mut shape = Hypershape::default();
+  for _ in 0..9 {
+    let volume = shape.voxelize(dimensions, spacing);
+    assert!(centre(&volume), "{:?}", shape);
+    if shape != Hypershape::default() {
+      assert!(corner(&volume) == false, "{:?}", shape);
+    }
+    shape = shape.next();
+  }
+  assert_eq!(shape, Hypershape::default());
+
+  // The hypersphere touches the middle of each side but not the edges
+  let hypersphere = Hypershape::Hypersphere.voxelize(dimensions, spacing);
+  assert!(hypersphere.get(0, 4, 4, 4).unwrap().occupied);
+  assert!(hypersphere.get(0, 0, 4, 4).unwrap().occupied == false);
+
+  // The spherinder is round in XYZ but flat along W
+  let spherinder = Hypershape::Spherinder.voxelize(dimensions, spacing);
+  assert!(spherinder.get(4, 4, 4, 0).unwrap().occupied);
+  assert!(spherinder.get(0, 0, 4, 4).unwrap().occupied == false);
+}
@@ -0,0 +1,54 @@
+//! Tests for recording a flicker cycle as a PNG sequence.
+
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use std::path::PathBuf;
+
+use game_loop::winit::dpi::PhysicalSize;
+use rust_wgpu_3D_vision::game_state::GameState;
+
+fn create_game_state() -> GameState {
+  return pollster::block_on(GameState::new_headless(PhysicalSize::new(32, 32)));
+}
+
+fn recording_directory(name: &str) -> PathBuf {
+  let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+  let _ = std::fs::remove_dir_all(&directory);
+  return directory;
+}
+
+#[test]
+fn recording_saves_each_layer_once() {
+  let mut game_state = create_game_state();
+
+  // Fast enough that every frame after the first shows a new layer
+  game_state.set_volumes_per_second(1000.0);
+  let directory = recording_directory("recording_saves_each_layer_once");
+  game_state.record_flicker_cycle(directory.clone());
+
+  let layer_count = game_state.flicker_layer_count();
+  for _ in 0..(4 * layer_count) {
+    if game_state.is_recording() == false {
+      break;
+    }
+    assert!(game_state.render());
+  }
+  assert!(game_state.is_recording() == false);
+
+  let saved = std::fs::read_dir(&directory).unwrap().count() as u32;
+  assert_eq!(saved, layer_count);
+  for layer_index in 0..layer_count {
+    assert!(directory.join(format!("layer_{:04}.png", layer_index)).exists());
+  }
+}
+
+#[test]
+fn turning_flicker_off_cancels_the_recording() {
+  let mut game_state = create_game_state();
+  game_state.record_flicker_cycle(recording_directory("turning_flicker_off_cancels_the_recording"));
+  assert!(game_state.is_recording());
+
+  game_state.set_voxel_flicker(false);
+  assert!(game_state.is_recording() == false);
+}
@@ -0,0 +1,84 @@
+#![allow(clippy::needless_return)]
+
+use std::time::Duration;
+
+use game_loop::winit::dpi::PhysicalSize;
+use rust_wgpu_3D_vision::{
+  frame_stats::{FrameRecord, FrameStats},
+  game_state::GameState,
+};
+
+fn frame(index: u64, new_layer: bool, skipped_layers: u32) -> FrameRecord {
+  return FrameRecord {
+    presented_at             : Duration::from_millis(10 * index),
+    cpu_time                 : Duration::from_millis(1 + index % 2),
+    layer                    : Some(index as u32 % 4),
+    layer_count              : 4,
+    new_layer,
+    skipped_layers,
+    repeated_layers          : 0,
+    target_volumes_per_second: 25.0,
+  };
+}
+
+#[test]
+fn frame_stats_compare_achieved_and_target_volumes_per_second() {
+  // 100 frames a second, each showing a new layer of 4, is 25 volumes a second
+  let mut stats = FrameStats::new(16);
+  (0..11).for_each(|index| stats.record(frame(index, true, 0)));
+  assert_eq!(stats.frame_rate(), 100.0);
+  assert_eq!(stats.layers_per_second(), 100.0);
+  assert_eq!(stats.achieved_volumes_per_second(), 25.0);
+  assert_eq!(stats.target_volumes_per_second(), 25.0);
+  assert_eq!(stats.skipped_layers(), 0);
+  assert_eq!(stats.max_cpu_time(), Duration::from_millis(2));
+
+  // Showing every other frame's layer twice halves the volumes per second
+  let mut stats = FrameStats::new(16);
+  (0..11).for_each(|index| stats.record(frame(index, index % 2 == 0, index as u32 % 2)));
+  assert_eq!(stats.achieved_volumes_per_second(), 12.5);
+  assert_eq!(stats.skipped_layers(), 5);
+}
+
+#[test]
+fn frame_stats_keep_only_the_latest_frames() {
+  let mut stats = FrameStats::new(4);
+  (0..10).for_each(|index| stats.record(frame(index, true, 0)));
+  assert_eq!(stats.frame_count(), 10);
+  assert_eq!(stats.records().count(), 4);
+  assert_eq!(stats.span(), Duration::from_millis(30));
+
+  let mut csv = Vec::new();
+  stats.write_csv(&mut csv).unwrap();
+  let csv = String::from_utf8(csv).unwrap();
+  let lines = csv.lines().collect::<Vec<_>>();
+  assert_eq!(lines.len(), 5);
+  assert!(lines[0].starts_with("frame,presented_at_ms,cpu_time_ms,layer"));
+  assert_eq!(lines[1], "6,60.000,1.000,2,4,1,0,0,25");
+}
+
+#[test]
+fn rendered_frames_are_recorded() {
+  let mut game_state = pollster::block_on(GameState::new_headless(PhysicalSize::new(32, 32)));
+  game_state.set_voxel_flicker(true);
+  for _ in 0..3 {
+    assert!(game_state.render());
+  }
+
+  let stats = game_state.frame_stats();
+  assert_eq!(stats.frame_count(), 3);
+  assert!(stats.records().all(|record| record.layer.is_some()));
+  assert!(stats.records().all(|record| record.layer_count == game_state.flicker_layer_count()));
+}
+
+#[test]
+fn frames_record_the_layer_drawn_with_flicker_off() {
+  let mut game_state = pollster::block_on(GameState::new_headless(PhysicalSize::new(32, 32)));
+  game_state.show_flicker_layer(1);
+  assert!(game_state.render());
+  game_state.show_all_volume_planes();
+  assert!(game_state.render());
+
+  let layers = game_state.frame_stats().records().map(|record| record.layer).collect::<Vec<_>>();
+  assert_eq!(layers, vec![Some(1), None]);
+}
@@ -0,0 +1,328 @@
+//! Golden-image regression tests for the renderer.
+//!
+//! Each test renders a known scene offscreen through the software fallback
+//! adapter and compares it against a reference PNG in `tests/golden/`.
+//! Pixels may differ by up to [CHANNEL_TOLERANCE] per channel; anything more
+//! fails the test and writes the actual frame and a diff image to
+//! `target/golden_diff/`.
+//!
+//! Run with `UPDATE_GOLDEN=1 cargo test --test golden_images` to regenerate
+//! the reference images after an intentional rendering change.
+
+#![allow(clippy::needless_return)]
+
+use std::{fs::File, path::{Path, PathBuf}};
+
+use cgmath::{Vector3, Zero};
+use game_loop::winit::dpi::PhysicalSize;
+use rust_wgpu_3D_vision::{
+  camera::stereo_camera::StereoMode,
+  four_d::{
+    hypershape::Hypershape,
+    hyperslice::HyperSlice,
+    polytope::RegularPolytope,
+    projection::Projection4D,
+    transform::RotationPlane,
+  },
+  frame_capture::CapturedFrame,
+  game_state::GameState,
+  instance::Instance,
+  render_pipeline_state::{RenderBackend, RenderMode},
+  transfer_function::TransferFunctionPreset,
+  voxel_volume::{ScalarWindow, VoxelVolume, DEFAULT_VOLUME_DIMENSIONS, DEFAULT_VOXEL_SPACING},
+};
+
+const FRAME_WIDTH : u32 = 128;
+const FRAME_HEIGHT: u32 = 128;
+
+/// The largest difference allowed in any colour channel of a pixel
+const CHANNEL_TOLERANCE: u8 = 8;
+
+/// The fraction of pixels allowed to exceed [CHANNEL_TOLERANCE], to absorb
+/// rasterisation differences along triangle edges between software adapters
+const MAX_MISMATCHED_FRACTION: f32 = 0.001;
+
+fn create_game_state() -> GameState {
+  return pollster::block_on(GameState::new_headless(
+    PhysicalSize::new(FRAME_WIDTH, FRAME_HEIGHT),
+  ));
+}
+
+fn render(game_state: &mut GameState) -> CapturedFrame {
+  game_state.update();
+  return game_state.capture_frame().expect("Failed to render frame");
+}
+
+fn golden_path(name: &str) -> PathBuf {
+  return Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("golden")
+    .join(format!("{}.png", name));
+}
+
+fn diff_dir() -> PathBuf {
+  return Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("target")
+    .join("golden_diff");
+}
+
+fn load_png(path: &Path) -> CapturedFrame {
+  let file = File::open(path).unwrap_or_else(|e| {
+    panic!("Missing golden image {:?} ({}); run with UPDATE_GOLDEN=1 to create it", path, e)
+  });
+  let decoder = png::Decoder::new(file);
+  let mut reader = decoder.read_info().unwrap();
+  let mut rgba = vec![0; reader.output_buffer_size()];
+  let info = reader.next_frame(&mut rgba).unwrap();
+  assert_eq!(info.color_type, png::ColorType::Rgba, "Golden images must be RGBA");
+  rgba.truncate(info.buffer_size());
+
+  return CapturedFrame {
+    width : info.width,
+    height: info.height,
+    rgba,
+  };
+}
+
+/// Compares `actual` against the golden image `name`, panicking with a
+/// summary and writing diff output if they differ beyond the tolerance
+fn assert_matches_golden(name: &str, actual: &CapturedFrame) {
+  let path = golden_path(name);
+
+  if std::env::var_os("UPDATE_GOLDEN").is_some() {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    actual.save_png(&path).unwrap();
+    return;
+  }
+
+  let expected = load_png(&path);
+  assert_eq!(
+    (expected.width, expected.height),
+    (actual.width, actual.height),
+    "Golden image {} has a different size",
+    name,
+  );
+
+  let mut diff = CapturedFrame {
+    width : actual.width,
+    height: actual.height,
+    rgba  : Vec::with_capacity(actual.rgba.len()),
+  };
+  let mut mismatched_pixels = 0;
+  let mut max_channel_difference = 0;
+
+  for (expected_pixel, actual_pixel) in expected.rgba.chunks_exact(4).zip(actual.rgba.chunks_exact(4)) {
+    let channel_difference = expected_pixel.iter()
+      .zip(actual_pixel)
+      .map(|(e, a)| e.abs_diff(*a))
+      .max()
+      .unwrap();
+    max_channel_difference = max_channel_difference.max(channel_difference);
+
+    if channel_difference > CHANNEL_TOLERANCE {
+      mismatched_pixels += 1;
+      diff.rgba.extend_from_slice(&[255, 0, 255, 255]);
+    } else {
+      // Matching pixels are dimmed so the mismatches stand out
+      diff.rgba.extend_from_slice(&[actual_pixel[0] / 4, actual_pixel[1] / 4, actual_pixel[2] / 4, 255]);
+    }
+  }
+
+  let pixel_count = (actual.width * actual.height) as f32;
+  if mismatched_pixels as f32 / pixel_count > MAX_MISMATCHED_FRACTION {
+    std::fs::create_dir_all(diff_dir()).unwrap();
+    let actual_path = diff_dir().join(format!("{}_actual.png", name));
+    let diff_path = diff_dir().join(format!("{}_diff.png", name));
+    actual.save_png(&actual_path).unwrap();
+    diff.save_png(&diff_path).unwrap();
+
+    panic!(
+      "{} differs from its golden image: {} of {} pixels mismatched (max channel difference {}); see {:?} and {:?}",
+      name,
+      mismatched_pixels,
+      pixel_count,
+      max_channel_difference,
+      actual_path,
+      diff_path,
+    );
+  }
+}
+
+#[test]
+fn single_cube() {
+  let mut game_state = create_game_state();
+  game_state.set_instances(vec![Instance::at(Vector3::zero())]);
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("single_cube", &frame);
+}
+
+#[test]
+fn grid() {
+  let mut game_state = create_game_state();
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("grid", &frame);
+}
+
+#[test]
+fn flicker_layers() {
+  let mut game_state = create_game_state();
+  let layer_count = game_state.flicker_layer_count();
+  assert!(layer_count > 0);
+
+  for layer_index in 0..layer_count {
+    game_state.show_flicker_layer(layer_index);
+    let frame = render(&mut game_state);
+    assert_matches_golden(&format!("flicker_layer_{}", layer_index), &frame);
+  }
+}
+
+#[test]
+fn tesseract_projections() {
+  let mut game_state = create_game_state();
+  game_state.set_show_tesseract(true);
+
+  game_state.set_tesseract_projection(Projection4D::default());
+  let frame = render(&mut game_state);
+  assert_matches_golden("tesseract_perspective", &frame);
+
+  game_state.set_tesseract_projection(Projection4D::Orthographic);
+  let frame = render(&mut game_state);
+  assert_matches_golden("tesseract_orthographic", &frame);
+}
+
+#[test]
+fn tesseract_rotated_through_w() {
+  let mut game_state = create_game_state();
+  game_state.set_show_tesseract(true);
+  game_state.rotate_tesseract(RotationPlane::XW, 0.6);
+  game_state.rotate_tesseract(RotationPlane::YZ, 0.3);
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("tesseract_rotated_through_w", &frame);
+}
+
+#[test]
+fn hyperslices() {
+  let mut game_state = create_game_state();
+  game_state.set_hyperslicing(true);
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("hyperslice_w_0", &frame);
+
+  let mut slice = HyperSlice::default();
+  slice.orientation.rotate(RotationPlane::XW, 0.8);
+  slice.orientation.rotate(RotationPlane::YW, 0.5);
+  game_state.set_hyperslice(slice);
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("hyperslice_turned", &frame);
+}
+
+#[test]
+fn binocular_retinas() {
+  let mut game_state = create_game_state();
+  game_state.set_binocular(true);
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("binocular_retinas", &frame);
+}
+
+#[test]
+fn polytope_hyperslice() {
+  let mut game_state = create_game_state();
+  game_state.set_hyperslicing(true);
+  game_state.set_hypershape(Hypershape::Regular(RegularPolytope::TwentyFourCell));
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("twenty_four_cell_w_0", &frame);
+}
+
+#[test]
+fn stereo_modes() {
+  let mut game_state = create_game_state();
+  game_state.show_all_volume_planes();
+
+  let modes = [
+    (StereoMode::SideBySide, "stereo_side_by_side"),
+    (StereoMode::OverUnder, "stereo_over_under"),
+    (StereoMode::Anaglyph, "stereo_anaglyph"),
+  ];
+  for (mode, name) in modes {
+    game_state.set_stereo_mode(mode);
+    let frame = render(&mut game_state);
+    assert_matches_golden(name, &frame);
+  }
+}
+
+#[test]
+fn weighted_blended_transparency() {
+  let mut game_state = create_game_state();
+  game_state.set_render_mode(RenderMode::WeightedBlended);
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("weighted_blended_grid", &frame);
+
+  // Each eye is composited in its own colours
+  game_state.set_stereo_mode(StereoMode::Anaglyph);
+  let frame = render(&mut game_state);
+  assert_matches_golden("weighted_blended_anaglyph", &frame);
+}
+
+#[test]
+fn additive_and_maximum_intensity() {
+  let mut game_state = create_game_state();
+  game_state.show_all_volume_planes();
+
+  game_state.set_render_mode(RenderMode::Additive);
+  let frame = render(&mut game_state);
+  assert_matches_golden("additive_grid", &frame);
+
+  game_state.set_render_mode(RenderMode::MaximumIntensity);
+  let frame = render(&mut game_state);
+  assert_matches_golden("maximum_intensity_grid", &frame);
+}
+
+#[test]
+fn ray_marched_volume() {
+  let mut game_state = create_game_state();
+  game_state.set_render_backend(RenderBackend::RayMarched);
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("ray_marched_grid", &frame);
+
+  // Flicker layers clip the rays to the same plane the cubes would show
+  game_state.show_flicker_layer(1);
+  let frame = render(&mut game_state);
+  assert_matches_golden("ray_marched_flicker_layer_1", &frame);
+}
+
+#[test]
+fn transfer_functions() {
+  let mut game_state = create_game_state();
+
+  // A scalar volume whose values rise through storage order
+  let voxel_count = DEFAULT_VOLUME_DIMENSIONS.iter().product::<u32>();
+  let values = (0..voxel_count).map(|i| i as f32).collect();
+  let mut volume = VoxelVolume::from_values(DEFAULT_VOLUME_DIMENSIONS, DEFAULT_VOXEL_SPACING, values);
+  volume.apply_window(&ScalarWindow::spanning(&volume));
+  game_state.set_volume(volume);
+  game_state.set_transfer_function_preset(Some(TransferFunctionPreset::Hot));
+  game_state.show_all_volume_planes();
+
+  let frame = render(&mut game_state);
+  assert_matches_golden("hot_transfer_function", &frame);
+
+  // The ray marcher looks its colours up from the same texture
+  game_state.set_render_backend(RenderBackend::RayMarched);
+  let frame = render(&mut game_state);
+  assert_matches_golden("ray_marched_hot_transfer_function", &frame);
+}
@@ -0,0 +1,70 @@
+#![allow(clippy::needless_return)]
+
+use game_loop::winit::dpi::PhysicalSize;
+use rust_wgpu_3D_vision::{
+  game_state::GameState,
+  hud::{self, glyph, layout_text, text_size, GLYPH_HEIGHT, HUD_SCALE},
+  render_pipeline_state::RenderMode,
+};
+
+#[test]
+fn hud_text_is_laid_out_a_quad_per_font_pixel() {
+  let lines = vec!["I.".to_string(), "i".to_string()];
+
+  // Two glyphs and the space between them, by two lines and their spacing
+  assert_eq!(text_size(&lines), [11 * HUD_SCALE, 17 * HUD_SCALE]);
+  assert_eq!(glyph('i'), glyph('I'));
+
+  let lit_pixels = |character| glyph(character).iter().map(|row| row.count_ones()).sum::<u32>();
+  let quads = 1 + 2 * lit_pixels('I') + lit_pixels('.');
+  let vertices = layout_text(&lines, [640, 480]);
+  assert_eq!(vertices.len() as u32, quads * 6);
+  assert!(vertices.iter().all(|vertex| vertex.position.iter().all(|p| (-1.0..=1.0).contains(p))));
+
+  assert!(layout_text(&[], [640, 480]).is_empty());
+  assert_eq!(text_size(&["".to_string()]), [0, 0]);
+  assert_eq!(GLYPH_HEIGHT as usize, glyph('?').len());
+}
+
+#[test]
+fn hud_labels_split_variant_names_into_words() {
+  assert_eq!(hud::label(RenderMode::WeightedBlended), "WEIGHTED BLENDED");
+  assert_eq!(hud::label(RenderMode::Opaque), "OPAQUE");
+}
+
+#[test]
+fn hud_shows_the_flicker_layer() {
+  let mut game_state = pollster::block_on(GameState::new_headless(PhysicalSize::new(64, 64)));
+  game_state.set_voxel_flicker(true);
+  game_state.show_flicker_layer(1);
+  assert!(game_state.render());
+
+  let lines = game_state.hud_lines();
+  let layer_line = format!("LAYER 2 / {}", game_state.flicker_layer_count());
+  assert!(lines.contains(&layer_line), "{:?}", lines);
+  assert!(lines.iter().any(|line| line.starts_with("FPS")));
+
+  game_state.set_show_hud(false);
+  assert!(game_state.render());
+}
+
+#[test]
+fn hud_shows_the_layer_drawn_with_flicker_off() {
+  let mut game_state = pollster::block_on(GameState::new_headless(PhysicalSize::new(64, 64)));
+  let layer_count = game_state.flicker_layer_count();
+  game_state.set_voxel_flicker(true);
+  game_state.show_flicker_layer(1);
+  game_state.set_voxel_flicker(false);
+  assert!(game_state.render());
+
+  // The flicker stops on its layer
+  let lines = game_state.hud_lines();
+  assert_eq!(game_state.shown_flicker_layer(), Some(1));
+  assert!(lines.iter().any(|line| line.starts_with("FLICKER OFF")), "{:?}", lines);
+  assert!(lines.contains(&format!("LAYER 2 / {}", layer_count)), "{:?}", lines);
+
+  game_state.show_all_volume_planes();
+  assert!(game_state.render());
+  assert_eq!(game_state.shown_flicker_layer(), None);
+  assert!(game_state.hud_lines().contains(&format!("LAYER ALL / {}", layer_count)));
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use rust_wgpu_3D_vision::presentation::{choose_present_mode, parse_present_mode, PresentationConfig};
+use wgpu::PresentMode;
+
+#[test]
+fn present_modes_are_parsed_by_name() {
+  assert_eq!(parse_present_mode("fifo"), Some(PresentMode::Fifo));
+  assert_eq!(parse_present_mode("Mailbox"), Some(PresentMode::Mailbox));
+  assert_eq!(parse_present_mode("immediate"), Some(PresentMode::Immediate));
+  assert_eq!(parse_present_mode("vsync"), None);
+}
+
+#[test]
+fn unsupported_present_modes_fall_back_to_fifo() {
+  let supported = [PresentMode::Fifo, PresentMode::Immediate];
+  assert_eq!(choose_present_mode(PresentMode::Immediate, &supported), PresentMode::Immediate);
+  assert_eq!(choose_present_mode(PresentMode::Mailbox, &supported), PresentMode::Fifo);
+}
+
+#[test]
+fn flicker_locks_to_vblanks_only_with_fifo() {
+  let presentation = PresentationConfig {
+    present_mode          : PresentMode::Fifo,
+    lock_flicker_to_vblank: true,
+  };
+  assert!(presentation.locks_flicker_to_vblank(PresentMode::Fifo));
+  assert!(presentation.locks_flicker_to_vblank(PresentMode::Immediate) == false);
+  assert!(PresentationConfig::default().locks_flicker_to_vblank(PresentMode::Fifo) == false);
+}
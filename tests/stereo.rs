@@ -0,0 +1,59 @@
+//! Tests for the stereo eye pair around the orbit camera.
+
+#![allow(clippy::needless_return)]
+
+use game_loop::winit::dpi::PhysicalSize;
+use glam::{Vec3, Vec4};
+use rust_wgpu_3D_vision::camera::{
+  orbit_camera::OrbitCamera,
+  stereo_camera::{StereoCamera, StereoEye, StereoMode},
+};
+
+fn screen_x(stereo_camera: &StereoCamera, camera: &OrbitCamera, eye: StereoEye, point: Vec3) -> f32 {
+  let clip = stereo_camera.build_view_projection_matrix(camera, eye) * Vec4::new(point.x, point.y, point.z, 1.0);
+  return clip.x / clip.w;
+}
+
+#[test]
+fn eyes_line_up_at_the_convergence_distance() {
+  let camera = OrbitCamera::new(2.0, 0.3, 0.7, Vec3::ZERO, 1.0);
+  let stereo_camera = StereoCamera::default();
+  let cyclops = StereoCamera { ipd: 0.0, ..stereo_camera };
+  let camera_eye = cyclops.eye_position(&camera, StereoEye::Left);
+
+  let left = stereo_camera.eye_position(&camera, StereoEye::Left);
+  let right = stereo_camera.eye_position(&camera, StereoEye::Right);
+  assert!((left.distance(right) - stereo_camera.ipd).abs() < 1e-5);
+  assert!(((left + right) / 2.0).distance(camera_eye) < 1e-5);
+
+  // The target is at the convergence distance, so it's in the middle of both
+  // views
+  for eye in StereoEye::ALL {
+    assert!(screen_x(&stereo_camera, &camera, eye, Vec3::ZERO).abs() < 1e-5);
+  }
+
+  // Nearer points are further right for the left eye than the right eye,
+  // and further points the other way around
+  let towards_camera = camera_eye.normalize();
+  let near = towards_camera * 0.5;
+  let far = -towards_camera * 0.5;
+  assert!(screen_x(&stereo_camera, &camera, StereoEye::Left, near) > screen_x(&stereo_camera, &camera, StereoEye::Right, near));
+  assert!(screen_x(&stereo_camera, &camera, StereoEye::Left, far) < screen_x(&stereo_camera, &camera, StereoEye::Right, far));
+}
+
+#[test]
+fn stereo_modes_split_the_frame() {
+  let size = PhysicalSize::new(200, 100);
+
+  assert_eq!(StereoMode::SideBySide.viewport(StereoEye::Left, size), [0.0, 0.0, 100.0, 100.0]);
+  assert_eq!(StereoMode::SideBySide.viewport(StereoEye::Right, size), [100.0, 0.0, 100.0, 100.0]);
+  assert_eq!(StereoMode::OverUnder.viewport(StereoEye::Left, size), [0.0, 0.0, 200.0, 50.0]);
+  assert_eq!(StereoMode::OverUnder.viewport(StereoEye::Right, size), [0.0, 50.0, 200.0, 50.0]);
+  assert_eq!(StereoMode::Anaglyph.viewport(StereoEye::Right, size), [0.0, 0.0, 200.0, 100.0]);
+
+  let mut mode = StereoMode::Off;
+  for _ in 0..4 {
+    mode = mode.next();
+  }
+  assert_eq!(mode, StereoMode::Off);
+}
@@ -0,0 +1,103 @@
+//! Tests for mapping scalar values to colours and opacities.
+
+#![allow(clippy::needless_return)]
+
+use rust_wgpu_3D_vision::transfer_function::{
+  ControlPoint,
+  TransferFunction,
+  TransferFunctionError,
+  TransferFunctionPreset,
+};
+
+fn ramp() -> TransferFunction {
+  return TransferFunction::new(vec![
+    ControlPoint::new(1.0, [1.0, 1.0, 1.0, 1.0]),
+    ControlPoint::new(0.5, [1.0, 0.0, 0.0, 0.5]),
+    ControlPoint::new(0.0, [0.0, 0.0, 0.0, 0.0]),
+  ]);
+}
+
+#[test]
+fn control_points_are_interpolated_in_order() {
+  let transfer_function = ramp();
+  assert_eq!(transfer_function.points()[0].position, 0.0);
+
+  assert_eq!(transfer_function.sample(0.25), [0.5, 0.0, 0.0, 0.25]);
+  assert_eq!(transfer_function.sample(0.75), [1.0, 0.5, 0.5, 0.75]);
+
+  // Positions outside the points take the nearest point's colour
+  assert_eq!(transfer_function.sample(-1.0), [0.0; 4]);
+  assert_eq!(transfer_function.sample(2.0), [1.0; 4]);
+}
+
+#[test]
+fn control_points_are_edited_in_order() {
+  let mut transfer_function = ramp();
+  transfer_function.add_point(ControlPoint::new(0.25, [0.0, 1.0, 0.0, 1.0]));
+  assert_eq!(transfer_function.points()[1].position, 0.25);
+
+  // Moving a point past its neighbour reorders them
+  transfer_function.set_point(1, ControlPoint::new(0.75, [0.0, 1.0, 0.0, 1.0]));
+  let positions = transfer_function.points().iter().map(|point| point.position).collect::<Vec<_>>();
+  assert_eq!(positions, vec![0.0, 0.5, 0.75, 1.0]);
+
+  assert_eq!(transfer_function.remove_point(2).position, 0.75);
+  assert_eq!(transfer_function, ramp());
+
+  // New points split the widest gap, keeping the colours as they were
+  let mut transfer_function = TransferFunction::new(vec![ControlPoint::new(0.25, [1.0, 0.0, 0.0, 0.5])]);
+  assert_eq!(transfer_function.new_point(), ControlPoint::new(0.625, [1.0, 0.0, 0.0, 0.5]));
+  transfer_function.add_point(ControlPoint::new(0.75, [0.0, 0.0, 1.0, 1.0]));
+  assert_eq!(transfer_function.new_point(), ControlPoint::new(0.5, [0.5, 0.0, 0.5, 0.75]));
+}
+
+#[test]
+fn lookup_tables_span_the_value_range() {
+  let lookup_table = ramp().lookup_table(5);
+  assert_eq!(lookup_table.len(), 5);
+  assert_eq!(lookup_table[0], [0, 0, 0, 0]);
+  assert_eq!(lookup_table[2], [255, 0, 0, 128]);
+  assert_eq!(lookup_table[4], [255, 255, 255, 255]);
+}
+
+#[test]
+fn text_format_round_trips() {
+  let transfer_function = ramp();
+  let text = transfer_function.to_string();
+  assert_eq!(TransferFunction::parse(&text).unwrap(), transfer_function);
+
+  let commented = "# a comment\n\n0 0 0 0 0  # black\n1 1 1 1 1\n";
+  assert_eq!(TransferFunction::parse(commented).unwrap().points().len(), 2);
+
+  let path = std::env::temp_dir().join("rust_wgpu_3D_vision_ramp.tf");
+  transfer_function.save(&path).unwrap();
+  assert_eq!(TransferFunction::load(&path).unwrap(), transfer_function);
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn malformed_text_is_rejected() {
+  for text in ["", "# only a comment", "0 1 1 1", "0 1 one 1 1"] {
+    assert!(
+      matches!(TransferFunction::parse(text), Err(TransferFunctionError::Format(_))),
+      "{:?}",
+      text,
+    );
+  }
+}
+
+#[test]
+fn presets_cycle_through_distinct_functions() {
+  let mut preset = TransferFunctionPreset::Greyscale;
+  for expected in TransferFunctionPreset::ALL {
+    assert_eq!(preset, expected);
+    preset = preset.next();
+  }
+  assert_eq!(preset, TransferFunctionPreset::Greyscale);
+
+  for (i, a) in TransferFunctionPreset::ALL.iter().enumerate() {
+    for b in &TransferFunctionPreset::ALL[i + 1..] {
+      assert_ne!(a.transfer_function(), b.transfer_function());
+    }
+  }
+}
@@ -0,0 +1,168 @@
+//! Tests for the volume file loaders, using small files built in memory.
+
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison)]
+
+use rust_wgpu_3D_vision::{
+  volume_loaders::{nrrd, raw::{self, RawVolumeDescription}, vox, VolumeLoadError},
+  voxel_volume::{ScalarWindow, ScalarWindowSettings},
+};
+
+fn chunk(id: &[u8; 4], content: &[u8], children: &[u8]) -> Vec<u8> {
+  let mut bytes = id.to_vec();
+  bytes.extend_from_slice(&(content.len() as u32).to_le_bytes());
+  bytes.extend_from_slice(&(children.len() as u32).to_le_bytes());
+  bytes.extend_from_slice(content);
+  bytes.extend_from_slice(children);
+  return bytes;
+}
+
+fn vox_file(children: &[Vec<u8>]) -> Vec<u8> {
+  let mut bytes = b"VOX ".to_vec();
+  bytes.extend_from_slice(&150u32.to_le_bytes());
+  bytes.extend(chunk(b"MAIN", &[], &children.concat()));
+  return bytes;
+}
+
+fn size_chunk(x: u32, y: u32, z: u32) -> Vec<u8> {
+  let content = [x.to_le_bytes(), y.to_le_bytes(), z.to_le_bytes()].concat();
+  return chunk(b"SIZE", &content, &[]);
+}
+
+fn xyzi_chunk(voxels: &[[u8; 4]]) -> Vec<u8> {
+  let mut content = (voxels.len() as u32).to_le_bytes().to_vec();
+  content.extend(voxels.concat());
+  return chunk(b"XYZI", &content, &[]);
+}
+
+#[test]
+fn vox_maps_z_up_and_palette_colours() {
+  let mut palette = vec![0; 256 * 4];
+  // Colour index 3 is the chunk's third entry
+  palette[8..12].copy_from_slice(&[10, 20, 30, 40]);
+
+  let bytes = vox_file(&[
+    size_chunk(2, 3, 4),
+    xyzi_chunk(&[[1, 0, 3, 3]]),
+    chunk(b"RGBA", &palette, &[]),
+  ]);
+
+  let volumes = vox::parse_vox(&bytes).unwrap();
+  assert_eq!(volumes.len(), 1);
+
+  let volume = &volumes[0];
+  assert_eq!(volume.dimensions, [2, 4, 3]);
+  assert_eq!(volume.occupied_voxels().count(), 1);
+
+  // MagicaVoxel z becomes y, and y is flipped into z
+  let voxel = volume.get(1, 3, 2).unwrap();
+  assert!(voxel.occupied);
+  assert_eq!(voxel.color, [10, 20, 30, 40]);
+  assert_eq!(voxel.value, 3.0);
+}
+
+#[test]
+fn vox_reads_multiple_models_with_default_palette() {
+  let bytes = vox_file(&[
+    size_chunk(1, 1, 1),
+    xyzi_chunk(&[[0, 0, 0, 1]]),
+    size_chunk(2, 2, 2),
+    xyzi_chunk(&[[0, 0, 0, 255], [1, 1, 1, 255]]),
+  ]);
+
+  let volumes = vox::parse_vox(&bytes).unwrap();
+  assert_eq!(volumes.len(), 2);
+  assert_eq!(volumes[0].get(0, 0, 0).unwrap().color, [255, 255, 255, 255]);
+  assert_eq!(volumes[1].occupied_voxels().count(), 2);
+  assert_eq!(volumes[1].get(1, 1, 0).unwrap().color, [0x11, 0x11, 0x11, 255]);
+}
+
+#[test]
+fn vox_rejects_bad_files() {
+  assert!(matches!(vox::parse_vox(b"NOPE0000"), Err(VolumeLoadError::Format(_))));
+
+  let mut truncated = vox_file(&[size_chunk(1, 1, 1), xyzi_chunk(&[[0, 0, 0, 1]])]);
+  truncated.truncate(truncated.len() - 2);
+  assert!(matches!(vox::parse_vox(&truncated), Err(VolumeLoadError::Format(_))));
+
+  let oversized = vox_file(&[size_chunk(1, u32::MAX, 1), xyzi_chunk(&[])]);
+  assert!(matches!(vox::parse_vox(&oversized), Err(VolumeLoadError::Format(_))));
+
+  // A voxel past the model's size along y, which is flipped
+  let outside = vox_file(&[size_chunk(2, 2, 2), xyzi_chunk(&[[0, 2, 0, 1]])]);
+  assert!(matches!(vox::parse_vox(&outside), Err(VolumeLoadError::Format(_))));
+}
+
+#[test]
+fn nrrd_reads_attached_big_endian_samples() {
+  let mut bytes = b"NRRD0004\n# A comment\ntype: ushort\ndimension: 3\nsizes: 2 1 2\nendian: big\nencoding: raw\n\n".to_vec();
+  for sample in [0u16, 100, 200, 1000] {
+    bytes.extend_from_slice(&sample.to_be_bytes());
+  }
+
+  let volume = nrrd::parse_nrrd(&bytes, &ScalarWindowSettings::default()).unwrap();
+
+  // The slice axis (z) becomes y
+  assert_eq!(volume.dimensions, [2, 2, 1]);
+  assert_eq!(volume.get(1, 1, 0).unwrap().value, 1000.0);
+
+  // The default window skips the background and spans the value range
+  assert!(volume.get(0, 0, 0).unwrap().occupied == false);
+  assert_eq!(volume.get(1, 1, 0).unwrap().color, [255, 255, 255, 255]);
+  assert_eq!(volume.occupied_voxels().count(), 3);
+}
+
+#[test]
+fn nrrd_rejects_unsupported_encodings() {
+  let bytes = b"NRRD0004\ntype: uchar\ndimension: 3\nsizes: 1 1 1\nencoding: gzip\n\n\0";
+  assert!(matches!(nrrd::parse_nrrd(bytes, &ScalarWindowSettings::default()), Err(VolumeLoadError::Format(_))));
+}
+
+#[test]
+fn raw_windowing_and_threshold() {
+  let description = RawVolumeDescription::parse("2x2x1", "u8").unwrap();
+  let mut volume = raw::parse_raw(&[0, 50, 100, 150], &description, &ScalarWindowSettings::default()).unwrap();
+
+  volume.apply_window(&ScalarWindow {
+    min      : 50.0,
+    max      : 150.0,
+    threshold: 50.0,
+  });
+
+  let brightnesses = volume.occupied_voxels()
+    .map(|(_, voxel)| voxel.color[0])
+    .collect::<Vec<_>>();
+  assert_eq!(brightnesses, vec![128, 255]);
+
+  assert!(RawVolumeDescription::parse("2x2", "u8").is_err());
+  assert!(RawVolumeDescription::parse("2x2x2", "i64").is_err());
+  assert!(raw::parse_raw(&[0, 1, 2], &description, &ScalarWindowSettings::default()).is_err());
+}
+
+#[test]
+fn raw_loads_with_a_chosen_window() {
+  let description = RawVolumeDescription::parse("2x2x1", "u8").unwrap();
+  let window = ScalarWindowSettings {
+    range    : ScalarWindowSettings::parse_range("50:150"),
+    threshold: Some(75.0),
+  };
+  let volume = raw::parse_raw(&[0, 50, 100, 150], &description, &window).unwrap();
+
+  let brightnesses = volume.occupied_voxels()
+    .map(|(_, voxel)| voxel.color[0])
+    .collect::<Vec<_>>();
+  assert_eq!(brightnesses, vec![128, 255]);
+
+  assert_eq!(ScalarWindowSettings::parse_range(" -10 : 2.5"), Some([-10.0, 2.5]));
+  assert_eq!(ScalarWindowSettings::parse_range("150:50"), None);
+  assert_eq!(ScalarWindowSettings::parse_range("150"), None);
+}
+
+#[test]
+fn constant_volumes_keep_every_voxel() {
+  let description = RawVolumeDescription::parse("2x2x1", "u8").unwrap();
+  let volume = raw::parse_raw(&[7, 7, 7, 7], &description, &ScalarWindowSettings::default()).unwrap();
+
+  assert_eq!(volume.occupied_voxels().count(), 4);
+  assert!(volume.occupied_voxels().all(|(_, voxel)| voxel.color == [255, 255, 255, 255]));
+}